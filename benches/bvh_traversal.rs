@@ -0,0 +1,50 @@
+//! Compares the time taken to build each built-in scene's hierarchies with each build method,
+//! and to find the closest hit of camera rays through them. Run with `cargo bench`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use ray_tracing::*;
+use ray_tracing::bvh::{count_traversal_steps, reset_traversal_steps, traversal_steps};
+use ray_tracing::sampler::seed_random_stream;
+use ray_tracing::scenes::{build_scene, SCENES};
+
+const SIZE: usize = 128;        // Width and height of the grid of rays traced through each scene
+const SAMPLES: u64 = 4;         // Number of rays traced through each point of the grid
+
+/// Finds the closest hit of every camera ray through a scene, returning the time taken, the
+/// fraction of rays which hit anything and the mean number of boxes and objects tested per ray
+fn traverse(scene: &Scene) -> (Duration, f64, f64) {
+    let rays = SIZE * SIZE * SAMPLES as usize;
+    let mut hits = 0;
+    reset_traversal_steps();
+    let start = Instant::now();
+    for pixel in 0..SIZE * SIZE {
+        for sample in 0..SAMPLES {
+            seed_random_stream(1, pixel as u64, sample);
+            let u = ((pixel % SIZE) as f64 + random_double()) / SIZE as f64;
+            let v = ((pixel / SIZE) as f64 + random_double()) / SIZE as f64;
+            if black_box(scene.world.hit(&scene.camera.get_ray(u, v), 0.001, INFINITY)).is_some() {
+                hits += 1;
+            }
+        }
+    }
+    (start.elapsed(), hits as f64 / rays as f64, traversal_steps() as f64 / rays as f64)
+}
+
+fn main() {
+    let _counting = count_traversal_steps();
+    println!("{:<20} {:<12} {:>10} {:>12} {:>8} {:>12}", "Scene", "Method", "Build", "Traversal", "Hits", "Steps/ray");
+    for (name, _) in SCENES {
+        for (method_name, method) in [("random-axis", BVHBuildMethod::RandomAxis), ("sah", BVHBuildMethod::SAH)] {
+            seed_random(1);
+            let start = Instant::now();
+            let scene = build_scene(name, 1., method).unwrap();
+            let build_time = start.elapsed();
+            let (traversal_time, hit_fraction, steps) = traverse(&scene);
+            println!(
+                "{name:<20} {method_name:<12} {:>8.1}ms {:>10.1}ms {:>7.1}% {steps:>12.1}",
+                build_time.as_secs_f64() * 1e3, traversal_time.as_secs_f64() * 1e3, hit_fraction * 100.
+            );
+        }
+    }
+}
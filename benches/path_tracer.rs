@@ -0,0 +1,49 @@
+//! Compares the time taken and mean radiance of the recursive and iterative path tracers on
+//! each built-in scene. Run with `cargo bench`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use ray_tracing::*;
+use ray_tracing::sampler::seed_random_stream;
+use ray_tracing::scenes::{build_scene, SCENES};
+
+const SIZE: usize = 32;         // Width and height of the grid of rays traced through each scene
+const SAMPLES: u64 = 16;        // Number of rays traced through each point of the grid
+const MAX_DEPTH: i32 = 50;      // Depth the recursive tracer cuts paths off at
+const MIN_DEPTH: i32 = 3;       // Number of bounces before Russian roulette can end a path
+
+/// Traces every ray through a scene with a function, returning the time taken and the mean
+/// radiance of the rays
+fn trace<F: Fn(&Scene, &Ray) -> Vec3>(scene: &Scene, colour: F) -> (Duration, f64) {
+    let start = Instant::now();
+    let mut sum = Vec3::zero();
+    for pixel in 0..SIZE * SIZE {
+        for sample in 0..SAMPLES {
+            seed_random_stream(1, pixel as u64, sample);
+            let u = ((pixel % SIZE) as f64 + random_double()) / SIZE as f64;
+            let v = ((pixel / SIZE) as f64 + random_double()) / SIZE as f64;
+            sum += black_box(colour(scene, &scene.camera.get_ray(u, v)));
+        }
+    }
+    let mean = (sum.x + sum.y + sum.z) / (3 * SIZE * SIZE) as f64 / SAMPLES as f64;
+    (start.elapsed(), mean)
+}
+
+fn main() {
+    println!("{:<20} {:>14} {:>14} {:>8} {:>10} {:>10}", "Scene", "Recursive", "Iterative", "Speedup", "Rec. mean", "Iter. mean");
+    for (name, _) in SCENES {
+        seed_random(1);
+        let scene = build_scene(name, 1., BVHBuildMethod::SAH).unwrap();
+        let (recursive_time, recursive_mean) = trace(&scene, |scene, ray| {
+            ray_colour(ray, &scene.background, &scene.world, &scene.lights, MAX_DEPTH)
+        });
+        let (iterative_time, iterative_mean) = trace(&scene, |scene, ray| {
+            path_colour(ray, &scene.background, &scene.world, &scene.lights, MIN_DEPTH, None)
+        });
+        println!(
+            "{name:<20} {:>12.1}ms {:>12.1}ms {:>7.2}x {recursive_mean:>10.4} {iterative_mean:>10.4}",
+            recursive_time.as_secs_f64() * 1e3, iterative_time.as_secs_f64() * 1e3,
+            recursive_time.as_secs_f64() / iterative_time.as_secs_f64()
+        );
+    }
+}
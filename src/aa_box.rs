@@ -16,6 +16,7 @@ pub struct AABox {
 }
 
 impl AABox {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(box_min: Vec3, box_max: Vec3, material: Arc<Material>) -> Hittable {
         let mut sides = HittableList::new();
 
@@ -34,10 +35,22 @@ impl AABox {
 
 impl HittableTrait for AABox {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        self.sides.hit(ray, t_min, t_max)
+        // The box is a single object, so the IDs its list gives each side are discarded
+        self.sides.hit(ray, t_min, t_max).map(|mut hit_record| {
+            hit_record.object_id = 0;
+            hit_record
+        })
     }
 
     fn bounding_box(&self, _: f64, _: f64) -> Option<AABB> {
         Some(AABB::new(self.box_min, self.box_max))
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        self.sides.collect_materials(materials);
+    }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<Hittable>>) {
+        self.sides.collect_lights(lights);
+    }
 }
\ No newline at end of file
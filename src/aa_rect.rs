@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use crate::{random_range, INFINITY};
 use crate::aabb::AABB;
 use crate::hit_record::HitRecord;
 use crate::hittable::{Hittable, HittableTrait};
@@ -17,6 +18,7 @@ pub struct XYRect {
 }
 
 impl XYRect {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(x0: f64, x1: f64, y0: f64, y1: f64, k: f64, material: Arc<Material>) -> Hittable {
         Hittable::XYRect(XYRect { x0, x1, y0, y1, k, material })
     }
@@ -49,6 +51,37 @@ impl HittableTrait for XYRect {
             Vec3::new(self.x1, self.y1, self.k + 0.0001),
         ))
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.push(Arc::clone(&self.material));
+    }
+
+    fn is_light(&self) -> bool {
+        matches!(self.material.as_ref(), Material::DiffuseLight(_))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let area = (self.x1 - self.x0) * (self.y1 - self.y0);
+        rect_pdf_value(self.hit(&Ray::new(*origin, *direction, 0.), 0.001, INFINITY), direction, area)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        Vec3::new(random_range(self.x0, self.x1), random_range(self.y0, self.y1), self.k) - *origin
+    }
+
+    fn area(&self) -> f64 {
+        (self.x1 - self.x0) * (self.y1 - self.y0)
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        let point = Vec3::new(random_range(self.x0, self.x1), random_range(self.y0, self.y1), self.k);
+        let u = (point.x - self.x0) / (self.x1 - self.x0);
+        let v = (point.y - self.y0) / (self.y1 - self.y0);
+        let mut hit_record = HitRecord::new(point, &self.material, u, v, 0.);
+        hit_record.normal = Vec3::new(0., 0., 1.);
+        hit_record.front_face = true;
+        Some(hit_record)
+    }
 }
 
 #[derive(Debug)]
@@ -62,6 +95,7 @@ pub struct XZRect {
 }
 
 impl XZRect {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(x0: f64, x1: f64, z0: f64, z1: f64, k: f64, material: Arc<Material>) -> Hittable {
         Hittable::XZRect(XZRect { x0, x1, z0, z1, k, material })
     }
@@ -94,6 +128,37 @@ impl HittableTrait for XZRect {
             Vec3::new(self.x1, self.k + 0.0001, self.z1),
         ))
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.push(Arc::clone(&self.material));
+    }
+
+    fn is_light(&self) -> bool {
+        matches!(self.material.as_ref(), Material::DiffuseLight(_))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let area = (self.x1 - self.x0) * (self.z1 - self.z0);
+        rect_pdf_value(self.hit(&Ray::new(*origin, *direction, 0.), 0.001, INFINITY), direction, area)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        Vec3::new(random_range(self.x0, self.x1), self.k, random_range(self.z0, self.z1)) - *origin
+    }
+
+    fn area(&self) -> f64 {
+        (self.x1 - self.x0) * (self.z1 - self.z0)
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        let point = Vec3::new(random_range(self.x0, self.x1), self.k, random_range(self.z0, self.z1));
+        let u = (point.x - self.x0) / (self.x1 - self.x0);
+        let v = (point.z - self.z0) / (self.z1 - self.z0);
+        let mut hit_record = HitRecord::new(point, &self.material, u, v, 0.);
+        hit_record.normal = Vec3::new(0., 1., 0.);
+        hit_record.front_face = true;
+        Some(hit_record)
+    }
 }
 
 #[derive(Debug)]
@@ -107,6 +172,7 @@ pub struct YZRect {
 }
 
 impl YZRect {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(y0: f64, y1: f64, z0: f64, z1: f64, k: f64, material: Arc<Material>) -> Hittable {
         Hittable::YZRect(YZRect { y0, y1, z0, z1, k, material })
     }
@@ -139,4 +205,47 @@ impl HittableTrait for YZRect {
             Vec3::new(self.k + 0.0001, self.y1, self.z1),
         ))
     }
-}
\ No newline at end of file
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.push(Arc::clone(&self.material));
+    }
+
+    fn is_light(&self) -> bool {
+        matches!(self.material.as_ref(), Material::DiffuseLight(_))
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let area = (self.y1 - self.y0) * (self.z1 - self.z0);
+        rect_pdf_value(self.hit(&Ray::new(*origin, *direction, 0.), 0.001, INFINITY), direction, area)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        Vec3::new(self.k, random_range(self.y0, self.y1), random_range(self.z0, self.z1)) - *origin
+    }
+
+    fn area(&self) -> f64 {
+        (self.y1 - self.y0) * (self.z1 - self.z0)
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        let point = Vec3::new(self.k, random_range(self.y0, self.y1), random_range(self.z0, self.z1));
+        let u = (point.y - self.y0) / (self.y1 - self.y0);
+        let v = (point.z - self.z0) / (self.z1 - self.z0);
+        let mut hit_record = HitRecord::new(point, &self.material, u, v, 0.);
+        hit_record.normal = Vec3::new(1., 0., 0.);
+        hit_record.front_face = true;
+        Some(hit_record)
+    }
+}
+/// Converts the area density of sampling a point uniformly on a rectangle, or any other flat
+/// surface, into a density per unit solid angle, given the hit of a direction with the surface
+pub(crate) fn rect_pdf_value(hit_record: Option<HitRecord>, direction: &Vec3, area: f64) -> f64 {
+    match hit_record {
+        None => 0.,
+        Some(hit_record) => {
+            let distance_squared = hit_record.t * hit_record.t * direction.length_squared();
+            let cosine = f64::abs(Vec3::dot(direction, &hit_record.normal) / direction.length());
+            distance_squared / (cosine * area)
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 /// Object representing an Axis-Aligned Bounding Box for grouping Hittable objects
 pub struct AABB {
     pub minimum: Vec3,
@@ -14,6 +14,17 @@ impl AABB {
         AABB { minimum, maximum }
     }
 
+    /// Gets the point at the centre of the box
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.minimum + self.maximum)
+    }
+
+    /// Gets the total area of the box's faces
+    pub fn surface_area(&self) -> f64 {
+        let extent = self.maximum - self.minimum;
+        2. * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
     pub fn surrounding_box(box0: &AABB, box1: &AABB) -> AABB {
         let minimum = Vec3::new(
             f64::min(box0.minimum.x, box1.minimum.x),
@@ -31,7 +42,7 @@ impl AABB {
     }
 
     /// Returns if a ray passed through the bounding box
-    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+    pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
         for a in 0..3 {
             // Calculates t0 and t1 for current dimension
             let inv_d = 1. / ray.direction[a];
@@ -40,14 +51,12 @@ impl AABB {
 
             // Swaps values if the inverse direction is negative
             if inv_d < 0. {
-                let temp = t0;
-                t0 = t1.clone();
-                t1 = temp;
+                std::mem::swap(&mut t0, &mut t1);
             }
 
-            // Returns false if the ray doesn't overlap with the current dimension
-            let t_min = if t0 > t_min { t0 } else { t_min };
-            let t_max = if t1 < t_max { t1 } else { t_max };
+            // Narrows the overlap to the current dimension, returning false if there is none
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
             if t_max <= t_min {
                 return false
             }
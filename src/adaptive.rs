@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+const DARK_LUMINANCE: f64 = 0.05;   // Luminance below which noise is judged in absolute rather than relative terms
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Settings for adaptive sampling, which stops taking samples through a pixel once its noise
+/// falls below a threshold, up to the render's number of samples per pixel
+pub struct AdaptiveSettings {
+    pub noise_threshold: f64,   // Standard error of a pixel's mean luminance, relative to the mean, at which it stops
+    pub min_samples: u32,       // Number of samples taken through every pixel before its noise is trusted
+}
+
+impl Default for AdaptiveSettings {
+    fn default() -> Self {
+        AdaptiveSettings { noise_threshold: 0.01, min_samples: 16 }
+    }
+}
+
+impl AdaptiveSettings {
+    /// Checks whether a pixel has converged, so that it needs no more samples
+    pub fn converged(&self, variance: &PixelVariance, samples: u32) -> bool {
+        // At least two samples are needed to estimate the variance at all
+        samples >= u32::max(self.min_samples, 2)
+            && variance.standard_error(samples) <= self.noise_threshold * f64::max(variance.mean, DARK_LUMINANCE)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// Running mean and variance of the luminance of a pixel's samples, updated with Welford's
+/// algorithm so that it stays accurate over many samples
+pub struct PixelVariance {
+    pub mean: f64,      // Mean luminance of the pixel's samples
+    pub m2: f64,        // Sum of squared differences of each sample's luminance from the mean
+}
+
+impl PixelVariance {
+    /// Adds a sample's luminance, given the number of samples including it
+    pub fn add(&mut self, luminance: f64, samples: u32) {
+        let delta = luminance - self.mean;
+        self.mean += delta / samples as f64;
+        self.m2 += delta * (luminance - self.mean);
+    }
+
+    /// Gets the unbiased variance of the samples' luminance
+    pub fn variance(&self, samples: u32) -> f64 {
+        if samples < 2 {
+            0.
+        } else {
+            self.m2 / (samples - 1) as f64
+        }
+    }
+
+    /// Gets the standard error of the mean luminance, which shrinks as more samples are taken
+    pub fn standard_error(&self, samples: u32) -> f64 {
+        f64::sqrt(self.variance(samples) / u32::max(samples, 1) as f64)
+    }
+}
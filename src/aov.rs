@@ -0,0 +1,130 @@
+use crate::INFINITY;
+use crate::hittable::HittableTrait;
+use crate::material::MaterialTrait;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Arbitrary output variable, storing information about the first surface seen through each
+/// pixel alongside the rendered image
+pub enum Aov {
+    Albedo,         // Base colour of the surface's material
+    Normal,         // Shading normal of the surface, facing towards the camera
+    Depth,          // Distance from the camera to the surface
+    Position,       // World space position of the surface
+    Uv,             // Texture coordinates of the surface
+    ObjectId,       // Index of the object hit, or -1 where nothing was hit
+    MaterialId,     // Index of the surface's material, or -1 where nothing was hit
+}
+
+impl Aov {
+    /// Every AOV, in the order they are written
+    pub const ALL: [Aov; 7] = [
+        Aov::Albedo, Aov::Normal, Aov::Depth, Aov::Position, Aov::Uv, Aov::ObjectId, Aov::MaterialId
+    ];
+
+    /// Gets the name of the AOV, used for its file name or EXR layer
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Position => "position",
+            Aov::Uv => "uv",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+        }
+    }
+
+    /// Gets the names of the AOV's channels
+    pub fn channels(&self) -> &'static [&'static str] {
+        match self {
+            Aov::Albedo => &["R", "G", "B"],
+            Aov::Normal | Aov::Position => &["X", "Y", "Z"],
+            Aov::Depth => &["Z"],
+            Aov::Uv => &["U", "V"],
+            Aov::ObjectId | Aov::MaterialId => &["id"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// First hits of the samples taken through a pixel. Continuous values are summed over every
+/// sample, while IDs can't be averaged so are taken from the pixel's first sample
+pub struct AovPixel {
+    pub albedo: Vec3,               // Sum of the albedo of each sample's first hit
+    pub normal: Vec3,               // Sum of the shading normal of each sample's first hit
+    pub depth: f64,                 // Sum of the distance to each sample's first hit
+    pub position: Vec3,             // Sum of the position of each sample's first hit
+    pub uv: Vec3,                   // Sum of the UV coordinates of each sample's first hit, in x and y
+    pub object_id: Option<u32>,     // ID of the object hit by the first sample
+    pub material_id: Option<u32>,   // ID of the material hit by the first sample
+}
+
+impl AovPixel {
+    /// Constructs a new AovPixel with no samples
+    pub fn new() -> Self {
+        AovPixel {
+            albedo: Vec3::zero(),
+            normal: Vec3::zero(),
+            depth: 0.,
+            position: Vec3::zero(),
+            uv: Vec3::zero(),
+            object_id: None,
+            material_id: None,
+        }
+    }
+
+    /// Finds the first hit of a camera ray, returning it as a pixel with a single sample.
+    /// Rays which miss every object leave each value at zero
+    pub fn trace(scene: &Scene, ray: &Ray) -> Self {
+        match scene.world.hit(ray, 0.001, INFINITY) {
+            Some(hit) => AovPixel {
+                albedo: hit.material.albedo(&hit),
+                normal: hit.normal,
+                depth: hit.t * ray.direction.length(),
+                position: hit.point,
+                uv: Vec3::new(hit.u, hit.v, 0.),
+                object_id: Some(hit.object_id),
+                material_id: scene.material_id(&hit.material),
+            },
+            None => AovPixel::new()
+        }
+    }
+
+    /// Adds a sample to the pixel, taking its IDs if it is the pixel's first sample
+    pub fn add(&mut self, sample: &AovPixel, first: bool) {
+        self.albedo += sample.albedo;
+        self.normal += sample.normal;
+        self.depth += sample.depth;
+        self.position += sample.position;
+        self.uv += sample.uv;
+        if first {
+            self.object_id = sample.object_id;
+            self.material_id = sample.material_id;
+        }
+    }
+
+    /// Gets the values of an AOV's channels, averaged over the pixel's number of samples
+    pub fn values(&self, aov: Aov, samples: u32) -> [f32; 3] {
+        let n = u32::max(samples, 1) as f64;
+        let vector = |v: Vec3| [(v.x / n) as f32, (v.y / n) as f32, (v.z / n) as f32];
+        let id = |id: Option<u32>| [id.map_or(-1., |id| id as f32), 0., 0.];
+        match aov {
+            Aov::Albedo => vector(self.albedo),
+            Aov::Normal => vector(self.normal),
+            Aov::Depth => [(self.depth / n) as f32, 0., 0.],
+            Aov::Position => vector(self.position),
+            Aov::Uv => vector(self.uv),
+            Aov::ObjectId => id(self.object_id),
+            Aov::MaterialId => id(self.material_id),
+        }
+    }
+}
+
+impl Default for AovPixel {
+    fn default() -> Self {
+        AovPixel::new()
+    }
+}
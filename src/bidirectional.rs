@@ -0,0 +1,401 @@
+use crate::{random_double, random_int, INFINITY, PI};
+use crate::hittable::HittableTrait;
+use crate::hittable_list::HittableList;
+use crate::material::MaterialTrait;
+use crate::ray::Ray;
+use crate::scatter_record::{Lobe, ScatterRecord};
+use crate::scene::Scene;
+use crate::vec3::Vec3;
+
+const MAX_SURVIVAL: f64 = 0.95;     // Highest chance of a subpath surviving Russian roulette, so that every subpath ends
+
+/// Light carried by a light subpath connected straight to the camera, which can land in any
+/// pixel rather than the one whose sample traced it
+#[derive(Debug, Clone, Copy)]
+pub struct LightSplat {
+    pub pixel: usize,       // Index of the pixel the light lands in, counting rows from the top
+    pub radiance: Vec3,     // Weighted radiance the light adds to the pixel, per light path traced
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Kind of point a vertex of a subpath lies at
+enum VertexKind {
+    Camera,                 // Point on the camera's lens
+    Light,                  // Point chosen on the surface of a light
+    Surface(Option<Lobe>),  // Point where a ray hit an object, with its lobe unless it can't be connected to
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Point along a camera or light subpath, with the densities of both directions of sampling
+/// it as needed by the multiple importance sampling weights
+struct Vertex {
+    kind: VertexKind,
+    point: Vec3,            // Position of the vertex
+    normal: Option<Vec3>,   // Normal of the surface at the vertex, or None within a medium
+    beta: Vec3,             // Throughput of the subpath up to the vertex, divided by its density
+    emitted: Vec3,          // Light emitted by the surface at the vertex
+    light_pdf: f64,         // Density of a light subpath starting at the vertex, or zero if it can't
+    delta: bool,            // Whether the vertex scatters specularly, so can't be connected to
+    pdf_fwd: f64,           // Density, per unit area, of the vertex being sampled by its own subpath
+    pdf_rev: f64,           // Density, per unit area, of the vertex being sampled from the other direction
+}
+
+impl Vertex {
+    /// Constructs a vertex with no lobe or densities
+    fn new(kind: VertexKind, point: Vec3, normal: Option<Vec3>, beta: Vec3) -> Self {
+        Vertex {
+            kind, point, normal, beta, emitted: Vec3::zero(), light_pdf: 0., delta: false, pdf_fwd: 0., pdf_rev: 0.
+        }
+    }
+
+    /// Gets the lobe the vertex scatters light with, if it can be connected to
+    fn lobe(&self) -> Option<Lobe> {
+        match self.kind {
+            VertexKind::Surface(lobe) if !self.delta => lobe,
+            _ => None,
+        }
+    }
+}
+
+/// Converts a density per unit solid angle of sampling a direction from one vertex into a
+/// density per unit area at the vertex it reaches
+fn convert_density(pdf: f64, from: &Vertex, to: &Vertex) -> f64 {
+    let offset = to.point - from.point;
+    let distance_squared = offset.length_squared();
+    if distance_squared == 0. {
+        return 0.;
+    }
+    let cos_theta = to.normal.map_or(1., |normal| Vec3::dot(&normal, &offset.unit()).abs());
+    pdf * cos_theta / distance_squared
+}
+
+/// Gets the density per unit solid angle of a light emitting towards a direction, which is
+/// cosine weighted about its normal on either side
+fn emission_pdf(normal: &Vec3, direction: &Vec3) -> f64 {
+    Vec3::dot(normal, &direction.unit()).abs() / (2. * PI)
+}
+
+/// Scene and image that a sample's subpaths are traced through and connected in
+struct Tracer<'a> {
+    scene: &'a Scene,
+    lights: &'a HittableList,
+    width: usize,
+    height: usize,
+    min_depth: i32,
+}
+
+impl Tracer<'_> {
+    /// Gets the area of the camera's image, in the units of positions taken by `get_ray`, which
+    /// the image's pixels cover
+    fn image_area(&self) -> f64 {
+        (self.width * self.height) as f64 / ((self.width - 1) * (self.height - 1)) as f64
+    }
+
+    /// Gets the density per unit area of a vertex sampling the next vertex of a subpath
+    fn pdf(&self, vertex: &Vertex, next: &Vertex) -> f64 {
+        let direction = next.point - vertex.point;
+        let pdf = match vertex.kind {
+            VertexKind::Camera => self.scene.camera.image_density(&direction) / self.image_area(),
+            VertexKind::Light => vertex.normal.map_or(0., |normal| emission_pdf(&normal, &direction)),
+            VertexKind::Surface(_) => vertex.lobe().map_or(0., |lobe| lobe.pdf(&direction)),
+        };
+        convert_density(pdf, vertex, next)
+    }
+
+    /// Gets the density per unit area of a light emitting from a vertex towards another
+    fn pdf_light(&self, vertex: &Vertex, next: &Vertex) -> f64 {
+        let pdf = vertex.normal.map_or(0., |normal| emission_pdf(&normal, &(next.point - vertex.point)));
+        convert_density(pdf, vertex, next)
+    }
+
+    /// Chooses a random point on a random light, returning it as the first vertex of a light
+    /// subpath
+    fn sample_light(&self) -> Option<Vertex> {
+        let count = self.lights.objects.len();
+        if count == 0 {
+            return None;
+        }
+        let light = &self.lights.objects[random_int(0, count as i32 - 1) as usize];
+        let hit_record = light.sample_surface()?;
+        let pdf = 1. / (count as f64 * light.area());
+        let emitted = hit_record.material.emitted(hit_record.u, hit_record.v, &hit_record.point);
+
+        let mut vertex = Vertex::new(VertexKind::Light, hit_record.point, Some(hit_record.normal), emitted / pdf);
+        vertex.emitted = emitted;
+        vertex.light_pdf = pdf;
+        vertex.pdf_fwd = pdf;
+        Some(vertex)
+    }
+
+    /// Checks whether nothing lies between two points
+    fn visible(&self, from: &Vec3, to: &Vec3, time: f64) -> bool {
+        let offset = *to - *from;
+        let distance = offset.length();
+        let ray = Ray::new(*from, offset / distance, time);
+        self.scene.world.hit(&ray, 0.001, distance - 0.001).is_none()
+    }
+
+    /// Follows a subpath along a ray from its last vertex, given the throughput and density
+    /// of sampling the ray, adding a vertex at each hit until it leaves the scene, is absorbed,
+    /// ends by Russian roulette or reaches a number of vertices. Returns the throughput of the
+    /// subpath and the direction it left in if it leaves the scene
+    fn random_walk(
+        &self, path: &mut Vec<Vertex>, mut ray: Ray, mut beta: Vec3, mut pdf_fwd: f64, max_vertices: Option<usize>
+    ) -> Option<(Vec3, Vec3)> {
+        let scale = f64::max(f64::max(beta.x, f64::max(beta.y, beta.z)), f64::MIN_POSITIVE);
+        let mut depth = 0;
+
+        while max_vertices.is_none_or(|max_vertices| path.len() < max_vertices) {
+            let hit_record = match self.scene.world.hit(&ray, 0.001, INFINITY) {
+                Some(hit_record) => hit_record,
+                None => return Some((beta, ray.direction)),
+            };
+            let scatter_record = hit_record.material.scatter(&ray, &hit_record);
+            let (lobe, delta) = match &scatter_record {
+                Some(ScatterRecord::Specular { .. }) => (None, true),
+                Some(ScatterRecord::Diffuse(lobe)) => (Some(*lobe), false),
+                None => (None, false),
+            };
+
+            // Media scatter light the same way in every direction, with no surface to face
+            let normal = match lobe {
+                Some(Lobe::Uniform { .. }) => None,
+                _ => Some(hit_record.normal),
+            };
+            let mut vertex = Vertex::new(VertexKind::Surface(lobe), hit_record.point, normal, beta);
+            vertex.emitted = hit_record.material.emitted(hit_record.u, hit_record.v, &hit_record.point);
+            vertex.light_pdf = self.light_origin_pdf(&ray, hit_record.t);
+            vertex.delta = delta;
+            vertex.pdf_fwd = convert_density(pdf_fwd, path.last().unwrap(), &vertex);
+            path.push(vertex);
+
+            let pdf_rev;
+            match scatter_record {
+                Some(ScatterRecord::Specular { attenuation, ray: scattered }) => {
+                    beta = beta * attenuation;
+                    ray = scattered;
+                    pdf_fwd = 0.;
+                    pdf_rev = 0.;
+                },
+                Some(ScatterRecord::Diffuse(lobe)) => {
+                    let scattered = Ray::new(hit_record.point, lobe.sample(), ray.time);
+                    pdf_fwd = lobe.pdf(&scattered.direction);
+                    if pdf_fwd <= 0. {
+                        break;
+                    }
+                    beta = beta * lobe.eval(&scattered.direction) / pdf_fwd;
+                    pdf_rev = lobe.pdf(&-ray.direction);
+                    ray = scattered;
+                },
+                None => break,
+            }
+            let n = path.len();
+            path[n - 2].pdf_rev = convert_density(pdf_rev, &path[n - 1], &path[n - 2]);
+
+            // Subpaths carrying little of their starting light are likely to end
+            depth += 1;
+            if depth >= self.min_depth {
+                let survival = f64::min(f64::max(beta.x, f64::max(beta.y, beta.z)) / scale, MAX_SURVIVAL);
+                if random_double() >= survival {
+                    break;
+                }
+                beta /= survival;
+            }
+        }
+        None
+    }
+
+    /// Gets the density of a light subpath starting at a ray's hit, or zero if it isn't on
+    /// one of the lights which light subpaths start from
+    fn light_origin_pdf(&self, ray: &Ray, t: f64) -> f64 {
+        // Lights are hit at the same distance as in the world, give or take rounding
+        let tolerance = 1e-9 * f64::max(t, 1.);
+        let count = self.lights.objects.len() as f64;
+        self.lights.objects.iter()
+            .find(|light| light.hit(ray, t - tolerance, t + tolerance).is_some())
+            .map_or(0., |light| 1. / (count * light.area()))
+    }
+
+    /// Weights the path made by joining the first `s` vertices of a light subpath to the
+    /// first `t` vertices of a camera subpath against every other way of sampling it, using
+    /// the balance heuristic. `sampled` replaces the vertex at the end of a subpath of one
+    /// vertex, which is sampled afresh when connecting
+    fn mis_weight(&self, light: &[Vertex], camera: &[Vertex], sampled: Option<Vertex>, s: usize, t: usize) -> f64 {
+        if s + t == 2 {
+            return 1.;
+        }
+        let mut light = light[..s].to_vec();
+        let mut camera = camera[..t].to_vec();
+        match (s, t, sampled) {
+            (1, _, Some(vertex)) => light[0] = vertex,
+            (_, 1, Some(vertex)) => camera[0] = vertex,
+            _ => {},
+        }
+
+        // The vertices being connected can't be specular, while the densities of sampling
+        // them and their predecessors in the reverse direction depend on the connection
+        camera[t - 1].delta = false;
+        let pt = camera[t - 1];
+        camera[t - 1].pdf_rev = match s {
+            0 => pt.light_pdf,
+            _ => self.pdf(&light[s - 1], &pt),
+        };
+        if t > 1 {
+            camera[t - 2].pdf_rev = match s {
+                0 => self.pdf_light(&pt, &camera[t - 2]),
+                _ => self.pdf(&pt, &camera[t - 2]),
+            };
+        }
+        if s > 0 {
+            light[s - 1].delta = false;
+            let qs = light[s - 1];
+            light[s - 1].pdf_rev = self.pdf(&pt, &qs);
+            if s > 1 {
+                light[s - 2].pdf_rev = self.pdf(&qs, &light[s - 2]);
+            }
+        }
+
+        // Sums the density of each other strategy relative to this one, skipping those which
+        // would need to connect to a specular vertex
+        let remap = |pdf: f64| if pdf != 0. { pdf } else { 1. };
+        let mut sum = 0.;
+        let mut ratio = 1.;
+        for i in (1..t).rev() {
+            ratio *= remap(camera[i].pdf_rev) / remap(camera[i].pdf_fwd);
+            if !camera[i].delta && !camera[i - 1].delta {
+                sum += ratio;
+            }
+        }
+        ratio = 1.;
+        for i in (0..s).rev() {
+            ratio *= remap(light[i].pdf_rev) / remap(light[i].pdf_fwd);
+            if !light[i].delta && (i == 0 || !light[i - 1].delta) {
+                sum += ratio;
+            }
+        }
+        1. / (1. + sum)
+    }
+
+    /// Finds the weighted light carried by the path joining the first `s` vertices of a light
+    /// subpath to the first `t` vertices of a camera subpath, along with the pixel it lands
+    /// in. Paths with a single camera vertex are traced to a new point on the lens, so can
+    /// land in any pixel, while the rest land in the pixel whose sample traced them
+    fn connect(&self, light: &[Vertex], camera: &[Vertex], s: usize, t: usize, time: f64) -> Option<(Vec3, Option<usize>)> {
+        let mut sampled = None;
+        let (radiance, pixel) = if s == 0 {
+            // The camera subpath found a light by itself. Light subpaths never start from
+            // emitters which aren't among the lights, so nothing else could find their light
+            // and it takes the whole weight. `mis_weight` can't be left to find that, as it
+            // treats a zero density as unknown rather than impossible
+            let pt = &camera[t - 1];
+            let radiance = pt.beta * pt.emitted;
+            if pt.light_pdf == 0. {
+                return (!radiance.near_zero()).then_some((radiance, None));
+            }
+            (radiance, None)
+        } else if t == 1 {
+            // The light subpath is traced straight to a point on the lens
+            let qs = &light[s - 1];
+            let lobe = qs.lobe()?;
+            let camera = &self.scene.camera;
+            let lens_point = camera.sample_lens();
+            let (u, v) = camera.image_position(&lens_point, &qs.point)?;
+            let x = u * (self.width - 1) as f64;
+            let y = v * (self.height - 1) as f64;
+            if x < 0. || y < 0. || x >= self.width as f64 || y >= self.height as f64
+                || !self.visible(&qs.point, &lens_point, time) {
+                return None;
+            }
+
+            let offset = lens_point - qs.point;
+            let pixel_area = 1. / ((self.width - 1) * (self.height - 1)) as f64;
+            let importance = camera.image_density(&-offset) / (pixel_area * offset.length_squared());
+            sampled = Some(Vertex::new(VertexKind::Camera, lens_point, None, Vec3::one()));
+            let pixel = (self.height - 1 - y as usize) * self.width + x as usize;
+            (qs.beta * lobe.eval(&offset) * importance, Some(pixel))
+        } else if s == 1 {
+            // A new point on a light is sampled for the camera subpath to connect to
+            let pt = &camera[t - 1];
+            let lobe = pt.lobe()?;
+            let light_vertex = self.sample_light()?;
+            if !self.visible(&pt.point, &light_vertex.point, time) {
+                return None;
+            }
+            let offset = light_vertex.point - pt.point;
+            let cos_theta = light_vertex.normal.map_or(1., |normal| Vec3::dot(&normal, &offset.unit()).abs());
+            sampled = Some(light_vertex);
+            (pt.beta * lobe.eval(&offset) * light_vertex.beta * cos_theta / offset.length_squared(), None)
+        } else {
+            // The ends of the two subpaths are joined
+            let qs = &light[s - 1];
+            let pt = &camera[t - 1];
+            let (light_lobe, camera_lobe) = (qs.lobe()?, pt.lobe()?);
+            if !self.visible(&pt.point, &qs.point, time) {
+                return None;
+            }
+            let offset = qs.point - pt.point;
+            let f = camera_lobe.eval(&offset) * light_lobe.eval(&-offset);
+            (pt.beta * f * qs.beta / offset.length_squared(), None)
+        };
+
+        if radiance.near_zero() {
+            return None;
+        }
+        Some((self.mis_weight(light, camera, sampled, s, t) * radiance, pixel))
+    }
+}
+
+/// Gets the colour of a camera ray by bidirectional path tracing. A camera subpath follows
+/// the ray while a light subpath leaves a random point on a light, and every prefix of one
+/// is joined to every prefix of the other, with the results weighted by multiple importance
+/// sampling. Russian roulette ends each subpath after `min_depth` bounces, and `max_depth`
+/// optionally caps the number of bounces of the joined paths. The light of paths joined
+/// straight to the camera lands in other pixels, so is returned separately for an image of
+/// the given size. Light subpaths start from the given lights, so an empty list leaves only
+/// the camera subpath finding lights by chance
+pub fn bidirectional_colour(
+    ray: &Ray, scene: &Scene, lights: &HittableList, image_width: usize, image_height: usize,
+    min_depth: i32, max_depth: Option<i32>
+) -> (Vec3, Vec<LightSplat>) {
+    let tracer = Tracer { scene, lights, width: image_width, height: image_height, min_depth };
+    let time = ray.time;
+    let max_vertices = |extra: i32| max_depth.map(|max_depth| (max_depth + extra) as usize);
+
+    // The camera subpath can reach up to `max_depth` hits, and the light subpath one fewer
+    let camera = &scene.camera;
+    let mut camera_path = vec![Vertex::new(VertexKind::Camera, ray.origin, None, Vec3::one())];
+    let pdf = camera.image_density(&ray.direction) / tracer.image_area();
+    let escaped = tracer.random_walk(&mut camera_path, Ray::new(ray.origin, ray.direction, time), Vec3::one(), pdf, max_vertices(1));
+
+    let mut light_path = vec![];
+    if let Some(light_vertex) = tracer.sample_light() {
+        let normal = light_vertex.normal.unwrap();
+        let side = if random_double() < 0.5 { normal } else { -normal };
+        let direction = Lobe::Cosine { albedo: Vec3::one(), normal: side }.sample();
+        let pdf = emission_pdf(&normal, &direction);
+        light_path.push(light_vertex);
+        if pdf > 0. {
+            let beta = light_vertex.beta * Vec3::dot(&normal, &direction.unit()).abs() / pdf;
+            tracer.random_walk(&mut light_path, Ray::new(light_vertex.point, direction, time), beta, pdf, max_vertices(0));
+        }
+    }
+
+    // The background can't be sampled by light subpaths, so only the camera subpath finds it
+    let mut radiance = escaped.map_or(Vec3::zero(), |(beta, direction)| beta * scene.background.value(&direction));
+    let mut splats = vec![];
+    for t in 1..=camera_path.len() {
+        for s in 0..=light_path.len() {
+            // Lights seen directly are left to the camera subpath rather than traced to the lens
+            let depth = (s + t) as i32 - 1;
+            if (t == 1 && s < 2) || max_depth.is_some_and(|max_depth| depth > max_depth) {
+                continue;
+            }
+            match tracer.connect(&light_path, &camera_path, s, t, time) {
+                Some((contribution, None)) => radiance += contribution,
+                Some((contribution, Some(pixel))) => splats.push(LightSplat { pixel, radiance: contribution }),
+                None => {},
+            }
+        }
+    }
+    (radiance, splats)
+}
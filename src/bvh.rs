@@ -0,0 +1,462 @@
+use std::cell::Cell;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use serde::{Deserialize, Serialize};
+use crate::random_int;
+use crate::aabb::AABB;
+use crate::hit_record::HitRecord;
+use crate::hittable::{Hittable, HittableTrait};
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+const MAX_LEAF_OBJECTS: usize = 4;      // Maximum number of objects stored in a leaf node
+pub(crate) const MAX_DEPTH: usize = 64; // Maximum depth of the hierarchy
+const SAH_BINS: usize = 16;             // Number of bins candidate SAH splits are taken between
+const TRAVERSAL_COST: f64 = 1.;         // Estimated cost of testing a ray against a node's box
+const INTERSECTION_COST: f64 = 1.;      // Estimated cost of testing a ray against an object
+
+// Number of live guards from `count_traversal_steps`, while any of which traversals count
+// their steps. Renders other than the traversal cost view have no use for them and so skip
+static TRAVERSAL_COUNTERS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Number of boxes and objects tested by the current thread's rays since it was last reset
+    static TRAVERSAL_STEPS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Guard which keeps traversals counting their steps until it's dropped
+pub struct TraversalCounting(());
+
+impl Drop for TraversalCounting {
+    fn drop(&mut self) {
+        TRAVERSAL_COUNTERS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Starts counting the boxes and objects every thread's rays are tested against, until the
+/// returned guard is dropped
+pub fn count_traversal_steps() -> TraversalCounting {
+    TRAVERSAL_COUNTERS.fetch_add(1, Ordering::Relaxed);
+    TraversalCounting(())
+}
+
+/// Resets the count of boxes and objects the current thread's rays have been tested against
+pub fn reset_traversal_steps() {
+    TRAVERSAL_STEPS.with(|steps| steps.set(0));
+}
+
+/// Gets the number of boxes and objects the current thread's rays have been tested against
+/// in bounding volume hierarchies and lists since the count was last reset
+pub fn traversal_steps() -> u64 {
+    TRAVERSAL_STEPS.with(|steps| steps.get())
+}
+
+/// Adds to the count of boxes and objects the current thread's rays have been tested against,
+/// which each traversal does once at the end rather than at every step, if counting is enabled
+pub(crate) fn add_traversal_steps(count: u64) {
+    if TRAVERSAL_COUNTERS.load(Ordering::Relaxed) > 0 {
+        TRAVERSAL_STEPS.with(|steps| steps.set(steps.get() + count));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Method used to choose how the objects of a BVH are split between each node's children
+pub enum BVHBuildMethod {
+    #[default]
+    RandomAxis,     // Splits at the median object along a random axis
+    SAH,            // Splits where the binned surface area heuristic estimates the cheapest traversal
+}
+
+/// Primitive being placed in a hierarchy, along with its cached bounds
+struct BuildPrimitive {
+    index: u32,
+    bounding_box: AABB,
+    centroid: Vec3,
+}
+
+#[derive(Debug)]
+/// Node in a flattened bounding volume hierarchy, stored in depth-first order
+pub(crate) struct LinearNode {
+    pub(crate) bounding_box: AABB,  // Bounding box of all primitives below this node
+    pub(crate) offset: u32,         // Index of the first primitive for leaves, or of the second child otherwise
+    pub(crate) count: u16,          // Number of primitives in a leaf, or 0 for interior nodes
+    pub(crate) axis: u8,            // Axis the children of an interior node were split along
+}
+
+#[derive(Debug)]
+/// Object to represent a Bounding Volume Hierarchy, flattened into an array of nodes whose
+/// leaves each hold a contiguous range of objects
+pub struct BVH {
+    objects: Vec<Arc<Hittable>>,    // Objects in the order they are referenced by the leaves
+    first_ids: Vec<u32>,            // ID of each object's first object, by their order in the original list
+    object_count: u32,              // Total number of objects within all of the objects
+    nodes: Vec<LinearNode>,         // Nodes of the hierarchy, with the root first
+}
+
+impl BVH {
+    /// Constructs a BVH from a Hittable List, splitting at the median along random axes
+    pub fn from_hittable_list(list: &HittableList, time0: f64, time1: f64) -> Hittable {
+        BVH::build(list, time0, time1, BVHBuildMethod::RandomAxis)
+    }
+
+    /// Constructs a BVH from a Hittable List using the given build method
+    pub fn build(list: &HittableList, time0: f64, time1: f64, method: BVHBuildMethod) -> Hittable {
+        assert!(!list.objects.is_empty(), "BVH must contain at least one object.");
+
+        let mut object_count = 0;
+        let mut first_ids = Vec::with_capacity(list.objects.len());
+        let bounding_boxes: Vec<AABB> = list.objects.iter().map(|object| {
+            first_ids.push(object_count);
+            object_count += object.object_count();
+            match object.bounding_box(time0, time1) {
+                Some(b) => b,
+                None => panic!("No bounding box in BVH constructor.")
+            }
+        }).collect();
+
+        let (nodes, order) = build_hierarchy(&bounding_boxes, method);
+
+        let first_ids = order.iter().map(|&i| first_ids[i]).collect();
+        let objects = order.iter().map(|&i| Arc::clone(&list.objects[i])).collect();
+        Hittable::BVH(BVH { objects, first_ids, object_count, nodes })
+    }
+
+    /// Gathers statistics about the shape of the BVH
+    pub fn statistics(&self) -> BVHStatistics {
+        let mut statistics = hierarchy_statistics(&self.nodes);
+        statistics.description = format!("BVH of {} objects", self.objects.len());
+        statistics
+    }
+}
+
+impl HittableTrait for BVH {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let direction_is_negative = [ray.direction.x < 0., ray.direction.y < 0., ray.direction.z < 0.];
+
+        let mut closest = None;
+        let mut closest_so_far = t_max;
+
+        let mut stack = [0u32; MAX_DEPTH + 1];
+        let mut stack_size = 1;
+        let mut steps = 0;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let node_index = stack[stack_size];
+            let node = &self.nodes[node_index as usize];
+            steps += 1;
+            if !node.bounding_box.hit(ray, t_min, closest_so_far) {
+                continue;
+            }
+
+            if node.count > 0 {
+                let start = node.offset as usize;
+                steps += node.count as u64;
+                for i in start..start + node.count as usize {
+                    if let Some(mut hit) = self.objects[i].hit(ray, t_min, closest_so_far) {
+                        closest_so_far = hit.t;
+                        hit.object_id += self.first_ids[i];
+                        closest = Some(hit);
+                    }
+                }
+            } else {
+                // Visits the child on the near side of the split first, so that hits found in
+                // it can cull the far child. The first child directly follows its parent
+                if direction_is_negative[node.axis as usize] {
+                    stack[stack_size] = node_index + 1;
+                    stack[stack_size + 1] = node.offset;
+                } else {
+                    stack[stack_size] = node.offset;
+                    stack[stack_size + 1] = node_index + 1;
+                }
+                stack_size += 2;
+            }
+        }
+
+        add_traversal_steps(steps);
+        closest
+    }
+
+    fn bounding_box(&self, _: f64, _: f64) -> Option<AABB> {
+        Some(self.nodes[0].bounding_box)
+    }
+
+    fn object_count(&self) -> u32 {
+        self.object_count
+    }
+
+    /// Adds the materials of every object in the order of the list the BVH was built from
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        let mut order: Vec<usize> = (0..self.objects.len()).collect();
+        order.sort_by_key(|&i| self.first_ids[i]);
+        for i in order {
+            self.objects[i].collect_materials(materials);
+        }
+    }
+
+    /// Adds the BVH's own statistics before those of the objects within it
+    fn collect_statistics(&self, statistics: &mut Vec<BVHStatistics>) {
+        statistics.push(self.statistics());
+        let mut order: Vec<usize> = (0..self.objects.len()).collect();
+        order.sort_by_key(|&i| self.first_ids[i]);
+        for i in order {
+            self.objects[i].collect_statistics(statistics);
+        }
+    }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<Hittable>>) {
+        let mut order: Vec<usize> = (0..self.objects.len()).collect();
+        order.sort_by_key(|&i| self.first_ids[i]);
+        for i in order {
+            if self.objects[i].is_light() {
+                lights.push(Arc::clone(&self.objects[i]));
+            } else {
+                self.objects[i].collect_lights(lights);
+            }
+        }
+    }
+}
+
+/// Builds a bounding volume hierarchy over primitives with the given bounding boxes, returning
+/// its nodes and the order the primitives must be stored in for each leaf's to be contiguous
+pub(crate) fn build_hierarchy(bounding_boxes: &[AABB], method: BVHBuildMethod) -> (Vec<LinearNode>, Vec<usize>) {
+    let mut primitives: Vec<BuildPrimitive> = bounding_boxes.iter().enumerate().map(|(i, bounding_box)| {
+        BuildPrimitive { index: i as u32, bounding_box: *bounding_box, centroid: bounding_box.centroid() }
+    }).collect();
+
+    let mut nodes = vec![];
+    build_node(&mut nodes, &mut primitives, 0, method, 0);
+
+    let order = primitives.iter().map(|p| p.index as usize).collect();
+    (nodes, order)
+}
+
+/// Recursively builds the node covering a range of primitives, reordering them so that each
+/// leaf's primitives are contiguous
+fn build_node(
+    nodes: &mut Vec<LinearNode>, primitives: &mut [BuildPrimitive], start: usize,
+    method: BVHBuildMethod, depth: usize
+) {
+    let bounding_box = primitives.iter().skip(1).fold(primitives[0].bounding_box, |b, p| {
+        AABB::surrounding_box(&b, &p.bounding_box)
+    });
+
+    let node_index = nodes.len();
+    nodes.push(LinearNode { bounding_box, offset: start as u32, count: primitives.len() as u16, axis: 0 });
+
+    // Leaves are forced at the maximum depth, as long as their primitive count still fits
+    let at_max_depth = depth + 1 >= MAX_DEPTH && primitives.len() <= u16::MAX as usize;
+    let leaf = primitives.len() == 1 || at_max_depth;
+
+    let split = match method {
+        BVHBuildMethod::RandomAxis => median_split(primitives, leaf),
+        BVHBuildMethod::SAH if leaf => None,
+        BVHBuildMethod::SAH => sah_split(primitives, &bounding_box),
+    };
+    let (axis, mid) = match split {
+        Some(split) => split,
+        None => return
+    };
+
+    let (left, right) = primitives.split_at_mut(mid);
+    build_node(nodes, left, start, method, depth + 1);
+    let right_index = nodes.len();
+    build_node(nodes, right, start + mid, method, depth + 1);
+
+    nodes[node_index].offset = right_index as u32;
+    nodes[node_index].count = 0;
+    nodes[node_index].axis = axis as u8;
+}
+
+/// Splits a range of primitives at the median along a random axis, returning the axis and the
+/// index of the split, or None if the primitives should form a leaf. Axes are drawn just as
+/// the original recursive builder drew them, so scenes drawing more random numbers after
+/// building a BVH are laid out the same for a given seed
+fn median_split(primitives: &mut [BuildPrimitive], leaf: bool) -> Option<(usize, usize)> {
+    let axis = random_int(0, 2) as usize;
+    if leaf || primitives.len() <= MAX_LEAF_OBJECTS {
+        // The original builder went on splitting down to single objects and pairs
+        for _ in 1..recursive_axis_draws(primitives.len()) {
+            random_int(0, 2);
+        }
+        return None;
+    }
+
+    let mid = primitives.len() / 2;
+    primitives.select_nth_unstable_by(mid, |a, b| {
+        a.bounding_box.minimum[axis].total_cmp(&b.bounding_box.minimum[axis])
+    });
+
+    Some((axis, mid))
+}
+
+/// Gets the number of axes the original recursive builder drew for a range of objects, one at
+/// every node of its tree
+fn recursive_axis_draws(count: usize) -> usize {
+    match count {
+        0..=2 => 1,
+        _ => 1 + recursive_axis_draws(count / 2) + recursive_axis_draws(count - count / 2),
+    }
+}
+
+/// Splits a range of primitives where the binned surface area heuristic estimates the lowest
+/// traversal cost, returning the axis and the index of the split, or None if the primitives
+/// are cheaper to intersect as a leaf
+fn sah_split(primitives: &mut [BuildPrimitive], bounding_box: &AABB) -> Option<(usize, usize)> {
+    let mut centroid_min = primitives[0].centroid;
+    let mut centroid_max = centroid_min;
+    for p in primitives.iter() {
+        for a in 0..3 {
+            centroid_min[a] = f64::min(centroid_min[a], p.centroid[a]);
+            centroid_max[a] = f64::max(centroid_max[a], p.centroid[a]);
+        }
+    }
+
+    let bin_of = |centroid: &Vec3, axis: usize| {
+        let offset = (centroid[axis] - centroid_min[axis]) / (centroid_max[axis] - centroid_min[axis]);
+        usize::min((offset * SAH_BINS as f64) as usize, SAH_BINS - 1)
+    };
+
+    // Finds the cheapest split between two bins along any axis
+    let mut best: Option<(f64, usize, usize)> = None;
+    for axis in 0..3 {
+        if centroid_max[axis] <= centroid_min[axis] {
+            continue;
+        }
+
+        let mut counts = [0; SAH_BINS];
+        let mut boxes: [Option<AABB>; SAH_BINS] = [None; SAH_BINS];
+        for p in primitives.iter() {
+            let bin = bin_of(&p.centroid, axis);
+            counts[bin] += 1;
+            boxes[bin] = Some(grow(boxes[bin], &p.bounding_box));
+        }
+
+        // Sweeps from the right to find the cost of every possible right child
+        let mut right_costs = [0.; SAH_BINS];
+        let mut right_box = None;
+        let mut right_count = 0;
+        for bin in (1..SAH_BINS).rev() {
+            if let Some(b) = &boxes[bin] {
+                right_box = Some(grow(right_box, b));
+            }
+            right_count += counts[bin];
+            right_costs[bin - 1] = right_box.map_or(0., |b| b.surface_area() * right_count as f64);
+        }
+
+        let mut left_box = None;
+        let mut left_count = 0;
+        for bin in 0..SAH_BINS - 1 {
+            if let Some(b) = &boxes[bin] {
+                left_box = Some(grow(left_box, b));
+            }
+            left_count += counts[bin];
+            if left_count == 0 || left_count == primitives.len() {
+                continue;
+            }
+
+            let cost = left_box.map_or(0., |b| b.surface_area() * left_count as f64) + right_costs[bin];
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, bin));
+            }
+        }
+    }
+
+    // Objects with identical centroids can't be separated, so are split evenly if too many
+    let (cost, axis, split_bin) = match best {
+        Some(best) => best,
+        None if primitives.len() <= MAX_LEAF_OBJECTS => return None,
+        None => return Some((0, primitives.len() / 2))
+    };
+
+    // Small ranges become leaves if intersecting every object is cheaper than splitting
+    let area = bounding_box.surface_area();
+    let split_cost = TRAVERSAL_COST + INTERSECTION_COST * if area > 0. { cost / area } else { 0. };
+    let leaf_cost = INTERSECTION_COST * primitives.len() as f64;
+    if primitives.len() <= MAX_LEAF_OBJECTS && leaf_cost <= split_cost {
+        return None;
+    }
+
+    let mut mid = 0;
+    for i in 0..primitives.len() {
+        if bin_of(&primitives[i].centroid, axis) <= split_bin {
+            primitives.swap(i, mid);
+            mid += 1;
+        }
+    }
+    Some((axis, mid))
+}
+
+/// Gathers statistics about the shape of a hierarchy
+pub(crate) fn hierarchy_statistics(nodes: &[LinearNode]) -> BVHStatistics {
+    let mut statistics = BVHStatistics::default();
+    let root_area = nodes[0].bounding_box.surface_area();
+    gather_statistics(nodes, &mut statistics, 0, 1, root_area);
+    statistics
+}
+
+/// Adds a node and its descendants to a set of statistics
+fn gather_statistics(nodes: &[LinearNode], statistics: &mut BVHStatistics, node_index: usize, depth: usize, root_area: f64) {
+    let node = &nodes[node_index];
+    let relative_area = if root_area > 0. { node.bounding_box.surface_area() / root_area } else { 1. };
+    statistics.node_count += 1;
+    statistics.max_depth = usize::max(statistics.max_depth, depth);
+
+    if node.count > 0 {
+        let leaf_size = node.count as usize;
+        statistics.leaf_count += 1;
+        statistics.primitive_count += leaf_size;
+        statistics.total_leaf_depth += depth;
+        if statistics.leaf_sizes.len() <= leaf_size {
+            statistics.leaf_sizes.resize(leaf_size + 1, 0);
+        }
+        statistics.leaf_sizes[leaf_size] += 1;
+        statistics.traversal_cost += INTERSECTION_COST * relative_area * leaf_size as f64;
+    } else {
+        statistics.traversal_cost += TRAVERSAL_COST * relative_area;
+        gather_statistics(nodes, statistics, node_index + 1, depth + 1, root_area);
+        gather_statistics(nodes, statistics, node.offset as usize, depth + 1, root_area);
+    }
+}
+
+/// Grows an optional bounding box to surround another box
+fn grow(bounding_box: Option<AABB>, other: &AABB) -> AABB {
+    match bounding_box {
+        Some(b) => AABB::surrounding_box(&b, other),
+        None => *other
+    }
+}
+
+#[derive(Debug, Default)]
+/// Report on the shape of a BVH, used to compare build methods
+pub struct BVHStatistics {
+    pub description: String,        // What the hierarchy was built over
+    pub node_count: usize,          // Number of nodes in the tree
+    pub leaf_count: usize,          // Number of nodes which directly hold objects
+    pub primitive_count: usize,     // Number of objects held by leaves
+    pub max_depth: usize,           // Number of nodes on the longest path from the root
+    pub total_leaf_depth: usize,    // Sum of the depths of every leaf
+    pub leaf_sizes: Vec<usize>,     // Number of leaves holding each number of objects
+    pub traversal_cost: f64,        // Expected cost of tracing a ray through the tree, by the SAH
+}
+
+impl fmt::Display for BVHStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mean_leaf_depth = self.total_leaf_depth as f64 / usize::max(self.leaf_count, 1) as f64;
+        let leaf_sizes: Vec<String> = self.leaf_sizes.iter().enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(size, count)| format!("{size}: {count}"))
+            .collect();
+
+        writeln!(f, "Nodes:            {}", self.node_count)?;
+        writeln!(f, "Leaves:           {}", self.leaf_count)?;
+        writeln!(f, "Objects:          {}", self.primitive_count)?;
+        writeln!(f, "Maximum depth:    {}", self.max_depth)?;
+        writeln!(f, "Mean leaf depth:  {mean_leaf_depth:.2}")?;
+        writeln!(f, "Leaf sizes:       {}", leaf_sizes.join(", "))?;
+        write!(f, "Traversal cost:   {:.2}", self.traversal_cost)
+    }
+}
@@ -1,4 +1,4 @@
-use ray_tracing::{degrees_to_radians, random_range};
+use crate::{degrees_to_radians, random_range};
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
@@ -17,6 +17,7 @@ pub struct Camera {
 
 impl Camera {
     /// Returns a new Camera object
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         look_from: Vec3,
         look_at: Vec3,
@@ -63,7 +64,48 @@ impl Camera {
         Ray::new(
             self.origin + offset, self.lower_left_corner
             + x * self.horizontal + y * self.vertical - self.origin - offset,
-            random_range(self.min_time, self.max_time)
+            self.sample_time()
         )
     }
+
+    /// Chooses a random time while the camera's shutter is open
+    pub fn sample_time(&self) -> f64 {
+        random_range(self.min_time, self.max_time)
+    }
+
+    /// Chooses a random point on the camera's lens, which every ray starts from
+    pub fn sample_lens(&self) -> Vec3 {
+        let rd = self.lens_radius * Vec3::random_in_unit_disk();
+        self.origin + self.u * rd.x + self.v * rd.y
+    }
+
+    /// Gets the (x, y) position, as taken by `get_ray`, at which a point seen from a point on
+    /// the lens appears on the camera's image, or None if the point is behind the camera
+    pub fn image_position(&self, lens_point: &Vec3, point: &Vec3) -> Option<(f64, f64)> {
+        let w = Vec3::cross(&self.u, &self.v);
+        let direction = *point - *lens_point;
+        if Vec3::dot(&direction, &w) >= 0. {
+            return None;
+        }
+
+        // The image lies on the plane in focus, where every ray through a position crosses it
+        let t = Vec3::dot(&(self.lower_left_corner - *lens_point), &w) / Vec3::dot(&direction, &w);
+        let offset = *lens_point + t * direction - self.lower_left_corner;
+        Some((
+            Vec3::dot(&offset, &self.horizontal) / self.horizontal.length_squared(),
+            Vec3::dot(&offset, &self.vertical) / self.vertical.length_squared()
+        ))
+    }
+
+    /// Gets the area of the camera's image, in the units of positions taken by `get_ray`,
+    /// covered by rays leaving the lens per unit solid angle in a direction
+    pub fn image_density(&self, direction: &Vec3) -> f64 {
+        let w = Vec3::cross(&self.u, &self.v);
+        let cos_theta = -Vec3::dot(&direction.unit(), &w);
+        if cos_theta <= 0. {
+            return 0.;
+        }
+        let focus_dist = Vec3::dot(&(self.origin - self.lower_left_corner), &w);
+        focus_dist * focus_dist / (cos_theta.powi(3) * self.horizontal.length() * self.vertical.length())
+    }
 }
\ No newline at end of file
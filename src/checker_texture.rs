@@ -10,11 +10,16 @@ pub struct CheckerTexture {
 }
 
 impl CheckerTexture {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(even_colour: Vec3, odd_colour: Vec3) -> Texture {
         let even = Arc::new(SolidColour::new(even_colour));
         let odd = Arc::new(SolidColour::new(odd_colour));
         Texture::CheckerTexture(CheckerTexture { even, odd })
     }
+
+    pub fn from_textures(even: Arc<Texture>, odd: Arc<Texture>) -> Texture {
+        Texture::CheckerTexture(CheckerTexture { even, odd })
+    }
 }
 
 impl TextureTrait for CheckerTexture {
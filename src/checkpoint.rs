@@ -0,0 +1,195 @@
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::adaptive::PixelVariance;
+use crate::bvh::BVHBuildMethod;
+use crate::render_buffer::RenderBuffer;
+use crate::renderer::{Integrator, RenderSettings};
+use crate::scene::Scene;
+use crate::scene_file::load_scene;
+use crate::scenes::build_scene;
+use crate::vec3::Vec3;
+
+const MAGIC: &[u8; 8] = b"RTCKPT05";   // Identifies checkpoint files and their format version
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Where the scene of a render comes from, so that it can be rebuilt when resuming
+pub enum SceneSource {
+    BuiltIn(String),    // Name of a built-in scene
+    File(PathBuf),      // Path of a TOML scene description file
+}
+
+impl SceneSource {
+    /// Builds the scene, using the given method for its BVHs
+    pub fn build(&self, aspect_ratio: f64, bvh_method: BVHBuildMethod) -> Result<Scene, String> {
+        match self {
+            SceneSource::BuiltIn(name) => build_scene(name, aspect_ratio, bvh_method),
+            SceneSource::File(path) => load_scene(path, aspect_ratio, bvh_method),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Everything needed to rebuild the scene and settings of a checkpointed render
+pub struct CheckpointHeader {
+    pub scene: SceneSource,             // Where the scene comes from
+    pub bvh_method: BVHBuildMethod,     // Method the scene's BVHs were built with
+    pub settings: RenderSettings,       // Settings of the render, including its seed
+}
+
+/// Saves a render in progress to a checkpoint file, which is replaced atomically so that an
+/// interrupted save never leaves a broken checkpoint behind
+pub fn save_checkpoint(path: &Path, header: &CheckpointHeader, buffer: &RenderBuffer) -> Result<(), String> {
+    if header.settings.aovs != buffer.aovs.is_some() {
+        return Err("checkpoint settings don't match whether the render records AOVs".to_string());
+    }
+    let bidirectional = header.settings.integrator == Integrator::Bidirectional;
+    let header = toml::to_string(header).map_err(|e| format!("could not serialise checkpoint: {e}"))?;
+
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    let write = || -> std::io::Result<()> {
+        let mut file = BufWriter::new(fs::File::create(&temporary)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&(header.len() as u64).to_le_bytes())?;
+        file.write_all(header.as_bytes())?;
+        file.write_all(&(buffer.width as u64).to_le_bytes())?;
+        file.write_all(&(buffer.height as u64).to_le_bytes())?;
+
+        // Radiance and noise are stored exactly, so that resumed renders match uninterrupted ones
+        for i in 0..buffer.radiance.len() {
+            let radiance = buffer.radiance[i];
+            for c in [radiance.x, radiance.y, radiance.z, buffer.weights[i]] {
+                file.write_all(&c.to_le_bytes())?;
+            }
+            file.write_all(&buffer.samples[i].to_le_bytes())?;
+            for c in [buffer.variance[i].mean, buffer.variance[i].m2] {
+                file.write_all(&c.to_le_bytes())?;
+            }
+        }
+
+        // AOVs follow the radiance if the render records them, with missing IDs stored as u32::MAX
+        for pixel in buffer.aovs.iter().flatten() {
+            for v in [pixel.albedo, pixel.normal, pixel.position, pixel.uv] {
+                for c in [v.x, v.y, v.z] {
+                    file.write_all(&c.to_le_bytes())?;
+                }
+            }
+            file.write_all(&pixel.depth.to_le_bytes())?;
+            for id in [pixel.object_id, pixel.material_id] {
+                file.write_all(&id.unwrap_or(u32::MAX).to_le_bytes())?;
+            }
+        }
+
+        // Light traced straight to the camera follows if the render is bidirectional
+        if bidirectional {
+            file.write_all(&buffer.light_paths.to_le_bytes())?;
+            for light in &buffer.light {
+                for c in [light.x, light.y, light.z] {
+                    file.write_all(&c.to_le_bytes())?;
+                }
+            }
+        }
+        file.into_inner()?.sync_all()
+    };
+
+    write().and_then(|_| fs::rename(&temporary, path))
+        .map_err(|e| format!("could not write '{}': {e}", path.display()))
+}
+
+/// Loads a render in progress from a checkpoint file
+pub fn load_checkpoint(path: &Path) -> Result<(CheckpointHeader, RenderBuffer), String> {
+    let data = fs::read(path).map_err(|e| format!("could not read '{}': {e}", path.display()))?;
+    let invalid = |reason: &str| format!("'{}' is not a valid checkpoint: {reason}", path.display());
+    let truncated = || invalid("file is truncated");
+
+    let mut reader = &data[..];
+    if take(&mut reader, MAGIC.len()) != Some(MAGIC) {
+        return Err(invalid("unrecognised format"));
+    }
+
+    let header_length = take_u64(&mut reader).ok_or_else(truncated)? as usize;
+    let header = take(&mut reader, header_length).ok_or_else(truncated)?;
+    let header = std::str::from_utf8(header).map_err(|_| invalid("header is not UTF-8"))?;
+    let header: CheckpointHeader = toml::from_str(header).map_err(|e| invalid(&e.to_string()))?;
+
+    let width = take_u64(&mut reader).ok_or_else(truncated)? as usize;
+    let height = take_u64(&mut reader).ok_or_else(truncated)? as usize;
+    if width != header.settings.image_width || height != header.settings.image_height {
+        return Err(invalid("buffer size doesn't match its settings"));
+    }
+
+    let mut buffer = match header.settings.aovs {
+        true => RenderBuffer::with_aovs(width, height),
+        false => RenderBuffer::new(width, height),
+    };
+    for i in 0..width * height {
+        let bytes = take(&mut reader, 52).ok_or_else(truncated)?;
+        let float = |j: usize| f64::from_le_bytes(bytes[j..j + 8].try_into().unwrap());
+        buffer.radiance[i] = Vec3::new(float(0), float(8), float(16));
+        buffer.weights[i] = float(24);
+        buffer.samples[i] = u32::from_le_bytes(bytes[32..36].try_into().unwrap());
+        buffer.variance[i] = PixelVariance { mean: float(36), m2: float(44) };
+    }
+
+    for pixel in buffer.aovs.iter_mut().flatten() {
+        let bytes = take(&mut reader, 112).ok_or_else(truncated)?;
+        let component = |j: usize| f64::from_le_bytes(bytes[8 * j..8 * j + 8].try_into().unwrap());
+        let vector = |j: usize| Vec3::new(component(j), component(j + 1), component(j + 2));
+        let id = |j: usize| match u32::from_le_bytes(bytes[j..j + 4].try_into().unwrap()) {
+            u32::MAX => None,
+            id => Some(id)
+        };
+        pixel.albedo = vector(0);
+        pixel.normal = vector(3);
+        pixel.position = vector(6);
+        pixel.uv = vector(9);
+        pixel.depth = component(12);
+        pixel.object_id = id(104);
+        pixel.material_id = id(108);
+    }
+
+    if header.settings.integrator == Integrator::Bidirectional {
+        buffer.light_paths = take_u64(&mut reader).ok_or_else(truncated)?;
+        for light in buffer.light.iter_mut() {
+            let bytes = take(&mut reader, 24).ok_or_else(truncated)?;
+            let float = |j: usize| f64::from_le_bytes(bytes[j..j + 8].try_into().unwrap());
+            *light = Vec3::new(float(0), float(8), float(16));
+        }
+    }
+
+    Ok((header, buffer))
+}
+
+/// Takes a number of bytes from the front of a slice, or returns None if there aren't enough
+fn take<'a>(reader: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if reader.len() < n {
+        return None;
+    }
+    let (bytes, rest) = reader.split_at(n);
+    *reader = rest;
+    Some(bytes)
+}
+
+/// Takes a little-endian 64-bit integer from the front of a slice
+fn take_u64(reader: &mut &[u8]) -> Option<u64> {
+    take(reader, 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// Serialises seeds as hexadecimal strings, since TOML integers can't hold every 64-bit value
+pub(crate) mod hex_seed {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{seed:#018x}"))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        u64::from_str_radix(text.trim_start_matches("0x"), 16).map_err(serde::de::Error::custom)
+    }
+}
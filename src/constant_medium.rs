@@ -1,6 +1,7 @@
 use std::sync::Arc;
-use ray_tracing::{INFINITY, random_double};
+use crate::{INFINITY, random_double};
 use crate::aabb::AABB;
+use crate::bvh::BVHStatistics;
 use crate::hit_record::HitRecord;
 use crate::hittable::{Hittable, HittableTrait};
 use crate::isotropic::Isotropic;
@@ -17,6 +18,7 @@ pub struct ConstantMedium {
 }
 
 impl ConstantMedium {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(boundary: Arc<Hittable>, density: f64, albedo: Arc<Texture>) -> Hittable {
         Hittable::ConstantMedium(ConstantMedium {
             boundary, neg_inv_density: (-1. / density), phase_function: Arc::new(Isotropic::new(albedo))
@@ -68,4 +70,12 @@ impl HittableTrait for ConstantMedium {
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
         self.boundary.bounding_box(time0, time1)
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.push(Arc::clone(&self.phase_function));
+    }
+
+    fn collect_statistics(&self, statistics: &mut Vec<BVHStatistics>) {
+        self.boundary.collect_statistics(statistics);
+    }
 }
\ No newline at end of file
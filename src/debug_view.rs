@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use crate::INFINITY;
+use crate::bvh::{count_traversal_steps, reset_traversal_steps, traversal_steps};
+use crate::hittable::HittableTrait;
+use crate::output::{heat_colour, id_colour};
+use crate::ray::Ray;
+use crate::scatter_record::Lobe;
+use crate::scene::Scene;
+use crate::vec3::Vec3;
+
+const AO_RADIUS_FRACTION: f64 = 0.1;    // Default ambient occlusion radius, as a fraction of the scene's extent
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Property of the first surface seen along each ray which a debug render shows instead of
+/// its light. Rays which miss every object are black
+pub enum DebugView {
+    Normal,         // Shading normal facing the camera, mapped from [-1,1] to [0,1]
+    Uv,             // Texture coordinates in red and green
+    Distance,       // Distance from the camera, as a fraction of the scene's extent
+    MaterialId,     // Distinct colour for each of the scene's materials
+    FrontFace,      // Green where the outside of a surface is seen and red where the inside is
+    AmbientOcclusion {
+        radius: Option<f64>,    // Distance within which objects occlude, or None to scale it to the scene
+    },
+    TraversalCost {
+        max_steps: u32,         // Number of boxes and objects tested which is shown as red
+    },
+}
+
+/// Gets the colour of a camera ray in a debug view of a scene
+pub fn debug_colour(ray: &Ray, scene: &Scene, view: &DebugView) -> Vec3 {
+    // Steps are only counted while this ray is traced, so other renders don't pay for them
+    let _counting = matches!(view, DebugView::TraversalCost { .. }).then(count_traversal_steps);
+    reset_traversal_steps();
+    let hit = scene.world.hit(ray, 0.001, INFINITY);
+
+    match (view, hit) {
+        // Rays which miss still cost something to trace, so are coloured too
+        (DebugView::TraversalCost { max_steps }, _) => {
+            let cost = traversal_steps() as f32 / u32::max(*max_steps, 1) as f32;
+            Vec3::from(heat_colour(cost).map(f64::from))
+        },
+        (_, None) => Vec3::zero(),
+        (DebugView::Normal, Some(hit)) => 0.5 * (hit.normal + Vec3::one()),
+        (DebugView::Uv, Some(hit)) => Vec3::new(hit.u, hit.v, 0.),
+        (DebugView::Distance, Some(hit)) => hit.t * ray.direction.length() / scene.extent() * Vec3::one(),
+        (DebugView::MaterialId, Some(hit)) => {
+            let id = scene.material_id(&hit.material).map_or(-1., |id| id as f32);
+            Vec3::from(id_colour(id).map(f64::from))
+        },
+        (DebugView::FrontFace, Some(hit)) => match hit.front_face {
+            true => Vec3::new(0., 1., 0.),
+            false => Vec3::new(1., 0., 0.),
+        },
+        (DebugView::AmbientOcclusion { radius }, Some(hit)) => {
+            // A single ray in a cosine weighted direction is either blocked or not, which
+            // averages to the unoccluded fraction of the light from above the surface
+            let radius = radius.unwrap_or(AO_RADIUS_FRACTION * scene.extent());
+            let direction = Lobe::Cosine { albedo: Vec3::one(), normal: hit.normal }.sample().unit();
+            let occlusion_ray = Ray::new(hit.point, direction, ray.time);
+            match scene.world.hit(&occlusion_ray, 0.001, radius) {
+                Some(_) => Vec3::zero(),
+                None => Vec3::one(),
+            }
+        },
+    }
+}
@@ -0,0 +1,128 @@
+use rayon::prelude::*;
+use crate::render_buffer::RenderBuffer;
+use crate::vec3::Vec3;
+
+const KERNEL: [f64; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];  // B3 spline weights of each tap
+
+#[derive(Debug, Clone, Copy)]
+/// Settings for the edge-avoiding À-Trous wavelet denoiser, where each sigma is the difference
+/// at which a neighbouring pixel's weight falls to 1/e
+pub struct DenoiseSettings {
+    pub iterations: u32,        // Number of filter passes, each doubling the spacing of the kernel's taps
+    pub colour_sigma: f64,      // Difference in compressed colour, halved after each pass
+    pub normal_sigma: f64,      // Difference in first-hit normals
+    pub albedo_sigma: f64,      // Difference in first-hit albedo
+    pub depth_sigma: f64,       // Difference in first-hit depth, relative to the pixel's own depth
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        DenoiseSettings {
+            iterations: 5,
+            colour_sigma: 0.5,
+            normal_sigma: 0.3,
+            albedo_sigma: 0.1,
+            depth_sigma: 0.05,
+        }
+    }
+}
+
+/// First-hit features of a pixel which guide the filter away from blurring across edges
+struct Features {
+    albedo: Vec3,
+    normal: Vec3,
+    depth: f64,
+}
+
+/// Denoises a render with an edge-avoiding À-Trous wavelet filter guided by its albedo, normal
+/// and depth AOVs, returning a new buffer holding the filtered radiance
+pub fn denoise(buffer: &RenderBuffer, settings: &DenoiseSettings) -> Result<RenderBuffer, String> {
+    let aovs = buffer.aovs.as_ref().ok_or("denoising needs the render to record AOVs")?;
+    let features: Vec<Features> = aovs.iter().zip(&buffer.samples).map(|(pixel, &samples)| {
+        let n = u32::max(samples, 1) as f64;
+        Features { albedo: pixel.albedo / n, normal: pixel.normal / n, depth: pixel.depth / n }
+    }).collect();
+
+    let mut colour: Vec<Vec3> = (0..buffer.radiance.len()).map(|i| buffer.pixel(i)).collect();
+    for iteration in 0..settings.iterations {
+        let step = 1 << iteration;
+        let colour_sigma = settings.colour_sigma / step as f64;
+        let pass = Pass { buffer, colour: &colour, features: &features, settings, colour_sigma, step };
+        colour = (0..buffer.height).into_par_iter()
+            .flat_map_iter(|y| (0..buffer.width).map(move |x| pass.filter_pixel(x, y)))
+            .collect();
+    }
+
+    let mut denoised = RenderBuffer::new(buffer.width, buffer.height);
+    denoised.radiance = colour;
+    denoised.weights = buffer.samples.iter().map(|&n| if n > 0 { 1. } else { 0. }).collect();
+    denoised.samples = buffer.samples.clone();
+    denoised.aovs = buffer.aovs.clone();
+    Ok(denoised)
+}
+
+/// One pass of the filter over an image, with its taps spaced `step` pixels apart
+#[derive(Clone, Copy)]
+struct Pass<'a> {
+    buffer: &'a RenderBuffer,
+    colour: &'a [Vec3],
+    features: &'a [Features],
+    settings: &'a DenoiseSettings,
+    colour_sigma: f64,
+    step: usize,
+}
+
+impl Pass<'_> {
+    /// Applies one pass of the filter to a pixel, weighting its 5x5 neighbours spaced `step`
+    /// pixels apart by how similar they are to it
+    fn filter_pixel(&self, x: usize, y: usize) -> Vec3 {
+        let Pass { buffer, colour, features, settings, colour_sigma, step } = *self;
+        let i = y * buffer.width + x;
+        if buffer.samples[i] == 0 {
+            return colour[i];
+        }
+        let centre = &features[i];
+        let centre_colour = compress(colour[i]);
+
+        let mut sum = Vec3::zero();
+        let mut total_weight = 0.;
+        for (ky, &kernel_y) in KERNEL.iter().enumerate() {
+            let qy = y as isize + (ky as isize - 2) * step as isize;
+            if qy < 0 || qy >= buffer.height as isize {
+                continue;
+            }
+            for (kx, &kernel_x) in KERNEL.iter().enumerate() {
+                let qx = x as isize + (kx as isize - 2) * step as isize;
+                if qx < 0 || qx >= buffer.width as isize {
+                    continue;
+                }
+                let j = qy as usize * buffer.width + qx as usize;
+                if buffer.samples[j] == 0 {
+                    continue;
+                }
+                let other = &features[j];
+
+                // Each feature's weight falls off with the squared difference from the centre
+                let depth_difference = (centre.depth - other.depth) / f64::max(centre.depth, 1e-3);
+                let exponent = (centre_colour - compress(colour[j])).length_squared() / (colour_sigma * colour_sigma)
+                    + (centre.normal - other.normal).length_squared() / (settings.normal_sigma * settings.normal_sigma)
+                    + (centre.albedo - other.albedo).length_squared() / (settings.albedo_sigma * settings.albedo_sigma)
+                    + depth_difference * depth_difference / (settings.depth_sigma * settings.depth_sigma);
+                let weight = kernel_x * kernel_y * f64::exp(-exponent);
+
+                sum += weight * colour[j];
+                total_weight += weight;
+            }
+        }
+
+        // The centre always has a positive weight, so the total can't be zero
+        sum / total_weight
+    }
+}
+
+/// Compresses a colour into the range [0,1) so that bright outliers don't dominate colour
+/// differences
+fn compress(colour: Vec3) -> Vec3 {
+    let compress = |c: f64| f64::max(c, 0.) / (1. + f64::max(c, 0.));
+    Vec3::new(compress(colour.x), compress(colour.y), compress(colour.z))
+}
@@ -1,19 +1,60 @@
-use ray_tracing::random_double;
+use crate::random_double;
 use crate::hit_record::HitRecord;
 use crate::material::{Material, MaterialTrait};
 use crate::ray::Ray;
+use crate::scatter_record::ScatterRecord;
 use crate::vec3::Vec3;
 
+const D_LINE: f64 = 587.6;  // Wavelength in nanometres which refraction indices are quoted at, used when rendering in RGB
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Refraction index of a dielectric, which can vary with the wavelength of light
+pub enum RefractionIndex {
+    Constant(f64),                          // Same index at every wavelength
+    Cauchy { a: f64, b: f64 },              // n = a + b / λ², with λ in micrometres
+    Sellmeier { b: [f64; 3], c: [f64; 3] }, // n² = 1 + Σ bᵢλ² / (λ² - cᵢ), with λ in micrometres
+}
+
+impl RefractionIndex {
+    /// Gets the refraction index at a wavelength in nanometres
+    pub fn at(&self, wavelength: f64) -> f64 {
+        let micrometres = wavelength / 1000.;
+        let squared = micrometres * micrometres;
+        match self {
+            RefractionIndex::Constant(n) => *n,
+            RefractionIndex::Cauchy { a, b } => a + b / squared,
+            RefractionIndex::Sellmeier { b, c } =>
+                f64::sqrt(1. + (0..3).map(|i| b[i] * squared / (squared - c[i])).sum::<f64>()),
+        }
+    }
+
+    /// Checks whether the index varies with wavelength, splitting light into its colours
+    pub fn is_dispersive(&self) -> bool {
+        match self {
+            RefractionIndex::Constant(_) => false,
+            RefractionIndex::Cauchy { b, .. } => *b != 0.,
+            RefractionIndex::Sellmeier { .. } => true,
+        }
+    }
+}
+
 #[derive(Debug)]
 /// Object to represent Dielectric materials such as water and glass
 pub struct Dielectric {
-    albedo: Vec3,           // Albedo of the material
-    refraction_index: f64   // Refraction index of the material
+    albedo: Vec3,                       // Albedo of the material
+    refraction_index: RefractionIndex   // Refraction index of the material
 }
 
 impl Dielectric {
     /// Constructs a new Dielectric object, wrapped in the Material enum
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(refraction_index: f64) -> Material {
+        Dielectric::from_index(RefractionIndex::Constant(refraction_index))
+    }
+
+    /// Constructs a new Dielectric object with a refraction index which can vary with wavelength,
+    /// wrapped in the Material enum
+    pub fn from_index(refraction_index: RefractionIndex) -> Material {
         Material::Dielectric(Dielectric { albedo: Vec3::one(), refraction_index })
     }
 
@@ -26,12 +67,13 @@ impl Dielectric {
 }
 
 impl MaterialTrait for Dielectric {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Vec3, Ray)> {
-        // Calculates refraction ratio depending on if the front face of the object was hit
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        // Calculates refraction ratio at the ray's wavelength depending on if the front face of the object was hit
+        let refraction_index = self.refraction_index.at(ray_in.wavelength.unwrap_or(D_LINE));
         let refraction_ratio = if hit_record.front_face {
-            1. / self.refraction_index
+            1. / refraction_index
         } else {
-            self.refraction_index
+            refraction_index
         };
 
         // Calculates unit direction of the ray in and appropriate trig values
@@ -40,16 +82,24 @@ impl MaterialTrait for Dielectric {
         let sin_theta = f64::sqrt(1. - cos_theta * cos_theta);
 
         // Determines if a ray should be reflected or refracted, and gets the resulting direction
-        let direction;
-        if refraction_ratio * sin_theta > 1. || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double() {
-            direction = Ray::reflect(unit_direction, hit_record.normal);
+        let direction = if refraction_ratio * sin_theta > 1.
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > random_double() {
+            Ray::reflect(unit_direction, hit_record.normal)
         } else {
-            direction = Ray::refract(unit_direction, hit_record.normal, refraction_ratio);
-        }
+            Ray::refract(unit_direction, hit_record.normal, refraction_ratio)
+        };
 
         // Constructs the scattered ray
         let scattered = Ray::new(hit_record.point, direction, ray_in.time);
 
-        Some((self.albedo, scattered))
+        Some(ScatterRecord::Specular { attenuation: self.albedo, ray: scattered })
+    }
+
+    fn albedo(&self, _: &HitRecord) -> Vec3 {
+        self.albedo
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.refraction_index.is_dispersive()
     }
 }
\ No newline at end of file
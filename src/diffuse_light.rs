@@ -2,6 +2,7 @@ use std::sync::Arc;
 use crate::hit_record::HitRecord;
 use crate::material::{Material, MaterialTrait};
 use crate::ray::Ray;
+use crate::scatter_record::ScatterRecord;
 use crate::solid_colour::SolidColour;
 use crate::texture::{Texture, TextureTrait};
 use crate::vec3::Vec3;
@@ -12,6 +13,7 @@ pub struct DiffuseLight {
 }
 
 impl DiffuseLight {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(emit: Arc<Texture>) -> Material {
         Material::DiffuseLight(DiffuseLight { emit })
     }
@@ -22,11 +24,18 @@ impl DiffuseLight {
 }
 
 impl MaterialTrait for DiffuseLight {
-    fn scatter(&self, _: &Ray, _: &HitRecord) -> Option<(Vec3, Ray)> {
+    /// Lights absorb every ray, only emitting their own light
+    fn scatter(&self, _: &Ray, _: &HitRecord) -> Option<ScatterRecord> {
         None
     }
 
     fn emitted(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
         self.emit.value(u, v, p)
     }
+
+    /// Gets the colour of the light, scaled down to fit within [0,1]
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        let colour = self.emit.value(hit_record.u, hit_record.v, &hit_record.point);
+        colour / f64::max(f64::max(colour.x, f64::max(colour.y, colour.z)), 1.)
+    }
 }
\ No newline at end of file
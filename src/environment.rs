@@ -0,0 +1,201 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use image::ImageFormat;
+use image::codecs::hdr::HdrDecoder;
+use crate::{degrees_to_radians, random_double, PI};
+use crate::aabb::AABB;
+use crate::hit_record::HitRecord;
+use crate::hittable::HittableTrait;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::tone_map::luminance;
+use crate::vec3::Vec3;
+
+#[derive(Debug, Clone)]
+/// Light arriving from infinitely far away along rays which don't hit anything
+pub enum Background {
+    Colour(Vec3),                   // Same colour in every direction
+    Environment(Arc<Environment>),  // Colour looked up in an environment map
+}
+
+impl Background {
+    /// Gets the light arriving from a direction
+    pub fn value(&self, direction: &Vec3) -> Vec3 {
+        match self {
+            Background::Colour(colour) => *colour,
+            Background::Environment(environment) => environment.value(direction),
+        }
+    }
+}
+
+impl From<Vec3> for Background {
+    fn from(colour: Vec3) -> Self {
+        Background::Colour(colour)
+    }
+}
+
+#[derive(Debug)]
+/// Equirectangular image of the light arriving from every direction, which can be sampled
+/// directly with a density following the luminance of its pixels
+pub struct Environment {
+    width: usize,               // Width of the image in pixels, covering every angle around the vertical axis
+    height: usize,              // Height of the image in pixels, from straight up to straight down
+    pixels: Vec<Vec3>,          // Radiance of each pixel in rows from the top, scaled by the map's intensity
+    rotation: f64,              // Angle in radians the map is turned by about the vertical axis
+    row_cdf: Vec<f64>,          // Cumulative weight of the rows, from zero up to one
+    column_cdfs: Vec<f64>,      // Cumulative weight of the pixels of each row, from zero up to one in each
+    total_weight: f64,          // Sum of the weight of every pixel
+}
+
+impl Environment {
+    /// Constructs an environment map from the radiance of its pixels in rows from the top,
+    /// turned by an angle in degrees about the vertical axis and scaled by an intensity
+    pub fn new(width: usize, height: usize, pixels: Vec<Vec3>, rotation: f64, intensity: f64) -> Self {
+        let pixels: Vec<Vec3> = pixels.into_iter().map(|pixel| intensity * pixel).collect();
+
+        // Pixels near the poles cover less of the sphere, so are weighted down to match
+        let mut row_cdf = vec![0.; height + 1];
+        let mut column_cdfs = vec![0.; height * (width + 1)];
+        for row in 0..height {
+            let cdf = &mut column_cdfs[row * (width + 1)..(row + 1) * (width + 1)];
+            for column in 0..width {
+                cdf[column + 1] = cdf[column] + pixel_weight(&pixels, width, height, row, column);
+            }
+            row_cdf[row + 1] = row_cdf[row] + cdf[width];
+            normalise(cdf);
+        }
+        let total_weight = row_cdf[height];
+        normalise(&mut row_cdf);
+
+        Environment {
+            width, height, pixels, rotation: degrees_to_radians(rotation), row_cdf, column_cdfs, total_weight
+        }
+    }
+
+    /// Loads an environment map from a Radiance HDR or OpenEXR image
+    pub fn load(path: &Path, rotation: f64, intensity: f64) -> Result<Self, String> {
+        let error = |e: &dyn std::fmt::Display| format!("could not load '{}': {e}", path.display());
+        let (width, height, pixels) = match ImageFormat::from_path(path) {
+            // The generic decoder clamps Radiance HDR images to the range of 8-bit colours
+            Ok(ImageFormat::Hdr) => {
+                let file = File::open(path).map_err(|e| error(&e))?;
+                let decoder = HdrDecoder::new(BufReader::new(file)).map_err(|e| error(&e))?;
+                let metadata = decoder.metadata();
+                let pixels = decoder.read_image_hdr().map_err(|e| error(&e))?;
+                (metadata.width as usize, metadata.height as usize, pixels)
+            },
+            Ok(ImageFormat::OpenExr) => {
+                let image = image::open(path).map_err(|e| error(&e))?.into_rgb32f();
+                (image.width() as usize, image.height() as usize, image.pixels().copied().collect())
+            },
+            _ => return Err(error(&"environment maps must be Radiance HDR or OpenEXR images")),
+        };
+        let pixels = pixels.into_iter().map(|pixel| Vec3::from(pixel.0.map(f64::from))).collect();
+        Ok(Environment::new(width, height, pixels, rotation, intensity))
+    }
+
+    /// Gets the light arriving from a direction
+    pub fn value(&self, direction: &Vec3) -> Vec3 {
+        let (u, v) = self.image_position(direction);
+        let (row, column) = self.pixel_at(u, v);
+        self.pixels[row * self.width + column]
+    }
+
+    /// Chooses a random direction with a density proportional to the luminance arriving from it
+    pub fn sample(&self) -> Vec3 {
+        let (row, v) = sample_cdf(&self.row_cdf, random_double());
+        let (_, u) = sample_cdf(&self.column_cdfs[row * (self.width + 1)..(row + 1) * (self.width + 1)], random_double());
+        self.direction_at(u, v)
+    }
+
+    /// Gets the density per unit solid angle with which `sample` chooses a direction
+    pub fn pdf(&self, direction: &Vec3) -> f64 {
+        if self.total_weight <= 0. {
+            return 0.;
+        }
+        let (u, v) = self.image_position(direction);
+        let sin_theta = f64::sin(PI * v);
+        if sin_theta <= 0. {
+            return 0.;
+        }
+        let (row, column) = self.pixel_at(u, v);
+        let image_pdf = pixel_weight(&self.pixels, self.width, self.height, row, column) / self.total_weight
+            * (self.width * self.height) as f64;
+        image_pdf / (2. * PI * PI * sin_theta)
+    }
+
+    /// Gets the position in the image of a direction, from 0 to 1 across and down
+    fn image_position(&self, direction: &Vec3) -> (f64, f64) {
+        let direction = direction.unit();
+        let theta = f64::acos(direction.y.clamp(-1., 1.));
+        let phi = (f64::atan2(-direction.z, direction.x) + PI - self.rotation).rem_euclid(2. * PI);
+        (phi / (2. * PI), theta / PI)
+    }
+
+    /// Gets the direction of a position in the image, from 0 to 1 across and down
+    fn direction_at(&self, u: f64, v: f64) -> Vec3 {
+        let theta = PI * v;
+        let phi = 2. * PI * u - PI + self.rotation;
+        Vec3::new(theta.sin() * phi.cos(), theta.cos(), -theta.sin() * phi.sin())
+    }
+
+    /// Gets the row and column of the pixel at a position in the image
+    fn pixel_at(&self, u: f64, v: f64) -> (usize, usize) {
+        let row = usize::min((v * self.height as f64) as usize, self.height - 1);
+        let column = usize::min((u * self.width as f64) as usize, self.width - 1);
+        (row, column)
+    }
+}
+
+/// Gets the weight of a pixel of an environment map when sampling it, which is its luminance
+/// scaled by the size of the patch of sky it covers
+fn pixel_weight(pixels: &[Vec3], width: usize, height: usize, row: usize, column: usize) -> f64 {
+    let sin_theta = f64::sin(PI * (row as f64 + 0.5) / height as f64);
+    f64::max(luminance(pixels[row * width + column]), 0.) * sin_theta
+}
+
+/// Scales a cumulative distribution so that it ends at one, spreading it evenly if it's empty
+fn normalise(cdf: &mut [f64]) {
+    let total = cdf[cdf.len() - 1];
+    let steps = (cdf.len() - 1) as f64;
+    for (i, value) in cdf.iter_mut().enumerate() {
+        *value = if total > 0. { *value / total } else { i as f64 / steps };
+    }
+}
+
+/// Chooses an interval of a cumulative distribution with a random number, returning its index
+/// and the position within the whole range from 0 to 1, spread continuously over the interval
+fn sample_cdf(cdf: &[f64], u: f64) -> (usize, f64) {
+    let intervals = cdf.len() - 1;
+    let index = usize::min(cdf.partition_point(|&value| value <= u).saturating_sub(1), intervals - 1);
+    let width = cdf[index + 1] - cdf[index];
+    let offset = if width > 0. { (u - cdf[index]) / width } else { 0.5 };
+    (index, (index as f64 + offset) / intervals as f64)
+}
+
+/// Environment maps are never hit, but join a scene's lights so that they're sampled directly
+impl HittableTrait for Environment {
+    fn hit(&self, _: &Ray, _: f64, _: f64) -> Option<HitRecord> {
+        None
+    }
+
+    fn bounding_box(&self, _: f64, _: f64) -> Option<AABB> {
+        None
+    }
+
+    fn object_count(&self) -> u32 {
+        0
+    }
+
+    fn collect_materials(&self, _: &mut Vec<Arc<Material>>) {}
+
+    fn pdf_value(&self, _: &Vec3, direction: &Vec3) -> f64 {
+        self.pdf(direction)
+    }
+
+    fn random(&self, _: &Vec3) -> Vec3 {
+        self.sample()
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use crate::PI;
+
+const MITCHELL_B: f64 = 1. / 3.;    // B parameter of the Mitchell-Netravali filter
+const MITCHELL_C: f64 = 1. / 3.;    // C parameter of the Mitchell-Netravali filter
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Shape of the filter used to weight samples by their distance from a pixel's centre
+pub enum FilterShape {
+    #[default]
+    Box,        // Weights every sample within the radius equally
+    Tent,       // Weights samples linearly down to zero at the radius
+    Gaussian,   // Gaussian with a standard deviation of a third of the radius
+    Mitchell,   // Mitchell-Netravali cubic with B = C = 1/3, which has small negative lobes
+    Lanczos,    // Sinc windowed by a wider sinc, with as many lobes as the radius in pixels
+}
+
+impl FilterShape {
+    /// Gets the radius usually used with the filter, in pixels
+    pub fn default_radius(&self) -> f64 {
+        match self {
+            FilterShape::Box => 0.5,
+            FilterShape::Tent => 1.,
+            FilterShape::Gaussian => 1.5,
+            FilterShape::Mitchell => 2.,
+            FilterShape::Lanczos => 3.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Reconstruction filter which splats each sample into every pixel within its radius
+pub struct Filter {
+    pub shape: FilterShape,     // Shape of the filter
+    pub radius: f64,            // Distance in pixels along each axis beyond which samples have no weight
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::new(FilterShape::Box)
+    }
+}
+
+impl Filter {
+    /// Constructs a new Filter with the shape's usual radius
+    pub fn new(shape: FilterShape) -> Self {
+        Filter { shape, radius: shape.default_radius() }
+    }
+
+    /// Gets the weight of a sample at an offset in pixels from a pixel's centre
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        self.evaluate_axis(x) * self.evaluate_axis(y)
+    }
+
+    /// Gets the number of neighbouring pixels along each axis that a sample can contribute to
+    pub fn pixel_reach(&self) -> usize {
+        // A sample lies within half a pixel of its own pixel's centre
+        usize::max((self.radius + 0.5).ceil() as usize, 1) - 1
+    }
+
+    /// Evaluates the filter along one axis, which multiplied with the other axis gives the weight
+    pub fn evaluate_axis(&self, x: f64) -> f64 {
+        let r = self.radius;
+        match self.shape {
+            // Half-open, so that a sample on the boundary of two pixels only counts for one
+            FilterShape::Box => if -r <= x && x < r { 1. } else { 0. },
+            FilterShape::Tent => f64::max(r - x.abs(), 0.),
+            FilterShape::Gaussian => {
+                // The value at the radius is subtracted so that the filter falls to zero there
+                let gaussian = |x: f64| f64::exp(-4.5 * x * x / (r * r));
+                f64::max(gaussian(x) - gaussian(r), 0.)
+            },
+            FilterShape::Mitchell => mitchell(2. * x.abs() / r),
+            FilterShape::Lanczos => if x.abs() < r { sinc(x) * sinc(x / r) } else { 0. },
+        }
+    }
+}
+
+/// Evaluates the Mitchell-Netravali cubic, which is non-zero between -2 and 2
+fn mitchell(x: f64) -> f64 {
+    let (b, c) = (MITCHELL_B, MITCHELL_C);
+    let value = if x < 1. {
+        (12. - 9. * b - 6. * c) * x * x * x + (-18. + 12. * b + 6. * c) * x * x + (6. - 2. * b)
+    } else if x < 2. {
+        (-b - 6. * c) * x * x * x + (6. * b + 30. * c) * x * x + (-12. * b - 48. * c) * x + (8. * b + 24. * c)
+    } else {
+        0.
+    };
+    value / 6.
+}
+
+/// Evaluates the normalised sinc function
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.
+    } else {
+        f64::sin(PI * x) / (PI * x)
+    }
+}
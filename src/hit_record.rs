@@ -12,6 +12,7 @@ pub struct HitRecord {
     pub t: f64,                     // Time in the ray's lifetime that the hit occurred
     pub u: f64,                     // U coordinate of hit
     pub v: f64,                     // V coordinate of hit
+    pub object_id: u32,             // Index of the object hit among every object in the scene
     pub front_face: bool            // Stores if the hit was on an outward face of the object hit
 }
 
@@ -20,7 +21,7 @@ impl HitRecord {
     pub fn new(point: Vec3, material: &Arc<Material>, u: f64, v: f64, t: f64) -> Self {
         HitRecord {
             point, t, material: Arc::clone(material),
-            normal: Vec3::zero(), u, v, front_face: false
+            normal: Vec3::zero(), u, v, object_id: 0, front_face: false
         }
     }
 
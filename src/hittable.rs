@@ -1,7 +1,9 @@
+use std::sync::Arc;
 use crate::aa_box::AABox;
 use crate::aabb::AABB;
-use crate::bvh_node::BVHNode;
+use crate::bvh::{BVH, BVHStatistics};
 use crate::hit_record::HitRecord;
+use crate::material::Material;
 use crate::moving_sphere::MovingSphere;
 use crate::ray::Ray;
 use crate::sphere::Sphere;
@@ -9,6 +11,10 @@ use crate::aa_rect::{XYRect, XZRect, YZRect};
 use crate::constant_medium::ConstantMedium;
 use crate::rotate_y::RotateY;
 use crate::translate::Translate;
+use crate::triangle::Triangle;
+use crate::vec3::Vec3;
+use crate::mesh::Mesh;
+use crate::environment::Environment;
 
 /// Trait implemented by all hittable objects
 pub trait HittableTrait {
@@ -17,12 +23,56 @@ pub trait HittableTrait {
 
     /// Gets the bounding box of the object
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB>;
+
+    /// Gets the number of separate objects within the object, which are given consecutive
+    /// object IDs in hit records
+    fn object_count(&self) -> u32 {
+        1
+    }
+
+    /// Adds every material used by the object to a list, in a consistent order
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>);
+
+    /// Checks whether the object is a light which can be sampled directly
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    /// Adds every light within the object which can be sampled directly to a list, in a
+    /// consistent order
+    fn collect_lights(&self, _: &mut Vec<Arc<Hittable>>) {}
+
+    /// Adds statistics about every bounding volume hierarchy within the object to a list,
+    /// including the object's own, in a consistent order
+    fn collect_statistics(&self, _: &mut Vec<BVHStatistics>) {}
+
+    /// Gets the probability density, per unit solid angle, of `random` choosing a direction
+    /// from an origin
+    fn pdf_value(&self, _: &Vec3, _: &Vec3) -> f64 {
+        0.
+    }
+
+    /// Chooses a random direction from an origin towards the object
+    fn random(&self, _: &Vec3) -> Vec3 {
+        Vec3::new(1., 0., 0.)
+    }
+
+    /// Gets the surface area of a light, over which `sample_surface` chooses points uniformly
+    fn area(&self) -> f64 {
+        0.
+    }
+
+    /// Chooses a random point uniformly over the surface of a light, given as a hit record
+    /// with the outward normal, or None if the object can't be sampled
+    fn sample_surface(&self) -> Option<HitRecord> {
+        None
+    }
 }
 
 #[derive(Debug)]
 /// Enum storing each hittable object variation
 pub enum Hittable {
-    BVHNode(BVHNode),
+    BVH(BVH),
     Sphere(Sphere),
     MovingSphere(MovingSphere),
     XYRect(XYRect),
@@ -32,13 +82,16 @@ pub enum Hittable {
     Translate(Translate),
     RotateY(RotateY),
     ConstantMedium(ConstantMedium),
+    Triangle(Triangle),
+    Mesh(Mesh),
+    Environment(Arc<Environment>),
 }
 
 /// Calls methods for objects in the Hittable enum
 impl HittableTrait for Hittable {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
         match self {
-            Hittable::BVHNode(obj) => obj.hit(ray, t_min, t_max),
+            Hittable::BVH(obj) => obj.hit(ray, t_min, t_max),
             Hittable::Sphere(obj) => obj.hit(ray, t_min, t_max),
             Hittable::MovingSphere(obj) => obj.hit(ray, t_min, t_max),
             Hittable::XYRect(obj) => obj.hit(ray, t_min, t_max),
@@ -48,12 +101,15 @@ impl HittableTrait for Hittable {
             Hittable::Translate(obj) => obj.hit(ray, t_min, t_max),
             Hittable::RotateY(obj) => obj.hit(ray, t_min, t_max),
             Hittable::ConstantMedium(obj) => obj.hit(ray, t_min, t_max),
+            Hittable::Triangle(obj) => obj.hit(ray, t_min, t_max),
+            Hittable::Mesh(obj) => obj.hit(ray, t_min, t_max),
+            Hittable::Environment(obj) => obj.hit(ray, t_min, t_max),
         }
     }
 
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
         match self {
-            Hittable::BVHNode(obj) => obj.bounding_box(time0, time1),
+            Hittable::BVH(obj) => obj.bounding_box(time0, time1),
             Hittable::Sphere(obj) => obj.bounding_box(time0, time1),
             Hittable::MovingSphere(obj) => obj.bounding_box(time0, time1),
             Hittable::XYRect(obj) => obj.bounding_box(time0, time1),
@@ -63,6 +119,170 @@ impl HittableTrait for Hittable {
             Hittable::Translate(obj) => obj.bounding_box(time0, time1),
             Hittable::RotateY(obj) => obj.bounding_box(time0, time1),
             Hittable::ConstantMedium(obj) => obj.bounding_box(time0, time1),
+            Hittable::Triangle(obj) => obj.bounding_box(time0, time1),
+            Hittable::Mesh(obj) => obj.bounding_box(time0, time1),
+            Hittable::Environment(obj) => obj.bounding_box(time0, time1),
+        }
+    }
+
+    fn object_count(&self) -> u32 {
+        match self {
+            Hittable::BVH(obj) => obj.object_count(),
+            Hittable::Sphere(obj) => obj.object_count(),
+            Hittable::MovingSphere(obj) => obj.object_count(),
+            Hittable::XYRect(obj) => obj.object_count(),
+            Hittable::XZRect(obj) => obj.object_count(),
+            Hittable::YZRect(obj) => obj.object_count(),
+            Hittable::AABox(obj) => obj.object_count(),
+            Hittable::Translate(obj) => obj.object_count(),
+            Hittable::RotateY(obj) => obj.object_count(),
+            Hittable::ConstantMedium(obj) => obj.object_count(),
+            Hittable::Triangle(obj) => obj.object_count(),
+            Hittable::Mesh(obj) => obj.object_count(),
+            Hittable::Environment(obj) => obj.object_count(),
+        }
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        match self {
+            Hittable::BVH(obj) => obj.collect_materials(materials),
+            Hittable::Sphere(obj) => obj.collect_materials(materials),
+            Hittable::MovingSphere(obj) => obj.collect_materials(materials),
+            Hittable::XYRect(obj) => obj.collect_materials(materials),
+            Hittable::XZRect(obj) => obj.collect_materials(materials),
+            Hittable::YZRect(obj) => obj.collect_materials(materials),
+            Hittable::AABox(obj) => obj.collect_materials(materials),
+            Hittable::Translate(obj) => obj.collect_materials(materials),
+            Hittable::RotateY(obj) => obj.collect_materials(materials),
+            Hittable::ConstantMedium(obj) => obj.collect_materials(materials),
+            Hittable::Triangle(obj) => obj.collect_materials(materials),
+            Hittable::Mesh(obj) => obj.collect_materials(materials),
+            Hittable::Environment(obj) => obj.collect_materials(materials),
+        }
+    }
+    fn is_light(&self) -> bool {
+        match self {
+            Hittable::BVH(obj) => obj.is_light(),
+            Hittable::Sphere(obj) => obj.is_light(),
+            Hittable::MovingSphere(obj) => obj.is_light(),
+            Hittable::XYRect(obj) => obj.is_light(),
+            Hittable::XZRect(obj) => obj.is_light(),
+            Hittable::YZRect(obj) => obj.is_light(),
+            Hittable::AABox(obj) => obj.is_light(),
+            Hittable::Translate(obj) => obj.is_light(),
+            Hittable::RotateY(obj) => obj.is_light(),
+            Hittable::ConstantMedium(obj) => obj.is_light(),
+            Hittable::Triangle(obj) => obj.is_light(),
+            Hittable::Mesh(obj) => obj.is_light(),
+            Hittable::Environment(obj) => obj.is_light(),
         }
     }
-}
\ No newline at end of file
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<Hittable>>) {
+        match self {
+            Hittable::BVH(obj) => obj.collect_lights(lights),
+            Hittable::Sphere(obj) => obj.collect_lights(lights),
+            Hittable::MovingSphere(obj) => obj.collect_lights(lights),
+            Hittable::XYRect(obj) => obj.collect_lights(lights),
+            Hittable::XZRect(obj) => obj.collect_lights(lights),
+            Hittable::YZRect(obj) => obj.collect_lights(lights),
+            Hittable::AABox(obj) => obj.collect_lights(lights),
+            Hittable::Translate(obj) => obj.collect_lights(lights),
+            Hittable::RotateY(obj) => obj.collect_lights(lights),
+            Hittable::ConstantMedium(obj) => obj.collect_lights(lights),
+            Hittable::Triangle(obj) => obj.collect_lights(lights),
+            Hittable::Mesh(obj) => obj.collect_lights(lights),
+            Hittable::Environment(obj) => obj.collect_lights(lights),
+        }
+    }
+
+    fn collect_statistics(&self, statistics: &mut Vec<BVHStatistics>) {
+        match self {
+            Hittable::BVH(obj) => obj.collect_statistics(statistics),
+            Hittable::Sphere(obj) => obj.collect_statistics(statistics),
+            Hittable::MovingSphere(obj) => obj.collect_statistics(statistics),
+            Hittable::XYRect(obj) => obj.collect_statistics(statistics),
+            Hittable::XZRect(obj) => obj.collect_statistics(statistics),
+            Hittable::YZRect(obj) => obj.collect_statistics(statistics),
+            Hittable::AABox(obj) => obj.collect_statistics(statistics),
+            Hittable::Translate(obj) => obj.collect_statistics(statistics),
+            Hittable::RotateY(obj) => obj.collect_statistics(statistics),
+            Hittable::ConstantMedium(obj) => obj.collect_statistics(statistics),
+            Hittable::Triangle(obj) => obj.collect_statistics(statistics),
+            Hittable::Mesh(obj) => obj.collect_statistics(statistics),
+            Hittable::Environment(obj) => obj.collect_statistics(statistics),
+        }
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        match self {
+            Hittable::BVH(obj) => obj.pdf_value(origin, direction),
+            Hittable::Sphere(obj) => obj.pdf_value(origin, direction),
+            Hittable::MovingSphere(obj) => obj.pdf_value(origin, direction),
+            Hittable::XYRect(obj) => obj.pdf_value(origin, direction),
+            Hittable::XZRect(obj) => obj.pdf_value(origin, direction),
+            Hittable::YZRect(obj) => obj.pdf_value(origin, direction),
+            Hittable::AABox(obj) => obj.pdf_value(origin, direction),
+            Hittable::Translate(obj) => obj.pdf_value(origin, direction),
+            Hittable::RotateY(obj) => obj.pdf_value(origin, direction),
+            Hittable::ConstantMedium(obj) => obj.pdf_value(origin, direction),
+            Hittable::Triangle(obj) => obj.pdf_value(origin, direction),
+            Hittable::Mesh(obj) => obj.pdf_value(origin, direction),
+            Hittable::Environment(obj) => obj.pdf_value(origin, direction),
+        }
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        match self {
+            Hittable::BVH(obj) => obj.random(origin),
+            Hittable::Sphere(obj) => obj.random(origin),
+            Hittable::MovingSphere(obj) => obj.random(origin),
+            Hittable::XYRect(obj) => obj.random(origin),
+            Hittable::XZRect(obj) => obj.random(origin),
+            Hittable::YZRect(obj) => obj.random(origin),
+            Hittable::AABox(obj) => obj.random(origin),
+            Hittable::Translate(obj) => obj.random(origin),
+            Hittable::RotateY(obj) => obj.random(origin),
+            Hittable::ConstantMedium(obj) => obj.random(origin),
+            Hittable::Triangle(obj) => obj.random(origin),
+            Hittable::Mesh(obj) => obj.random(origin),
+            Hittable::Environment(obj) => obj.random(origin),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        match self {
+            Hittable::BVH(obj) => obj.area(),
+            Hittable::Sphere(obj) => obj.area(),
+            Hittable::MovingSphere(obj) => obj.area(),
+            Hittable::XYRect(obj) => obj.area(),
+            Hittable::XZRect(obj) => obj.area(),
+            Hittable::YZRect(obj) => obj.area(),
+            Hittable::AABox(obj) => obj.area(),
+            Hittable::Translate(obj) => obj.area(),
+            Hittable::RotateY(obj) => obj.area(),
+            Hittable::ConstantMedium(obj) => obj.area(),
+            Hittable::Triangle(obj) => obj.area(),
+            Hittable::Mesh(obj) => obj.area(),
+            Hittable::Environment(obj) => obj.area(),
+        }
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        match self {
+            Hittable::BVH(obj) => obj.sample_surface(),
+            Hittable::Sphere(obj) => obj.sample_surface(),
+            Hittable::MovingSphere(obj) => obj.sample_surface(),
+            Hittable::XYRect(obj) => obj.sample_surface(),
+            Hittable::XZRect(obj) => obj.sample_surface(),
+            Hittable::YZRect(obj) => obj.sample_surface(),
+            Hittable::AABox(obj) => obj.sample_surface(),
+            Hittable::Translate(obj) => obj.sample_surface(),
+            Hittable::RotateY(obj) => obj.sample_surface(),
+            Hittable::ConstantMedium(obj) => obj.sample_surface(),
+            Hittable::Triangle(obj) => obj.sample_surface(),
+            Hittable::Mesh(obj) => obj.sample_surface(),
+            Hittable::Environment(obj) => obj.sample_surface(),
+        }
+    }
+}
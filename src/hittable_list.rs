@@ -1,10 +1,14 @@
 use std::sync::Arc;
+use crate::random_int;
 use crate::aabb::AABB;
+use crate::bvh::{add_traversal_steps, BVHStatistics};
 use crate::hit_record::HitRecord;
 use crate::hittable::{Hittable, HittableTrait};
+use crate::material::Material;
 use crate::ray::Ray;
+use crate::vec3::Vec3;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 /// Stores a list of Hittable objects
 pub struct HittableList {
     pub objects: Vec<Arc<Hittable>>
@@ -42,13 +46,18 @@ impl HittableTrait for HittableList {
         let mut hit_record: Option<HitRecord> = None;
         let mut closest_so_far = t_max;
 
+        // Each object's IDs follow on from those of the objects before it
+        let mut first_id = 0;
         for object in &self.objects {
-            if let Some(rec) = object.hit(ray, t_min, closest_so_far) {
+            if let Some(mut rec) = object.hit(ray, t_min, closest_so_far) {
                 closest_so_far = rec.t;
+                rec.object_id += first_id;
                 hit_record = Some(rec);
             }
+            first_id += object.object_count();
         }
 
+        add_traversal_steps(self.objects.len() as u64);
         hit_record
     }
 
@@ -76,4 +85,48 @@ impl HittableTrait for HittableList {
 
         output_box
     }
+
+    /// Gets the total number of objects in the list
+    fn object_count(&self) -> u32 {
+        self.objects.iter().map(|object| object.object_count()).sum()
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        for object in &self.objects {
+            object.collect_materials(materials);
+        }
+    }
+
+    fn collect_lights(&self, lights: &mut Vec<Arc<Hittable>>) {
+        for object in &self.objects {
+            if object.is_light() {
+                lights.push(Arc::clone(object));
+            } else {
+                object.collect_lights(lights);
+            }
+        }
+    }
+
+    fn collect_statistics(&self, statistics: &mut Vec<BVHStatistics>) {
+        for object in &self.objects {
+            object.collect_statistics(statistics);
+        }
+    }
+
+    /// Gets the density of picking an object uniformly and then a direction towards it
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        if self.objects.is_empty() {
+            return 0.;
+        }
+        let sum: f64 = self.objects.iter().map(|object| object.pdf_value(origin, direction)).sum();
+        sum / self.objects.len() as f64
+    }
+
+    /// Picks an object uniformly and then a direction towards it
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        if self.objects.is_empty() {
+            return Vec3::new(1., 0., 0.);
+        }
+        self.objects[random_int(0, self.objects.len() as i32 - 1) as usize].random(origin)
+    }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
-use image::{DynamicImage, GenericImageView, ImageResult};
-use ray_tracing::clamp;
+use image::{DynamicImage, GenericImageView};
+use crate::clamp;
 use crate::texture::{Texture, TextureTrait};
 use crate::vec3::Vec3;
 
@@ -7,54 +7,38 @@ const COLOR_SCALE: f64 = 1. / 255.;
 
 #[derive(Debug)]
 pub struct ImageTexture {
-    data: Option<DynamicImage>,
+    data: DynamicImage,
     width: u32,
     height: u32,
 }
 
 impl ImageTexture {
-    pub fn new(path: &str) -> Texture {
-        let width;
-        let height;
-        let data = match image::open(path) {
-            Ok(i) => {
-                width = i.width();
-                height = i.height();
-                Some(i)
-            },
-            Err(_) => {
-                width = 0;
-                height = 0;
-                println!("FAILED TO LOAD IMAGE {path}");
-                None
-            }
-        };
-
-        Texture::ImageTexture(ImageTexture { data, width, height })
+    /// Loads an image texture from a file, wrapped in the Texture enum, or reports why it
+    /// couldn't be read
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(path: &str) -> Result<Texture, String> {
+        let data = image::open(path).map_err(|e| format!("could not load '{path}': {e}"))?;
+        Ok(Texture::ImageTexture(ImageTexture { width: data.width(), height: data.height(), data }))
     }
 }
 
 impl TextureTrait for ImageTexture {
-    fn value(&self, u: f64, v: f64, p: &Vec3) -> Vec3 {
-        if let Some(data) = &self.data {
-            let u = clamp(u, 0., 1.);
-            let v = 1. - clamp(v, 0., 1.);
-
-            let mut i = (u * self.width as f64) as u32;
-            let mut j = (v * self.height as f64) as u32;
-
-            if i >= self.width { i = self.width - 1 }
-            if j >= self.height { j = self.height - 1 }
-
-            let pixel = data.get_pixel(i, j);
-
-            Vec3::new(
-                COLOR_SCALE * pixel[0] as f64,
-                COLOR_SCALE * pixel[1] as f64,
-                COLOR_SCALE * pixel[2] as f64
-            )
-        } else {
-            Vec3::new(0., 1., 1.)
-        }
+    fn value(&self, u: f64, v: f64, _: &Vec3) -> Vec3 {
+        let u = clamp(u, 0., 1.);
+        let v = 1. - clamp(v, 0., 1.);
+
+        let mut i = (u * self.width as f64) as u32;
+        let mut j = (v * self.height as f64) as u32;
+
+        if i >= self.width { i = self.width - 1 }
+        if j >= self.height { j = self.height - 1 }
+
+        let pixel = self.data.get_pixel(i, j);
+
+        Vec3::new(
+            COLOR_SCALE * pixel[0] as f64,
+            COLOR_SCALE * pixel[1] as f64,
+            COLOR_SCALE * pixel[2] as f64
+        )
     }
 }
\ No newline at end of file
@@ -2,6 +2,7 @@ use std::sync::Arc;
 use crate::hit_record::HitRecord;
 use crate::material::{Material, MaterialTrait};
 use crate::ray::Ray;
+use crate::scatter_record::{Lobe, ScatterRecord};
 use crate::solid_colour::SolidColour;
 use crate::texture::{Texture, TextureTrait};
 use crate::vec3::Vec3;
@@ -12,6 +13,7 @@ pub struct Isotropic {
 }
 
 impl Isotropic {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(albedo: Arc<Texture>) -> Material {
         Material::Isotropic(Isotropic { albedo })
     }
@@ -22,9 +24,14 @@ impl Isotropic {
 }
 
 impl MaterialTrait for Isotropic {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Vec3, Ray)> {
-        let attenuation = self.albedo.value(hit_record.u, hit_record.v, &hit_record.point);
-        let scattered = Ray::new(hit_record.point, Vec3::random_in_unit_sphere(), ray_in.time);
-        Some((attenuation, scattered))
+    /// Scatters light equally in every direction
+    fn scatter(&self, _: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Diffuse(Lobe::Uniform {
+            albedo: self.albedo.value(hit_record.u, hit_record.v, &hit_record.point),
+        }))
+    }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value(hit_record.u, hit_record.v, &hit_record.point)
     }
 }
\ No newline at end of file
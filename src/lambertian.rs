@@ -2,6 +2,7 @@ use std::sync::Arc;
 use crate::hit_record::HitRecord;
 use crate::material::{Material, MaterialTrait};
 use crate::ray::Ray;
+use crate::scatter_record::{Lobe, ScatterRecord};
 use crate::solid_colour::SolidColour;
 use crate::texture::{Texture, TextureTrait};
 use crate::vec3::Vec3;
@@ -14,6 +15,7 @@ pub struct Lambertian {
 
 impl Lambertian {
     /// Constructs a new Lambertian object from a given colour, wrapped in the Material enum
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(colour: Vec3) -> Material {
         Material::Lambertian(Lambertian { albedo: Arc::new(SolidColour::new(colour)) })
     }
@@ -25,18 +27,15 @@ impl Lambertian {
 }
 
 impl MaterialTrait for Lambertian {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Vec3, Ray)> {
-        // Gets the direction of the scattered ray, which bounces randomly away from the object
-        let mut scatter_direction = hit_record.normal + Vec3::random_unit_vector();
-
-        // Ignores scatter directions near zero to avoid impossible rays
-        if scatter_direction.near_zero() {
-            scatter_direction = hit_record.normal;
-        };
-
-        // Constructs scattered ray
-        let scattered = Ray::new(hit_record.point, scatter_direction, ray_in.time);
+    /// Scatters light with a cosine distribution about the normal
+    fn scatter(&self, _: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord::Diffuse(Lobe::Cosine {
+            albedo: self.albedo.value(hit_record.u, hit_record.v, &hit_record.point),
+            normal: hit_record.normal,
+        }))
+    }
 
-        Some((self.albedo.value(hit_record.u, hit_record.v, &hit_record.point), scattered))
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        self.albedo.value(hit_record.u, hit_record.v, &hit_record.point)
     }
 }
\ No newline at end of file
@@ -1,3 +1,110 @@
+// Public module declaration
+pub mod vec3;
+pub mod onb;
+pub mod ray;
+pub mod camera;
+pub mod hit_record;
+pub mod hittable;
+pub mod hittable_list;
+pub mod aabb;
+pub mod bvh;
+pub mod sphere;
+pub mod moving_sphere;
+pub mod aa_rect;
+pub mod aa_box;
+pub mod translate;
+pub mod rotate_y;
+pub mod constant_medium;
+pub mod triangle;
+pub mod mesh;
+pub mod obj_loader;
+pub mod material;
+pub mod scatter_record;
+pub mod lambertian;
+pub mod metal;
+pub mod dielectric;
+pub mod diffuse_light;
+pub mod isotropic;
+pub mod texture;
+pub mod solid_colour;
+pub mod checker_texture;
+pub mod perlin;
+pub mod noise_texture;
+pub mod image_texture;
+pub mod environment;
+pub mod scene;
+pub mod scenes;
+pub mod scene_file;
+pub mod checkpoint;
+pub mod output;
+pub mod render_buffer;
+pub mod tone_map;
+pub mod filter;
+pub mod aov;
+pub mod adaptive;
+pub mod denoise;
+pub mod bidirectional;
+pub mod photon_map;
+pub mod debug_view;
+pub mod spectrum;
+pub mod sampler;
+pub mod renderer;
+
+// Re-exporting the public API at the crate root
+pub use vec3::Vec3;
+pub use onb::ONB;
+pub use ray::Ray;
+pub use camera::Camera;
+pub use hit_record::HitRecord;
+pub use hittable::{Hittable, HittableTrait};
+pub use hittable_list::HittableList;
+pub use aabb::AABB;
+pub use bvh::{BVH, BVHBuildMethod, BVHStatistics};
+pub use sphere::Sphere;
+pub use moving_sphere::MovingSphere;
+pub use aa_rect::{XYRect, XZRect, YZRect};
+pub use aa_box::AABox;
+pub use translate::Translate;
+pub use rotate_y::RotateY;
+pub use constant_medium::ConstantMedium;
+pub use triangle::Triangle;
+pub use mesh::Mesh;
+pub use obj_loader::{load_obj, load_obj_list, load_obj_mesh};
+pub use material::{Material, MaterialTrait};
+pub use scatter_record::{Lobe, ScatterRecord};
+pub use lambertian::Lambertian;
+pub use metal::Metal;
+pub use dielectric::{Dielectric, RefractionIndex};
+pub use diffuse_light::DiffuseLight;
+pub use isotropic::Isotropic;
+pub use texture::{Texture, TextureTrait};
+pub use solid_colour::SolidColour;
+pub use checker_texture::CheckerTexture;
+pub use perlin::Perlin;
+pub use noise_texture::{NoiseTexture, NoiseType};
+pub use image_texture::ImageTexture;
+pub use environment::{Background, Environment};
+pub use scene::Scene;
+pub use scene_file::load_scene;
+pub use checkpoint::{load_checkpoint, save_checkpoint, CheckpointHeader, SceneSource};
+pub use output::{check_output_path, save_image, save_sample_heatmap, BitDepth, OutputSettings};
+pub use render_buffer::RenderBuffer;
+pub use tone_map::{ToneMap, ToneMapOperator, TransferFunction};
+pub use filter::{Filter, FilterShape};
+pub use aov::{Aov, AovPixel};
+pub use adaptive::{AdaptiveSettings, PixelVariance};
+pub use denoise::{denoise, DenoiseSettings};
+pub use bidirectional::{bidirectional_colour, LightSplat};
+pub use photon_map::{Photon, PhotonMap, PhotonSettings};
+pub use debug_view::{debug_colour, DebugView};
+pub use sampler::{Sampler, CAMERA_DIMENSIONS, VERTEX_DIMENSIONS};
+pub use spectrum::{cie_xyz, rgb_to_spectrum, xyz_to_rgb, SampledWavelengths};
+pub use renderer::{path_colour, photon_colour, ray_colour, spectral_colour, render, render_progressive, Integrator, RenderSettings};
+
+use std::cell::RefCell;
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
 pub const INFINITY: f64 = f64::INFINITY;    // Storing value for infinity
 pub const PI: f64 = std::f64::consts::PI;   // Storing value for PI
 
@@ -6,9 +113,30 @@ pub fn degrees_to_radians(degrees: f64) -> f64 {
     degrees * PI / 180.
 }
 
-/// Generates a random double between 0 and 1
+thread_local! {
+    // Random number generator used by the current thread
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+/// Seeds the current thread's random number generator, making its sequence reproducible. Any
+/// sample whose numbers were being drawn from a sampler is ended
+pub fn seed_random(seed: u64) {
+    sampler::end_sample();
+    RNG.with(|rng| *rng.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+/// Scrambles a seed with the SplitMix64 finaliser, so that nearby seeds give unrelated streams
+pub(crate) fn mix_seed(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Generates a random double between 0 and 1, taken from the current sample's sampler if it
+/// has one, or otherwise from the thread's random number generator
 pub fn random_double() -> f64 {
-    rand::random::<f64>()
+    sampler::next_sample().unwrap_or_else(|| RNG.with(|rng| rng.borrow_mut().gen::<f64>()))
 }
 
 /// Generates a random double in a given range
@@ -24,4 +152,4 @@ pub fn random_int(min: i32, max: i32) -> i32 {
 /// Clamps a given value between a minimum and maximum
 pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     if x < min { min } else if x > max { max } else { x }
-}
\ No newline at end of file
+}
@@ -1,575 +1,633 @@
-// Internal module declaration
-mod vec3;
-mod ray;
-mod camera;
-mod hit_record;
-mod hittable;
-mod hittable_list;
-mod aabb;
-mod bvh_node;
-mod sphere;
-mod moving_sphere;
-mod aa_rect;
-mod aa_box;
-mod translate;
-mod rotate_y;
-mod constant_medium;
-mod material;
-mod lambertian;
-mod metal;
-mod dielectric;
-mod diffuse_light;
-mod isotropic;
-mod texture;
-mod solid_colour;
-mod checker_texture;
-mod perlin;
-mod noise_texture;
-mod image_texture;
-
-// Importing own crate's module behaviour
+// Importing own crate's library
 use ray_tracing::*;
-use crate::vec3::Vec3;
-use crate::ray::Ray;
-use crate::camera::Camera;
-use crate::hittable::HittableTrait;
-use crate::hittable_list::HittableList;
-use crate::bvh_node::BVHNode;
-use crate::aabb::AABB;
-use crate::sphere::Sphere;
-use crate::moving_sphere::MovingSphere;
-use crate::aa_rect::{XYRect, XZRect, YZRect};
-use crate::aa_box::AABox;
-use crate::translate::Translate;
-use crate::rotate_y::RotateY;
-use crate::constant_medium::ConstantMedium;
-use crate::material::MaterialTrait;
-use crate::lambertian::Lambertian;
-use crate::metal::Metal;
-use crate::dielectric::Dielectric;
-use crate::diffuse_light::DiffuseLight;
-use crate::isotropic::Isotropic;
-use crate::texture::TextureTrait;
-use crate::solid_colour::SolidColour;
-use crate::checker_texture::CheckerTexture;
-use crate::image_texture::ImageTexture;
-use crate::noise_texture::{NoiseTexture, NoiseType};
+use ray_tracing::scenes::SCENES;
 
 // Importing other crates
-use std::sync::Arc;
-use rayon::prelude::*;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use clap::{Parser, ValueEnum};
 use indicatif::{ ProgressBar, ProgressStyle };
-use image::{ ImageBuffer, Rgb };
-
-/// Generates the final scene from 'Ray Tracing in a Weekend'
-fn in_a_weekend_scene() -> HittableList {
-    // Creates world list
-    let mut world = HittableList::new();
-
-    // Sets up ground
-    let ground_material = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
-    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&ground_material)));
-
-    // Generates random spheres
-    for a in -11..=11 {
-       for b in -11..=11 {
-           let choose_mat = random_double();
-           let centre = Vec3::new(
-               a as f64 + 0.9 * random_double(),
-               0.2,
-               b as f64 + 0.9 * random_double()
-           );
-           if (centre - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
-               if choose_mat < 0.8 {
-                   let albedo = Vec3::random(0., 1.) * Vec3::random(0., 1.);
-                   let sphere_material = Arc::new(Lambertian::new(albedo));
-                   world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
-               } else if choose_mat < 0.95 {
-                   let albedo = Vec3::random(0.5, 1.);
-                   let fuzz = random_range(0.0, 0.5);
-                   let sphere_material = Arc::new(Metal::new(albedo, fuzz));
-                   world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
-               } else {
-                   let sphere_material = Arc::new(Dielectric::new(1.5));
-                   world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
-               }
-           }
-        }
-    }
-
-    // Adds three main spheres
-    let material1 = Arc::new(Dielectric::new(1.5));
-    let material2 = Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)));
-    let material3 = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.));
-    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., Arc::clone(&material1)));
-    world.add(Sphere::new(Vec3::new(-4., 1., 0.), 1., Arc::clone(&material2)));
-    world.add(Sphere::new(Vec3::new(4., 1., 0.), 1., Arc::clone(&material3)));
 
-
-    HittableList::from_objects(vec![BVHNode::from_hittable_list(&world, 0., 1.)])
+/// Renders a built-in scene or a scene description file to an image file
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// Name of the scene to render (see --list-scenes)
+    #[arg(long, default_value = "cornell-box", value_parser = parse_scene_name)]
+    scene: String,
+
+    /// Path of a TOML scene description file to render instead of a built-in scene
+    #[arg(long, conflicts_with = "scene")]
+    scene_file: Option<PathBuf>,
+
+    /// Width of the output image in pixels
+    #[arg(long, default_value_t = 400, value_parser = clap::value_parser!(u32).range(2..))]
+    width: u32,
+
+    /// Height of the output image in pixels [default: same as width]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(2..))]
+    height: Option<u32>,
+
+    /// Number of samples taken for each pixel [default: 500, or the checkpoint's when resuming]
+    #[arg(short, long, value_parser = clap::value_parser!(i32).range(1..))]
+    samples: Option<i32>,
+
+    /// Number of bounces before Russian roulette can end a path, with paths carrying less light
+    /// more likely to end
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(i32).range(1..))]
+    min_depth: i32,
+
+    /// Hard cap on the number of bounces for each path, which darkens the image slightly by
+    /// cutting off the light of longer paths [default: none]
+    #[arg(short = 'd', long, value_parser = clap::value_parser!(i32).range(1..))]
+    max_depth: Option<i32>,
+
+    /// Path of the output image, with the format chosen by its extension. OpenEXR (.exr) and
+    /// Radiance HDR (.hdr) images store the full range of linear radiance
+    #[arg(short, long, default_value = "image.png", value_parser = parse_output_path)]
+    output: PathBuf,
+
+    /// Tone mapping operator used to compress radiance into displayable colours
+    #[arg(long, value_enum, default_value_t = ToneMapArg::Linear)]
+    tone_map: ToneMapArg,
+
+    /// Number of stops to brighten the image by before tone mapping, or darken if negative
+    #[arg(long, default_value_t = 0., allow_negative_numbers = true)]
+    exposure: f64,
+
+    /// Luminance mapped to pure white by the extended Reinhard operator
+    #[arg(long, default_value_t = 4., value_parser = parse_positive)]
+    white_point: f64,
+
+    /// Transfer function used to encode tone mapped colours
+    #[arg(long, value_enum, default_value_t = TransferArg::Srgb)]
+    transfer: TransferArg,
+
+    /// Bits per channel of PNG, TIFF and PNM images
+    #[arg(long, default_value = "8", value_parser = parse_bit_depth)]
+    bit_depth: BitDepth,
+
+    /// Dithers 8 and 16-bit images to hide banding in smooth gradients
+    #[arg(long)]
+    dither: bool,
+
+    /// Reconstruction filter used to weight each sample's contribution to nearby pixels
+    #[arg(long, value_enum, default_value_t = FilterArg::Box)]
+    filter: FilterArg,
+
+    /// Radius of the reconstruction filter in pixels [default: depends on the filter]
+    #[arg(long, value_parser = parse_positive)]
+    filter_radius: Option<f64>,
+
+    /// AOVs to save along with the image, as layers of OpenEXR images or otherwise as separate
+    /// images named after each AOV
+    #[arg(long, value_enum, value_delimiter = ',')]
+    aov: Vec<AovArg>,
+
+    /// Denoises the image and previews with a filter guided by the albedo, normals and depth of
+    /// the first surfaces seen, which are recorded while rendering
+    #[arg(long)]
+    denoise: bool,
+
+    /// Saves AOVs as separate images even when writing OpenEXR
+    #[arg(long, requires = "aov")]
+    separate_aovs: bool,
+
+    /// Samples pixels adaptively, stopping once the standard error of a pixel's luminance falls
+    /// below this fraction of its mean, with --samples as the most taken through any pixel
+    #[arg(long, value_parser = parse_positive)]
+    noise_threshold: Option<f64>,
+
+    /// Number of samples taken through every pixel before adaptive sampling can stop
+    #[arg(long, default_value_t = 16, requires = "noise_threshold", value_parser = clap::value_parser!(u32).range(2..))]
+    min_samples: u32,
+
+    /// Path to write a heatmap of the number of samples taken through each pixel to, with the
+    /// format chosen by its extension
+    #[arg(long, value_parser = parse_output_path)]
+    sample_heatmap: Option<PathBuf>,
+
+    /// Algorithm used to find the light carried along each ray
+    #[arg(long, value_enum, default_value_t = IntegratorArg::Path)]
+    integrator: IntegratorArg,
+
+    /// Way the random numbers of each pixel's samples are chosen, which low-discrepancy
+    /// samplers spread more evenly than independent ones
+    #[arg(long, value_enum, default_value_t = SamplerArg::Independent)]
+    sampler: SamplerArg,
+
+    /// Number of photons emitted from the lights for photon mapping
+    #[arg(long, default_value_t = 200_000, value_parser = clap::value_parser!(u64).range(1..))]
+    photons: u64,
+
+    /// Radius within which photon mapping gathers photons, in scene units [default: 0.4% of
+    /// the scene's size]
+    #[arg(long, value_parser = parse_positive)]
+    photon_radius: Option<f64>,
+
+    /// Distance within which objects occlude each other in the ambient occlusion view, in
+    /// scene units [default: 10% of the scene's size]
+    #[arg(long, value_parser = parse_positive)]
+    ao_radius: Option<f64>,
+
+    /// Number of boxes and objects tested by a ray which the traversal cost view shows as red
+    #[arg(long, default_value_t = 100, value_parser = clap::value_parser!(u32).range(1..))]
+    max_traversal_steps: u32,
+
+    /// Stops diffuse surfaces sampling lights directly with shadow rays, leaving every light to
+    /// be found by chance. Bidirectional path tracing needs light sampling, so can't be used
+    /// with this
+    #[arg(long)]
+    no_light_sampling: bool,
+
+    /// Width and height of the tiles each thread renders at a time, in pixels
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+    tile_size: u32,
+
+    /// Path to write a preview of the image to while rendering, with the format chosen by its
+    /// extension
+    #[arg(long, value_parser = parse_output_path)]
+    preview: Option<PathBuf>,
+
+    /// Minimum number of seconds between preview images, which are written after each pass of
+    /// the render (1, 4, 16, ... samples per pixel)
+    #[arg(long, default_value = "10", requires = "preview", value_parser = parse_interval)]
+    preview_interval: Duration,
+
+    /// Path to periodically save the render's progress to, so that it can be resumed
+    #[arg(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Minimum number of seconds between checkpoints, which are saved after each pass and when
+    /// the render finishes
+    #[arg(long, default_value = "60", value_parser = parse_interval)]
+    checkpoint_interval: Duration,
+
+    /// Path of a checkpoint to resume rendering from, taking more samples if --samples is
+    /// higher than the checkpoint's. Progress keeps being saved to the same file unless
+    /// --checkpoint is given
+    #[arg(long, conflicts_with_all = ["scene", "scene_file", "width", "height", "min_depth", "max_depth", "bvh", "seed", "filter", "filter_radius", "integrator", "sampler", "photons", "photon_radius", "ao_radius", "max_traversal_steps", "no_light_sampling"])]
+    resume: Option<PathBuf>,
+
+    /// Number of threads to render with [default: one per logical core]
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u16).range(1..))]
+    threads: Option<u16>,
+
+    /// Method used to build the scene's bounding volume hierarchies
+    #[arg(long, value_enum, default_value_t = BvhArg::RandomAxis)]
+    bvh: BvhArg,
+
+    /// Prints statistics about the scene's bounding volume hierarchies, including those nested
+    /// in other objects and within meshes, before rendering
+    #[arg(long)]
+    bvh_stats: bool,
+
+    /// Seed for the random numbers used to build and render the scene, making renders
+    /// reproducible [default: random]
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Lists the available scenes and exits
+    #[arg(long)]
+    list_scenes: bool,
 }
 
-/// Generates the camera for the 'Ray Tracing in a Weekend' scene
-fn in_a_weekend_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(13., 2., 3.);
-    let look_at = Vec3::new(0., 0., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.1;
-    *background =Vec3::new(0.7, 0.8, 1.);
-
-    Camera::new(
-        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
-    )
+/// BVH build methods which can be chosen from the command line
+#[derive(Clone, Copy, ValueEnum)]
+enum BvhArg {
+    /// Split at the median object along a random axis
+    RandomAxis,
+    /// Split using the binned surface area heuristic
+    Sah,
 }
 
-/// Generates scene with bouncing balls and a checkered texture
-fn bouncing_balls_scene() -> HittableList {
-    // Creates world list
-    let mut world = HittableList::new();
-
-    // Sets up ground
-    let texture = Arc::new(CheckerTexture::new(
-        Vec3::new(0.2, 0.3, 0.1), Vec3::new(0.9, 0.9, 0.9)));
-    let ground_material = Arc::new(Lambertian::from_texture(Arc::clone(&texture)));
-    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&ground_material)));
-
-    // Generates random spheres
-    for a in -11..=11 {
-        for b in -11..=11 {
-            let choose_mat = random_double();
-            let centre = Vec3::new(
-                a as f64 + 0.9 * random_double(),
-                0.2,
-                b as f64 + 0.9 * random_double()
-            );
-            if (centre - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    let albedo = Vec3::random(0., 1.) * Vec3::random(0., 1.);
-                    let sphere_material = Arc::new(Lambertian::new(albedo));
-                    let centre2 = centre + Vec3::new(0., random_range(0., 0.5), 0.);
-                    world.add(MovingSphere::new(centre, centre2, 0., 1., 0.2, Arc::clone(&sphere_material)));
-                } else if choose_mat < 0.95 {
-                    let albedo = Vec3::random(0.5, 1.);
-                    let fuzz = random_range(0.0, 0.5);
-                    let sphere_material = Arc::new(Metal::new(albedo, fuzz));
-                    world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
-                } else {
-                    let sphere_material = Arc::new(Dielectric::new(1.5));
-                    world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
-                }
-            }
+impl From<BvhArg> for BVHBuildMethod {
+    fn from(arg: BvhArg) -> Self {
+        match arg {
+            BvhArg::RandomAxis => BVHBuildMethod::RandomAxis,
+            BvhArg::Sah => BVHBuildMethod::SAH,
         }
     }
-
-    // Adds three main spheres
-    let material1 = Arc::new(Dielectric::new(1.5));
-    let material2 = Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)));
-    let material3 = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.));
-    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., Arc::clone(&material1)));
-    world.add(Sphere::new(Vec3::new(-4., 1., 0.), 1., Arc::clone(&material2)));
-    world.add(Sphere::new(Vec3::new(4., 1., 0.), 1., Arc::clone(&material3)));
-
-    HittableList::from_objects(vec![BVHNode::from_hittable_list(&world, 0., 1.)])
 }
 
-/// Generates camera for bouncing balls scene
-fn bouncing_balls_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(13., 2., 3.);
-    let look_at = Vec3::new(0., 0., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.1;
-    *background = Vec3::new(0.7, 0.8, 1.);
-
-    Camera::new(
-        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 1.
-    )
+/// Checks that a scene name is one of the built-in scenes
+fn parse_scene_name(name: &str) -> Result<String, String> {
+    if SCENES.iter().any(|(scene, _)| *scene == name) {
+        Ok(name.to_string())
+    } else {
+        let names: Vec<&str> = SCENES.iter().map(|(scene, _)| *scene).collect();
+        Err(format!("unknown scene '{name}', expected one of: {}", names.join(", ")))
+    }
 }
 
-/// Generates a scene with two checkered spheres
-fn two_spheres_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let checker = Arc::new(CheckerTexture::new(
-        Vec3::new(0.2, 0.3, 0.1), Vec3::new(0.9, 0.9, 0.9)));
-    let material = Arc::new(Lambertian::from_texture(Arc::clone(&checker)));
-    world.add(Sphere::new(Vec3::new(0., -10., 0.), 10., Arc::clone(&material)));
-    world.add(Sphere::new(Vec3::new(0., 10., 0.), 10., Arc::clone(&material)));
-
-    world
+/// Tone mapping operators which can be chosen from the command line
+#[derive(Clone, Copy, ValueEnum)]
+enum ToneMapArg {
+    /// Scale by the exposure and clip anything brighter than white
+    Linear,
+    /// Reinhard's operator, which approaches white for infinite luminance
+    Reinhard,
+    /// Reinhard's operator, reaching white at --white-point
+    ExtendedReinhard,
+    /// Filmic curve fitted to the ACES reference rendering transform
+    Aces,
 }
 
-/// Generates the camera for the two spheres scene
-fn two_spheres_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(13., 2., 3.);
-    let look_at = Vec3::new(0., 0., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.0;
-    *background =Vec3::new(0.7, 0.8, 1.);
-
-    Camera::new(
-        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
-    )
+impl From<ToneMapArg> for ToneMapOperator {
+    fn from(arg: ToneMapArg) -> Self {
+        match arg {
+            ToneMapArg::Linear => ToneMapOperator::Linear,
+            ToneMapArg::Reinhard => ToneMapOperator::Reinhard,
+            ToneMapArg::ExtendedReinhard => ToneMapOperator::ExtendedReinhard,
+            ToneMapArg::Aces => ToneMapOperator::Aces,
+        }
+    }
 }
 
-/// Generates a scene with two perlin noise spheres
-fn two_perlin_spheres_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let perlin_texture = Arc::new(NoiseTexture::new(4., NoiseType::Marbled));
-    let material = Arc::new(Lambertian::from_texture(
-        Arc::clone(&perlin_texture)));
-    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&material)));
-    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., Arc::clone(&material)));
-
-    world
+/// Transfer functions which can be chosen from the command line
+#[derive(Clone, Copy, ValueEnum)]
+enum TransferArg {
+    /// The standard sRGB curve
+    Srgb,
+    /// A square root, matching the original renderer
+    Gamma2,
 }
 
-/// Generates the camera for the two perlin spheres scene
-fn two_perlin_spheres_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(13., 2., 3.);
-    let look_at = Vec3::new(0., 0., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.0;
-    *background = Vec3::new(0.7, 0.8, 1.);
-
-    Camera::new(
-        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
-    )
+impl From<TransferArg> for TransferFunction {
+    fn from(arg: TransferArg) -> Self {
+        match arg {
+            TransferArg::Srgb => TransferFunction::Srgb,
+            TransferArg::Gamma2 => TransferFunction::Gamma2,
+        }
+    }
 }
 
-/// Generates scene with a sphere using an earth image texture
-fn earth_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let earth_texture = Arc::new(ImageTexture::new("earthmap.jpg"));
-    let earth_material = Arc::new(Lambertian::from_texture(Arc::clone(&earth_texture)));
-    world.add(Sphere::new(Vec3::zero(), 2., Arc::clone(&earth_material)));
-
-    world
+/// AOVs which can be chosen from the command line
+#[derive(Clone, Copy, ValueEnum)]
+enum AovArg {
+    /// Base colour of the first surface hit
+    Albedo,
+    /// Shading normal of the first surface hit
+    Normal,
+    /// Distance from the camera to the first surface hit
+    Depth,
+    /// World space position of the first surface hit
+    Position,
+    /// Texture coordinates of the first surface hit
+    Uv,
+    /// Index of the first object hit
+    ObjectId,
+    /// Index of the material of the first surface hit
+    MaterialId,
 }
 
-/// Generates the camera for the earth scene
-fn earth_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(13., 2., 3.);
-    let look_at = Vec3::new(0., 0., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.0;
-    *background =Vec3::new(0.7, 0.8, 1.);
-
-    Camera::new(
-        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
-    )
+impl From<AovArg> for Aov {
+    fn from(arg: AovArg) -> Self {
+        match arg {
+            AovArg::Albedo => Aov::Albedo,
+            AovArg::Normal => Aov::Normal,
+            AovArg::Depth => Aov::Depth,
+            AovArg::Position => Aov::Position,
+            AovArg::Uv => Aov::Uv,
+            AovArg::ObjectId => Aov::ObjectId,
+            AovArg::MaterialId => Aov::MaterialId,
+        }
+    }
 }
 
-/// Generates scene with a simple light
-fn simple_light_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let perlin_texture = Arc::new(NoiseTexture::new(4., NoiseType::Marbled));
-    let material = Arc::new(Lambertian::from_texture(
-        Arc::clone(&perlin_texture)));
-    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&material)));
-    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., Arc::clone(&material)));
-
-    let light_mat = Arc::new(DiffuseLight::from_colour(4., 4., 4.));
-    world.add(XYRect::new(3., 5., 1., 3., -2., Arc::clone(&light_mat)));
-    world.add(Sphere::new(Vec3::new(0., 6.5, 0.), 2., Arc::clone(&light_mat)));
-
-    world
+/// Reconstruction filters which can be chosen from the command line
+#[derive(Clone, Copy, ValueEnum)]
+enum FilterArg {
+    /// Weights samples within half a pixel equally, averaging each pixel's own samples
+    Box,
+    /// Weights samples linearly by their distance from the pixel's centre
+    Tent,
+    /// Smooth Gaussian falloff, which slightly blurs the image
+    Gaussian,
+    /// Mitchell-Netravali cubic, balancing sharpness against ringing
+    Mitchell,
+    /// Windowed sinc, the sharpest filter but prone to ringing around bright edges
+    Lanczos,
 }
 
-/// Generates the camera for the simple light scene
-fn simple_light_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(26., 3., 6.);
-    let look_at = Vec3::new(0., 2., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.0;
-    *background = Vec3::zero();
-
-    Camera::new(
-        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
-    )
+impl From<FilterArg> for FilterShape {
+    fn from(arg: FilterArg) -> Self {
+        match arg {
+            FilterArg::Box => FilterShape::Box,
+            FilterArg::Tent => FilterShape::Tent,
+            FilterArg::Gaussian => FilterShape::Gaussian,
+            FilterArg::Mitchell => FilterShape::Mitchell,
+            FilterArg::Lanczos => FilterShape::Lanczos,
+        }
+    }
 }
 
-/// Generates Cornell Box scene
-fn cornell_box_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let red = Arc::new(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
-    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
-    let green = Arc::new(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
-    let light = Arc::new(DiffuseLight::from_colour(15., 15., 15.));
-
-    world.add(YZRect::new(0., 555., 0., 555., 555., Arc::clone(&green)));
-    world.add(YZRect::new(0., 555., 0., 555., 0., Arc::clone(&red)));
-    world.add(XZRect::new(213., 343., 227., 332., 554., Arc::clone(&light)));
-    world.add(XZRect::new(0., 555., 0., 555., 0., Arc::clone(&white)));
-    world.add(XZRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
-    world.add(XYRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
-
-    let box1 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 330., 165.), Arc::clone(&white)));
-    let box1 = Arc::new(RotateY::new(Arc::clone(&box1), 15.));
-    world.add(Translate::new(Arc::clone(&box1), Vec3::new(265., 0., 295.)));
-
-    let box2 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 165., 165.), Arc::clone(&white)));
-    let box2 = Arc::new(RotateY::new(Arc::clone(&box2), -18.));
-    world.add(Translate::new(Arc::clone(&box2), Vec3::new(130., 0., 65.)));
-
-    world
+/// Samplers which can be chosen from the command line
+#[derive(Clone, Copy, ValueEnum)]
+enum SamplerArg {
+    /// Independent random numbers, converging purely by Monte Carlo
+    Independent,
+    /// One number in each of as many strata as there are samples per pixel, so renders resumed
+    /// with more samples lose some of the benefit
+    Stratified,
+    /// Randomly shifted Halton sequence, well spread in the first dimensions of each path
+    Halton,
+    /// Owen-scrambled Sobol sequence, well spread in every pair of dimensions
+    Sobol,
 }
 
-/// Generates the camera for the Cornell Box scene
-fn cornell_box_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(278., 278., -800.);
-    let look_at = Vec3::new(278., 278., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.0;
-    *background = Vec3::zero();
-
-    Camera::new(
-        look_from, look_at, up, 40., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
-    )
+impl From<SamplerArg> for Sampler {
+    fn from(arg: SamplerArg) -> Self {
+        match arg {
+            SamplerArg::Independent => Sampler::Independent,
+            SamplerArg::Stratified => Sampler::Stratified,
+            SamplerArg::Halton => Sampler::Halton,
+            SamplerArg::Sobol => Sampler::Sobol,
+        }
+    }
 }
 
-/// Generates Cornell Box scene with fog
-fn cornell_box_smoke_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let red = Arc::new(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
-    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
-    let green = Arc::new(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
-    let light = Arc::new(DiffuseLight::from_colour(7., 7., 7.));
-
-    world.add(YZRect::new(0., 555., 0., 555., 555., Arc::clone(&green)));
-    world.add(YZRect::new(0., 555., 0., 555., 0., Arc::clone(&red)));
-    world.add(XZRect::new(113., 443., 127., 432., 554., Arc::clone(&light)));
-    world.add(XZRect::new(0., 555., 0., 555., 0., Arc::clone(&white)));
-    world.add(XZRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
-    world.add(XYRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
-
-    let box1 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 330., 165.), Arc::clone(&white)));
-    let box1 = Arc::new(RotateY::new(Arc::clone(&box1), 15.));
-    let box1 = Arc::new(Translate::new(Arc::clone(&box1), Vec3::new(265., 0., 295.)));
-
-    let box2 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 165., 165.), Arc::clone(&white)));
-    let box2 = Arc::new(RotateY::new(Arc::clone(&box2), -18.));
-    let box2 = Arc::new(Translate::new(Arc::clone(&box2), Vec3::new(130., 0., 65.)));
-
-    world.add(ConstantMedium::from_colour(Arc::clone(&box1), 0.01, Vec3::zero()));
-    world.add(ConstantMedium::from_colour(Arc::clone(&box2), 0.01, Vec3::one()));
+/// Integrators and debug views which can be chosen from the command line
+#[derive(Clone, Copy, ValueEnum)]
+enum IntegratorArg {
+    /// Path tracing from the camera, sampling lights directly at diffuse bounces
+    Path,
+    /// Bidirectional path tracing, joining paths from the camera and lights, which finds
+    /// caustics that path tracing misses
+    Bidirectional,
+    /// Path tracing which gathers caustics from a photon map at diffuse surfaces
+    Photon,
+    /// Path tracing of sampled wavelengths of light rather than RGB, which splits light
+    /// through dispersive glass into its colours
+    Spectral,
+    /// Shading normals of the first surfaces seen
+    Normal,
+    /// Texture coordinates of the first surfaces seen
+    Uv,
+    /// Distance to the first surfaces seen, relative to the scene's size
+    Distance,
+    /// A distinct colour for each material
+    MaterialId,
+    /// Green where the outside of a surface is seen and red where the inside is
+    FrontFace,
+    /// Ambient occlusion within --ao-radius
+    AmbientOcclusion,
+    /// Heatmap of the boxes and objects each camera ray is tested against
+    TraversalCost,
+}
 
-    world
+/// Parses a number which must be greater than zero
+fn parse_positive(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(value) if value > 0. => Ok(value),
+        Ok(_) => Err("must be greater than zero".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
 }
 
-/// Generates the camera for the Cornell Box scene
-fn cornell_box_smoke_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    cornell_box_camera(aspect_ratio, background)
+/// Parses a number of seconds, which must be finite and not negative
+fn parse_interval(seconds: &str) -> Result<Duration, String> {
+    let seconds = seconds.parse::<f64>().map_err(|e| e.to_string())?;
+    Duration::try_from_secs_f64(seconds).map_err(|_| "must be a finite number of seconds, not below zero".to_string())
 }
 
-/// Generates the final scene of 'Ray Tracing The Next Week'
-fn the_next_week_scene() -> HittableList {
-    let mut world = HittableList::new();
-
-    let ground = Arc::new(Lambertian::new(Vec3::new(0.48, 0.83, 0.53)));
-
-    let mut boxes1 = HittableList::new();
-    const BOXES_PER_SIDE: i32 = 20;
-    for i in 0..BOXES_PER_SIDE {
-        for j in 0..BOXES_PER_SIDE {
-            let w = 100.;
-            let x0 = -1000. + i as f64 * w;
-            let y0 = 0.;
-            let z0 = -1000. + j as f64 * w;
-            let x1 = x0 + w;
-            let y1 = random_range(1., 101.);
-            let z1 = z0 + w;
-
-            boxes1.add(AABox::new(
-                Vec3::new(x0, y0, z0), Vec3::new(x1, y1, z1),
-                Arc::clone(&ground))
-            );
-        }
+/// Parses the bit depth of integer output images
+fn parse_bit_depth(depth: &str) -> Result<BitDepth, String> {
+    match depth {
+        "8" => Ok(BitDepth::Eight),
+        "16" => Ok(BitDepth::Sixteen),
+        _ => Err(format!("unsupported bit depth '{depth}', expected 8 or 16")),
     }
+}
 
-    world.add(BVHNode::from_hittable_list(&boxes1, 0., 1.));
-
-    let light = Arc::new(DiffuseLight::from_colour(7., 7., 7.));
-    world.add(XZRect::new(123., 423., 147., 412., 554., Arc::clone(&light)));
-
-    let centre1 = Vec3::new(400., 400., 200.);
-    let centre2 = centre1 + Vec3::new(30., 0., 0.);
-    let moving_sphere_mat = Arc::new(Lambertian::new(Vec3::new(0.7, 0.3, 0.1)));
-    world.add(MovingSphere::new(centre1, centre2, 0., 1., 50., Arc::clone(&moving_sphere_mat)));
-
-    let glass = Arc::new(Dielectric::new(1.5));
-    world.add(Sphere::new(Vec3::new(260., 150., 45.), 50., Arc::clone(&glass)));
-    world.add(Sphere::new(Vec3::new(0., 150., 145.), 50., Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.9), 1.))));
-
-    let boundary = Arc::new(Sphere::new(Vec3::new(360., 150., 145.), 70., Arc::clone(&glass)));
-    world.add_arc(Arc::clone(&boundary));
-    world.add(ConstantMedium::from_colour(Arc::clone(&boundary), 0.2, Vec3::new(0.2, 0.4, 0.9)));
-    let boundary = Arc::new(Sphere::new(Vec3::new(0., 0., 0.), 5000., Arc::clone(&glass)));
-    world.add(ConstantMedium::from_colour(Arc::clone(&boundary), 0.0001, Vec3::one()));
+/// Checks that an output path has an image format extension which can be written to
+fn parse_output_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    check_output_path(&path, &OutputSettings::default())?;
+    Ok(path)
+}
 
-    let earth_mat = Arc::new(Lambertian::from_texture(Arc::new(ImageTexture::new("earthmap.jpg"))));
-    world.add(Sphere::new(Vec3::new(400., 200., 400.), 100., Arc::clone(&earth_mat)));
-    let noise_mat = Arc::new(Lambertian::from_texture( Arc::new(NoiseTexture::new(0.1, NoiseType::Marbled))));
-    world.add(Sphere::new(Vec3::new(220., 280., 300.), 80., Arc::clone(&noise_mat)));
+fn main() -> ExitCode {
+    let args = Args::parse();
 
-    let mut boxes2 = HittableList::new();
-    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
-    for _ in 0..1000 {
-        boxes2.add(Sphere::new(Vec3::random(0., 165.), 10., Arc::clone(&white)));
+    if args.list_scenes {
+        for (name, description) in SCENES {
+            println!("{name:<20} {description}");
+        }
+        return ExitCode::SUCCESS;
     }
 
-    world.add(Translate::new(
-        Arc::new(RotateY::new(
-            Arc::new(BVHNode::from_hittable_list(&boxes2, 0., 1.)), 15.
-        )),
-        Vec3::new(-100., 270., 395.))
-    );
-
-    HittableList::from_objects(vec![BVHNode::from_hittable_list(&world, 0., 1.)])
-}
-
-/// Generates the camera for the 'Ray Tracing The Next Week' scene
-fn the_next_week_camera(aspect_ratio: f64, background: &mut Vec3) -> Camera {
-    let look_from = Vec3::new(478., 278., -600.);
-    let look_at = Vec3::new(278., 278., 0.);
-    let up = Vec3::new(0., 1., 0.);
-    let dist_to_focus = 10.;
-    let aperture = 0.0;
-    *background = Vec3::zero();
-
-    Camera::new(
-        look_from, look_at, up, 40., aperture, dist_to_focus, aspect_ratio, 2., 0., 1.
-    )
-}
+    // Checks the output formats support the bit depth before spending time rendering
+    let output_settings = OutputSettings {
+        tone_map: ToneMap {
+            operator: ToneMapOperator::from(args.tone_map),
+            exposure: args.exposure,
+            white_point: args.white_point,
+            transfer: TransferFunction::from(args.transfer),
+        },
+        bit_depth: args.bit_depth,
+        dither: args.dither,
+        aovs: args.aov.iter().map(|&aov| Aov::from(aov)).collect(),
+        separate_aovs: args.separate_aovs,
+        denoise: args.denoise.then(DenoiseSettings::default),
+    };
+    let record_aovs = !output_settings.aovs.is_empty() || output_settings.denoise.is_some();
+    let adaptive = args.noise_threshold.map(|noise_threshold| AdaptiveSettings { noise_threshold, min_samples: args.min_samples });
+    let integrator = match args.integrator {
+        IntegratorArg::Path => Integrator::Path,
+        IntegratorArg::Bidirectional => Integrator::Bidirectional,
+        IntegratorArg::Photon =>
+            Integrator::PhotonMapping(PhotonSettings { photons: args.photons as usize, radius: args.photon_radius }),
+        IntegratorArg::Spectral => Integrator::Spectral,
+        IntegratorArg::Normal => Integrator::Debug(DebugView::Normal),
+        IntegratorArg::Uv => Integrator::Debug(DebugView::Uv),
+        IntegratorArg::Distance => Integrator::Debug(DebugView::Distance),
+        IntegratorArg::MaterialId => Integrator::Debug(DebugView::MaterialId),
+        IntegratorArg::FrontFace => Integrator::Debug(DebugView::FrontFace),
+        IntegratorArg::AmbientOcclusion => Integrator::Debug(DebugView::AmbientOcclusion { radius: args.ao_radius }),
+        IntegratorArg::TraversalCost =>
+            Integrator::Debug(DebugView::TraversalCost { max_steps: args.max_traversal_steps }),
+    };
 
-/// Gets the colour of a given ray in the world
-fn ray_colour(ray: &Ray, background: Vec3, world: &HittableList, depth: i32) -> Vec3 {
-    // Stops recursion once past the max depth
-    if depth <= 0 {
-        return Vec3::zero();
+    // Light subpaths start from the sampled lights, so without them every one would be lost
+    if integrator == Integrator::Bidirectional && args.no_light_sampling {
+        eprintln!("Error: bidirectional path tracing needs light sampling, so can't be used with --no-light-sampling.");
+        return ExitCode::FAILURE;
+    }
+    for path in std::iter::once(&args.output).chain(&args.preview).chain(&args.sample_heatmap) {
+        if let Err(e) = check_output_path(path, &output_settings) {
+            eprintln!("Error: {}", e);
+            return ExitCode::FAILURE;
+        }
     }
 
-    // Checks if the ray hit anything in the world
-    if let Some(hit_record) = world.hit(ray, 0.001, INFINITY) {
-        let emitted = hit_record.material.emitted(hit_record.u, hit_record.v, &hit_record.point);
-
-        // If the ray hit anything, return the result of the scattered ray against the
-        // hit's material
-        match hit_record.material.scatter(ray, &hit_record) {
-            Some((attenuation, scattered)) =>
-                emitted + attenuation * ray_colour(&scattered, background, world, depth - 1),
-            None => emitted
+    if let Some(threads) = args.threads {
+        if let Err(e) = rayon::ThreadPoolBuilder::new().num_threads(threads as usize).build_global() {
+            eprintln!("Error setting up thread pool: {}", e);
+            return ExitCode::FAILURE;
         }
-    } else {
-        background
     }
-}
 
-fn main() {
-    // ---- IMAGE SETUP ----
-    const ASPECT_RATIO: f64 = 1.;
-    const IMAGE_WIDTH: usize = 400;
-    const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as usize;
-    const SAMPLES_PER_PIXEL: i32 = 500;
-    const MAX_DEPTH: i32 = 50;
-
-    // ---- WORLD SETUP ----
-    const WORLD_TYPE: usize = 7;
-    let world = match WORLD_TYPE {
-        1 => in_a_weekend_scene(),
-        2 => bouncing_balls_scene(),
-        3 => two_spheres_scene(),
-        4 => two_perlin_spheres_scene(),
-        5 => earth_scene(),
-        6 => simple_light_scene(),
-        7 => cornell_box_scene(),
-        8 => cornell_box_smoke_scene(),
-        _ => the_next_week_scene(),
-    };
+    // ---- RENDER SETUP ----
 
-    // ---- CAMERA SETUP ----
-    let mut background = Vec3::zero();
-    let camera = match WORLD_TYPE {
-        1 => in_a_weekend_camera(ASPECT_RATIO, &mut background),
-        2 => bouncing_balls_camera(ASPECT_RATIO, &mut background),
-        3 => two_spheres_camera(ASPECT_RATIO, &mut background),
-        4 => two_perlin_spheres_camera(ASPECT_RATIO, &mut background),
-        5 => earth_camera(ASPECT_RATIO, &mut background),
-        6 => simple_light_camera(ASPECT_RATIO, &mut background),
-        7 => cornell_box_camera(ASPECT_RATIO, &mut background),
-        8 => cornell_box_smoke_camera(ASPECT_RATIO, &mut background),
-        _ => the_next_week_camera(ASPECT_RATIO, &mut background),
+    // Resumed renders take their scene, settings and progress from the checkpoint
+    let (header, mut buffer) = match &args.resume {
+        Some(path) => match load_checkpoint(path) {
+            Ok((mut header, buffer)) => {
+                if let Some(samples) = args.samples {
+                    header.settings.samples_per_pixel = samples;
+                }
+                header.settings.tile_size = args.tile_size as usize;
+                if adaptive.is_some() {
+                    header.settings.adaptive = adaptive;
+                }
+                if record_aovs && !header.settings.aovs {
+                    eprintln!("Error: the checkpoint was rendered without AOVs, which AOV outputs and denoising need.");
+                    return ExitCode::FAILURE;
+                }
+                (header, buffer)
+            },
+            Err(e) => {
+                eprintln!("Error loading checkpoint: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => {
+            let image_width = args.width as usize;
+            let image_height = args.height.unwrap_or(args.width) as usize;
+            let seed = match args.seed {
+                Some(seed) => seed,
+                None => {
+                    let seed = rand::random();
+                    eprintln!("Using random seed {seed}.");
+                    seed
+                }
+            };
+            let header = CheckpointHeader {
+                scene: match &args.scene_file {
+                    Some(path) => SceneSource::File(path.clone()),
+                    None => SceneSource::BuiltIn(args.scene.clone()),
+                },
+                bvh_method: BVHBuildMethod::from(args.bvh),
+                settings: RenderSettings {
+                    image_width,
+                    image_height,
+                    samples_per_pixel: args.samples.unwrap_or(500),
+                    min_depth: args.min_depth,
+                    max_depth: args.max_depth,
+                    seed,
+                    tile_size: args.tile_size as usize,
+                    filter: {
+                        let shape = FilterShape::from(args.filter);
+                        Filter { shape, radius: args.filter_radius.unwrap_or(shape.default_radius()) }
+                    },
+                    aovs: record_aovs,
+                    adaptive,
+                    light_sampling: !args.no_light_sampling,
+                    integrator,
+                    sampler: Sampler::from(args.sampler),
+                },
+            };
+            let buffer = match header.settings.aovs {
+                true => RenderBuffer::with_aovs(image_width, image_height),
+                false => RenderBuffer::new(image_width, image_height),
+            };
+            (header, buffer)
+        }
+    };
+    let settings = &header.settings;
+    let checkpoint_path = args.checkpoint.as_ref().or(args.resume.as_ref());
+
+    // ---- SCENE SETUP ----
+    seed_random(settings.seed);
+    let aspect_ratio = settings.image_width as f64 / settings.image_height as f64;
+    let scene = match header.scene.build(aspect_ratio, header.bvh_method) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("Error loading scene: {}", e);
+            return ExitCode::FAILURE;
+        }
     };
 
+    if args.bvh_stats {
+        let mut statistics = vec![];
+        scene.world.collect_statistics(&mut statistics);
+        for hierarchy in &statistics {
+            eprintln!("{}:\n{}\n", hierarchy.description, hierarchy);
+        }
+        if statistics.is_empty() {
+            eprintln!("The scene has no bounding volume hierarchies.\n");
+        }
+    }
+
     // ---- RENDERING THE SCENE ----
 
     // Sets up progress bar
-    let progress_bar_style = ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {human_pos}/{human_len} ({eta})")
+    let progress_bar_style = ProgressStyle::with_template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {human_pos}/{human_len} samples, pass {msg} ({eta})")
         .unwrap()
         .progress_chars("#>-");
-    let render_progress_bar = ProgressBar::new((IMAGE_WIDTH * IMAGE_HEIGHT) as u64);
-    render_progress_bar.set_style(progress_bar_style.clone());
-
-    // Create pixels array to store an RGB value for each pixel in the image
-    let mut pixels = vec![Rgb([0 as u8, 0 as u8, 0 as u8]); IMAGE_HEIGHT * IMAGE_WIDTH];
-
-    // Loop through each pixel and calculate their colour in parallel
-    pixels.par_iter_mut().enumerate().for_each(|(i, pixel)| {
-        let mut pixel_colour = Vec3::zero();
-
-        // Get pixel's x and y position from index
-        let y = IMAGE_HEIGHT - 1 - (i / IMAGE_WIDTH);
-        let x = i % IMAGE_WIDTH;
-
-        // Calculate the colour at each sample
-        for _ in 0..SAMPLES_PER_PIXEL {
-            let u = (x as f64 + random_double()) / (IMAGE_WIDTH - 1) as f64;
-            let v = (y as f64 + random_double()) / (IMAGE_HEIGHT - 1) as f64;
-            let r = camera.get_ray(u, v);
-            pixel_colour += ray_colour(&r, background, &world, MAX_DEPTH);
+    let total_samples = (settings.image_width * settings.image_height) as u64 * settings.samples_per_pixel as u64;
+    let render_progress_bar = ProgressBar::new(total_samples);
+    render_progress_bar.set_style(progress_bar_style);
+    // Pixels which have already converged count as having every sample
+    let samples_taken = (0..buffer.samples.len()).map(|i| match settings.adaptive {
+        Some(adaptive) if adaptive.converged(&buffer.variance[i], buffer.samples[i]) => settings.samples_per_pixel as u64,
+        _ => buffer.samples[i] as u64,
+    }).sum::<u64>();
+    render_progress_bar.set_position(samples_taken.min(total_samples));
+
+    // Writes previews and checkpoints after each pass, as long as enough time has passed
+    // since the last one
+    let preview_interval = args.preview_interval;
+    let checkpoint_interval = args.checkpoint_interval;
+    let preview_settings = OutputSettings { aovs: vec![], ..output_settings.clone() };
+    let mut last_preview = Instant::now();
+    let mut last_checkpoint = Instant::now();
+    render_progressive(&scene, settings, &mut buffer, &render_progress_bar, |buffer| {
+        if let Some(path) = &args.preview {
+            if last_preview.elapsed() >= preview_interval {
+                if let Err(e) = save_image(buffer, path, &preview_settings) {
+                    render_progress_bar.suspend(|| eprintln!("Error writing preview: {}", e));
+                }
+                last_preview = Instant::now();
+            }
+        }
+        if let Some(path) = checkpoint_path {
+            if last_checkpoint.elapsed() >= checkpoint_interval {
+                if let Err(e) = save_checkpoint(path, &header, buffer) {
+                    render_progress_bar.suspend(|| eprintln!("Error saving checkpoint: {}", e));
+                }
+                last_checkpoint = Instant::now();
+            }
         }
-
-        // Averages pixel colour over all samples
-        pixel_colour /= SAMPLES_PER_PIXEL as f64;
-
-        // Saves the gamma corrected colour value in the range [0,255] to the pixel array
-        *pixel = Rgb([
-            (256. * clamp(f64::sqrt(pixel_colour.x), 0., 0.999)) as u8,
-            (256. * clamp(f64::sqrt(pixel_colour.y), 0., 0.999)) as u8,
-            (256. * clamp(f64::sqrt(pixel_colour.z), 0., 0.999)) as u8,
-        ]);
-
-        // Increment progress bar
-        render_progress_bar.inc(1);
     });
+    render_progress_bar.finish();
+
+    // The final checkpoint lets more samples be added later
+    if let Some(path) = checkpoint_path {
+        if let Err(e) = save_checkpoint(path, &header, &buffer) {
+            eprintln!("Error saving checkpoint: {}", e);
+        }
+    }
 
     // ---- SAVING THE SCENE TO IMAGE ----
 
-    // Sets up progress bar
-    let draw_progress_bar = ProgressBar::new((IMAGE_WIDTH * IMAGE_HEIGHT) as u64);
-    draw_progress_bar.set_style(progress_bar_style);
-
-    // Loop through each pixel in a new image buffer, and set it to its colour from the pixel array
-    let mut buffer = ImageBuffer::new(IMAGE_WIDTH as u32, IMAGE_HEIGHT as u32);
-    for (x, y, pixel) in buffer.enumerate_pixels_mut() {
-        *pixel = pixels[(y * IMAGE_WIDTH as u32 + x) as usize];
-        draw_progress_bar.inc(1);
+    if let Some(path) = &args.sample_heatmap {
+        if let Err(e) = save_sample_heatmap(&buffer, path, &output_settings) {
+            eprintln!("Error writing sample heatmap: {}", e);
+        }
     }
 
-    // Attempt to save to 'image.png' file
-    match buffer.save("image.png") {
-        Err(e) => eprintln!("Error writing to file: {}", e),
-        Ok(()) => eprintln!("Image saved successfully.")
+    // Attempt to save to the output file
+    match save_image(&buffer, &args.output, &output_settings) {
+        Err(e) => {
+            eprintln!("Error writing to file: {}", e);
+            ExitCode::FAILURE
+        },
+        Ok(()) => {
+            eprintln!("Image saved successfully.");
+            ExitCode::SUCCESS
+        }
     }
 }
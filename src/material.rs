@@ -5,17 +5,28 @@ use crate::isotropic::Isotropic;
 use crate::lambertian::Lambertian;
 use crate::metal::Metal;
 use crate::ray::Ray;
+use crate::scatter_record::ScatterRecord;
 use crate::vec3::Vec3;
 
 /// Trait implemented by all materials
 pub trait MaterialTrait {
-    /// Calculates scattered ray and colour for a given hit with a material
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Vec3, Ray)>;
+    /// Scatters a ray hitting the material, returning either a specular ray or a lobe of
+    /// directions, or None if the ray is absorbed
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord>;
 
     /// Returns the emitted colour of a material
     fn emitted(&self, _: f64, _: f64, _: &Vec3) -> Vec3 {
         Vec3::zero()
     }
+
+    /// Gets the base colour of a material at a hit, as recorded by albedo AOVs
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3;
+
+    /// Checks whether the material scatters each wavelength of light differently, so that
+    /// spectral renders only follow one wavelength through it
+    fn is_dispersive(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug)]
@@ -30,7 +41,7 @@ pub enum Material {
 
 /// Calls methods for materials in the Material enum
 impl MaterialTrait for Material {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Vec3, Ray)> {
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
         match self {
             Material::Lambertian(obj) => obj.scatter(ray_in, hit_record),
             Material::Metal(obj) => obj.scatter(ray_in, hit_record),
@@ -49,4 +60,24 @@ impl MaterialTrait for Material {
             Material::Isotropic(obj) => obj.emitted(u, v, p),
         }
     }
+
+    fn albedo(&self, hit_record: &HitRecord) -> Vec3 {
+        match self {
+            Material::Lambertian(obj) => obj.albedo(hit_record),
+            Material::Metal(obj) => obj.albedo(hit_record),
+            Material::Dielectric(obj) => obj.albedo(hit_record),
+            Material::DiffuseLight(obj) => obj.albedo(hit_record),
+            Material::Isotropic(obj) => obj.albedo(hit_record),
+        }
+    }
+
+    fn is_dispersive(&self) -> bool {
+        match self {
+            Material::Lambertian(obj) => obj.is_dispersive(),
+            Material::Metal(obj) => obj.is_dispersive(),
+            Material::Dielectric(obj) => obj.is_dispersive(),
+            Material::DiffuseLight(obj) => obj.is_dispersive(),
+            Material::Isotropic(obj) => obj.is_dispersive(),
+        }
+    }
 }
\ No newline at end of file
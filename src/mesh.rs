@@ -0,0 +1,276 @@
+use std::sync::Arc;
+use crate::{random_double, INFINITY};
+use crate::aabb::AABB;
+use crate::bvh::{add_traversal_steps, build_hierarchy, hierarchy_statistics, BVHBuildMethod, BVHStatistics, LinearNode, MAX_DEPTH};
+use crate::hit_record::HitRecord;
+use crate::hittable::{Hittable, HittableTrait};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::triangle::Triangle;
+use crate::vec3::Vec3;
+
+#[derive(Debug)]
+/// Object to store a triangle mesh with shared vertices, which builds its own internal
+/// bounding volume hierarchy over its triangles
+pub struct Mesh {
+    positions: Vec<[f32; 3]>,           // Position of each vertex
+    normals: Vec<[f32; 3]>,             // Normal of each vertex, or empty for flat shading
+    uvs: Vec<[f32; 2]>,                 // Texture coordinates of each vertex, or empty
+    indices: Vec<[u32; 3]>,             // Vertex indices of each triangle, in hierarchy order
+    material_ids: Vec<u32>,             // Material index of each triangle, or empty to use the first
+    materials: Vec<Arc<Material>>,      // Materials used by the mesh
+    nodes: Vec<LinearNode>,             // Nodes of the mesh's hierarchy, with the root first
+    area_cdf: Vec<f64>,                 // Total area of the triangles up to each one if the whole mesh emits light, or empty
+}
+
+impl Mesh {
+    /// Constructs a new Mesh from its vertex and triangle buffers, wrapped in the Hittable enum.
+    /// Normals and UVs are either empty or given for each vertex, and material IDs are either
+    /// empty or given for each triangle
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        positions: Vec<[f32; 3]>, normals: Vec<[f32; 3]>, uvs: Vec<[f32; 2]>,
+        indices: Vec<[u32; 3]>, material_ids: Vec<u32>, materials: Vec<Arc<Material>>
+    ) -> Hittable {
+        assert!(!indices.is_empty(), "Mesh must contain at least one triangle.");
+        assert!(!materials.is_empty(), "Mesh must have at least one material.");
+        assert!(normals.is_empty() || normals.len() == positions.len(), "Mesh normals must match its vertices.");
+        assert!(uvs.is_empty() || uvs.len() == positions.len(), "Mesh UVs must match its vertices.");
+        assert!(material_ids.is_empty() || material_ids.len() == indices.len(), "Mesh material IDs must match its triangles.");
+
+        let mut mesh = Mesh { positions, normals, uvs, indices, material_ids, materials, nodes: vec![], area_cdf: vec![] };
+
+        // Meshes are built with the SAH, which unlike random axes doesn't draw from the random stream
+        let bounding_boxes: Vec<AABB> = (0..mesh.indices.len()).map(|t| {
+            let [v0, v1, v2] = mesh.triangle(t);
+            Triangle::bounds(v0, v1, v2)
+        }).collect();
+        let (nodes, order) = build_hierarchy(&bounding_boxes, BVHBuildMethod::SAH);
+        mesh.indices = order.iter().map(|&t| mesh.indices[t]).collect();
+        if !mesh.material_ids.is_empty() {
+            mesh.material_ids = order.iter().map(|&t| mesh.material_ids[t]).collect();
+        }
+        mesh.nodes = nodes;
+
+        // Meshes made entirely of lights are sampled as one light, choosing triangles by area
+        if mesh.materials.iter().all(|material| matches!(material.as_ref(), Material::DiffuseLight(_))) {
+            let mut total = 0.;
+            mesh.area_cdf = (0..mesh.indices.len()).map(|t| {
+                let [v0, v1, v2] = mesh.triangle(t);
+                total += Triangle::area_of(v0, v1, v2);
+                total
+            }).collect();
+        }
+
+        Hittable::Mesh(mesh)
+    }
+
+    /// Constructs a new Mesh with a single material, wrapped in the Hittable enum
+    pub fn from_buffers(positions: Vec<[f32; 3]>, indices: Vec<[u32; 3]>, material: Arc<Material>) -> Hittable {
+        Mesh::new(positions, vec![], vec![], indices, vec![], vec![material])
+    }
+
+    /// Gets the number of triangles in the mesh
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Gathers statistics about the shape of the mesh's hierarchy
+    pub fn statistics(&self) -> BVHStatistics {
+        let mut statistics = hierarchy_statistics(&self.nodes);
+        statistics.description = format!("Mesh of {} triangles", self.indices.len());
+        statistics
+    }
+
+    /// Gets the position of a vertex
+    fn position(&self, i: u32) -> Vec3 {
+        let p = self.positions[i as usize];
+        Vec3::new(p[0] as f64, p[1] as f64, p[2] as f64)
+    }
+
+    /// Gets the normal of a vertex
+    fn normal(&self, i: u32) -> Vec3 {
+        let n = self.normals[i as usize];
+        Vec3::new(n[0] as f64, n[1] as f64, n[2] as f64)
+    }
+
+    /// Gets the texture coordinates of a vertex
+    fn uv(&self, i: u32) -> (f64, f64) {
+        let uv = self.uvs[i as usize];
+        (uv[0] as f64, uv[1] as f64)
+    }
+
+    /// Gets the positions of a triangle's vertices
+    fn triangle(&self, triangle: usize) -> [Vec3; 3] {
+        let [i0, i1, i2] = self.indices[triangle];
+        [self.position(i0), self.position(i1), self.position(i2)]
+    }
+
+    /// Gets the material of a triangle
+    fn material(&self, triangle: usize) -> &Arc<Material> {
+        match self.material_ids.get(triangle) {
+            Some(&id) => &self.materials[id as usize],
+            None => &self.materials[0]
+        }
+    }
+
+    /// Gets the texture coordinates and outward normal of a point on a triangle, from its
+    /// barycentric coordinates for the second and third vertices
+    fn attributes(&self, triangle: usize, b1: f64, b2: f64) -> ((f64, f64), Vec3) {
+        let b0 = 1. - b1 - b2;
+        let [i0, i1, i2] = self.indices[triangle];
+
+        // Uses the vertex texture coordinates if given, otherwise the barycentric coordinates
+        let uv = if self.uvs.is_empty() {
+            (b1, b2)
+        } else {
+            let [uv0, uv1, uv2] = [self.uv(i0), self.uv(i1), self.uv(i2)];
+            (b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0, b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1)
+        };
+
+        // Interpolates the vertex normals if given, otherwise uses the face's geometric normal
+        let outward_normal = if self.normals.is_empty() {
+            let [v0, v1, v2] = self.triangle(triangle);
+            Triangle::face_normal(v0, v1, v2)
+        } else {
+            (b0 * self.normal(i0) + b1 * self.normal(i1) + b2 * self.normal(i2)).unit()
+        };
+
+        (uv, outward_normal)
+    }
+
+    /// Visits every triangle a ray hits within a range, which `on_hit` is given along with the
+    /// hit time and barycentric coordinates, returning the new end of the range
+    fn traverse<F: FnMut(usize, f64, f64, f64) -> f64>(&self, ray: &Ray, t_min: f64, mut t_max: f64, mut on_hit: F) {
+        let direction_is_negative = [ray.direction.x < 0., ray.direction.y < 0., ray.direction.z < 0.];
+        let mut stack = [0u32; MAX_DEPTH + 1];
+        let mut stack_size = 1;
+        let mut steps = 0;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let node_index = stack[stack_size];
+            let node = &self.nodes[node_index as usize];
+            steps += 1;
+            if !node.bounding_box.hit(ray, t_min, t_max) {
+                continue;
+            }
+
+            if node.count > 0 {
+                let start = node.offset as usize;
+                steps += node.count as u64;
+                for triangle in start..start + node.count as usize {
+                    let [v0, v1, v2] = self.triangle(triangle);
+                    if let Some((t, b1, b2)) = Triangle::intersect(ray, v0, v1, v2, t_min, t_max) {
+                        t_max = on_hit(triangle, t, b1, b2);
+                    }
+                }
+            } else {
+                // Visits the child on the near side of the split first, as in the BVH
+                if direction_is_negative[node.axis as usize] {
+                    stack[stack_size] = node_index + 1;
+                    stack[stack_size + 1] = node.offset;
+                } else {
+                    stack[stack_size] = node.offset;
+                    stack[stack_size + 1] = node_index + 1;
+                }
+                stack_size += 2;
+            }
+        }
+        add_traversal_steps(steps);
+    }
+}
+
+impl HittableTrait for Mesh {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Finds the closest triangle without building any hit records
+        let mut closest: Option<(usize, f64, f64, f64)> = None;
+        self.traverse(ray, t_min, t_max, |triangle, t, b1, b2| {
+            closest = Some((triangle, t, b1, b2));
+            t
+        });
+
+        let (triangle, t, b1, b2) = closest?;
+        let ((u, v), outward_normal) = self.attributes(triangle, b1, b2);
+        let mut hit_record = HitRecord::new(ray.at(t), self.material(triangle), u, v, t);
+        hit_record.calculate_face_normal(ray, outward_normal);
+
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self, _: f64, _: f64) -> Option<AABB> {
+        let root = &self.nodes[0].bounding_box;
+        Some(AABB::new(root.minimum, root.maximum))
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.extend(self.materials.iter().cloned());
+    }
+
+    fn collect_statistics(&self, statistics: &mut Vec<BVHStatistics>) {
+        statistics.push(self.statistics());
+    }
+
+    fn is_light(&self) -> bool {
+        !self.area_cdf.is_empty()
+    }
+
+    /// Meshes which are partly lights add each of their emitting triangles as a separate light
+    fn collect_lights(&self, lights: &mut Vec<Arc<Hittable>>) {
+        if self.is_light() {
+            return;
+        }
+        for triangle in 0..self.indices.len() {
+            let material = self.material(triangle);
+            if !matches!(material.as_ref(), Material::DiffuseLight(_)) {
+                continue;
+            }
+            let [i0, i1, i2] = self.indices[triangle];
+            let normals = (!self.normals.is_empty()).then(|| [self.normal(i0), self.normal(i1), self.normal(i2)]);
+            let uvs = (!self.uvs.is_empty()).then(|| [self.uv(i0), self.uv(i1), self.uv(i2)]);
+            lights.push(Arc::new(Triangle::with_attributes(self.triangle(triangle), normals, uvs, Arc::clone(material))));
+        }
+    }
+
+    /// Sums the density of every triangle along the direction, as a point sampled behind the
+    /// nearest one is just as likely to have chosen it
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let area = self.area();
+        if area <= 0. {
+            return 0.;
+        }
+        let mut pdf = 0.;
+        self.traverse(&Ray::new(*origin, *direction, 0.), 0.001, INFINITY, |triangle, t, _, _| {
+            let [v0, v1, v2] = self.triangle(triangle);
+            let normal = Triangle::face_normal(v0, v1, v2);
+            let distance_squared = t * t * direction.length_squared();
+            let cosine = f64::abs(Vec3::dot(direction, &normal) / direction.length());
+            pdf += distance_squared / (cosine * area);
+            INFINITY
+        });
+        pdf
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        match self.sample_surface() {
+            Some(hit_record) => hit_record.point - *origin,
+            None => Vec3::new(1., 0., 0.)
+        }
+    }
+
+    fn area(&self) -> f64 {
+        self.area_cdf.last().copied().unwrap_or(0.)
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        let total = *self.area_cdf.last()?;
+        let target = random_double() * total;
+        let triangle = usize::min(self.area_cdf.partition_point(|&area| area <= target), self.area_cdf.len() - 1);
+        let [v0, v1, v2] = self.triangle(triangle);
+        let (point, b1, b2) = Triangle::sample_point(v0, v1, v2);
+        let ((u, v), _) = self.attributes(triangle, b1, b2);
+        let mut hit_record = HitRecord::new(point, self.material(triangle), u, v, 0.);
+        hit_record.normal = Triangle::face_normal(v0, v1, v2);
+        hit_record.front_face = true;
+        Some(hit_record)
+    }
+}
@@ -1,6 +1,7 @@
 use crate::hit_record::HitRecord;
 use crate::material::{Material, MaterialTrait};
 use crate::ray::Ray;
+use crate::scatter_record::ScatterRecord;
 use crate::vec3::Vec3;
 
 #[derive(Debug)]
@@ -12,13 +13,16 @@ pub struct Metal {
 
 impl Metal {
     /// Constructs a new Metal object, wrapped in the Material enum
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(albedo: Vec3, fuzz: f64) -> Material {
         Material::Metal(Metal { albedo, fuzz })
     }
 }
 
 impl MaterialTrait for Metal {
-    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<(Vec3, Ray)> {
+    /// Reflects rays specularly, with fuzzy reflections treated as specular too since their
+    /// distribution can't be evaluated
+    fn scatter(&self, ray_in: &Ray, hit_record: &HitRecord) -> Option<ScatterRecord> {
         // Gets the reflected direction and constructs the scattered ray with a certain fuzz
         let reflected = Ray::reflect(ray_in.direction.unit(), hit_record.normal);
         let scattered = Ray::new(
@@ -27,9 +31,13 @@ impl MaterialTrait for Metal {
 
         // Excludes the ray if it's reflected towards the object
         if Vec3::dot(&scattered.direction, &hit_record.normal) > 0. {
-            Some((self.albedo, scattered))
+            Some(ScatterRecord::Specular { attenuation: self.albedo, ray: scattered })
         } else {
             None
         }
     }
+
+    fn albedo(&self, _: &HitRecord) -> Vec3 {
+        self.albedo
+    }
 }
\ No newline at end of file
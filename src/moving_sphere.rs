@@ -1,5 +1,4 @@
 use std::sync::Arc;
-use ray_tracing::PI;
 use crate::aabb::AABB;
 use crate::hit_record::HitRecord;
 use crate::hittable::{Hittable, HittableTrait};
@@ -21,6 +20,7 @@ pub struct MovingSphere {
 
 impl MovingSphere {
     /// Constructs a new Moving Sphere object, wrapped in the Hittable enum
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(
         centre0: Vec3, centre1: Vec3, time0: f64, time1: f64, radius: f64, material: Arc<Material>
     ) -> Hittable {
@@ -78,4 +78,8 @@ impl HittableTrait for MovingSphere {
         let box1 = AABB::new(self.centre(time1) - r_vec, self.centre(time1) + r_vec);
         Some(AABB::surrounding_box(&box0, &box1))
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.push(Arc::clone(&self.material));
+    }
 }
\ No newline at end of file
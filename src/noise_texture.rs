@@ -17,6 +17,7 @@ pub struct NoiseTexture {
 }
 
 impl NoiseTexture {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(scale: f64, noise_type: NoiseType) -> Texture {
         Texture::NoiseTexture(NoiseTexture { noise: Perlin::new(), noise_type, scale })
     }
@@ -0,0 +1,179 @@
+use std::path::Path;
+use std::sync::Arc;
+use crate::vec3::Vec3;
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::bvh::{BVH, BVHBuildMethod};
+use crate::triangle::Triangle;
+use crate::mesh::Mesh;
+use crate::material::Material;
+use crate::lambertian::Lambertian;
+use crate::metal::Metal;
+use crate::dielectric::Dielectric;
+use crate::diffuse_light::DiffuseLight;
+use crate::image_texture::ImageTexture;
+
+/// Loads the models of a Wavefront OBJ file along with the materials from its MTL files
+fn load_models(path: &Path) -> Result<(Vec<tobj::Model>, Vec<Arc<Material>>), String> {
+    let (models, materials) = tobj::load_obj(path, &tobj::GPU_LOAD_OPTIONS)
+        .map_err(|e| format!("could not load '{}': {e}", path.display()))?;
+
+    // Faces without materials use the default material, but MTL files which are named and
+    // can't be loaded are reported rather than silently replaced
+    let materials = materials.map_err(|e| format!("could not load materials for '{}': {e}", path.display()))?;
+
+    // Textures are given relative to the OBJ file, and are reported if they can't be loaded
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let materials = materials.iter()
+        .map(|m| convert_material(m, base_dir).map(Arc::new).map_err(|e| format!("material '{}': {e}", m.name)))
+        .collect::<Result<_, _>>()?;
+
+    Ok((models, materials))
+}
+
+/// Loads the triangles of a Wavefront OBJ file into a list, using the materials from its MTL
+/// files where possible and the given default material for faces without one
+pub fn load_obj_list(path: &Path, default_material: Arc<Material>) -> Result<HittableList, String> {
+    let (models, materials) = load_models(path)?;
+
+    let mut list = HittableList::new();
+    for model in &models {
+        let mesh = &model.mesh;
+        let material = match mesh.material_id.and_then(|id| materials.get(id)) {
+            Some(material) => Arc::clone(material),
+            None => Arc::clone(&default_material)
+        };
+
+        let position = |i: usize| Vec3::new(
+            mesh.positions[3 * i] as f64, mesh.positions[3 * i + 1] as f64, mesh.positions[3 * i + 2] as f64
+        );
+        let normal = |i: usize| Vec3::new(
+            mesh.normals[3 * i] as f64, mesh.normals[3 * i + 1] as f64, mesh.normals[3 * i + 2] as f64
+        );
+        let uv = |i: usize| (mesh.texcoords[2 * i] as f64, mesh.texcoords[2 * i + 1] as f64);
+
+        for face in mesh.indices.chunks_exact(3) {
+            let [i0, i1, i2] = [face[0] as usize, face[1] as usize, face[2] as usize];
+            let vertices = [position(i0), position(i1), position(i2)];
+
+            // Skips degenerate triangles, which can't be hit
+            if Vec3::cross(&(vertices[1] - vertices[0]), &(vertices[2] - vertices[0])).near_zero() {
+                continue;
+            }
+
+            let normals = if mesh.normals.is_empty() {
+                None
+            } else {
+                Some([normal(i0), normal(i1), normal(i2)])
+            };
+            let uvs = if mesh.texcoords.is_empty() {
+                None
+            } else {
+                Some([uv(i0), uv(i1), uv(i2)])
+            };
+
+            list.add(Triangle::with_attributes(vertices, normals, uvs, Arc::clone(&material)));
+        }
+    }
+
+    if list.objects.is_empty() {
+        return Err(format!("'{}' contains no triangles", path.display()));
+    }
+
+    Ok(list)
+}
+
+/// Loads a Wavefront OBJ file as a BVH of triangles, built with the given method
+pub fn load_obj(
+    path: &Path, default_material: Arc<Material>, bvh_method: BVHBuildMethod
+) -> Result<Hittable, String> {
+    let list = load_obj_list(path, default_material)?;
+    Ok(BVH::build(&list, 0., 1., bvh_method))
+}
+
+/// Loads a Wavefront OBJ file as a single shared-vertex mesh, using the materials from its MTL
+/// files where possible and the given default material for faces without one
+pub fn load_obj_mesh(path: &Path, default_material: Arc<Material>) -> Result<Hittable, String> {
+    let (models, mut materials) = load_models(path)?;
+    let default_id = materials.len() as u32;
+
+    // The default material is only added if some model needs it, as an unused one would keep
+    // a mesh made entirely of lights from being sampled as one
+    if models.iter().any(|m| m.mesh.material_id.is_none_or(|id| id >= default_id as usize)) {
+        materials.push(default_material);
+    }
+
+    // Vertex normals and UVs are only kept if every model in the file has them
+    let has_normals = models.iter().all(|m| !m.mesh.normals.is_empty());
+    let has_uvs = models.iter().all(|m| !m.mesh.texcoords.is_empty());
+
+    let mut positions = vec![];
+    let mut normals = vec![];
+    let mut uvs = vec![];
+    let mut indices = vec![];
+    let mut material_ids = vec![];
+
+    for model in &models {
+        let mesh = &model.mesh;
+        let offset = positions.len() as u32;
+        let material_id = match mesh.material_id {
+            Some(id) if id < default_id as usize => id as u32,
+            _ => default_id
+        };
+
+        positions.extend(mesh.positions.chunks_exact(3).map(|p| [p[0], p[1], p[2]]));
+        if has_normals {
+            normals.extend(mesh.normals.chunks_exact(3).map(|n| [n[0], n[1], n[2]]));
+        }
+        if has_uvs {
+            uvs.extend(mesh.texcoords.chunks_exact(2).map(|t| [t[0], t[1]]));
+        }
+
+        for face in mesh.indices.chunks_exact(3) {
+            indices.push([face[0] + offset, face[1] + offset, face[2] + offset]);
+            material_ids.push(material_id);
+        }
+    }
+
+    if indices.is_empty() {
+        return Err(format!("'{}' contains no triangles", path.display()));
+    }
+
+    Ok(Mesh::new(positions, normals, uvs, indices, material_ids, materials))
+}
+
+/// Maps an MTL material onto the closest available material, or reports why its texture
+/// couldn't be loaded
+fn convert_material(material: &tobj::Material, base_dir: &Path) -> Result<Material, String> {
+    let colour = |c: Option<[f32; 3]>| c.map(|c| Vec3::new(c[0] as f64, c[1] as f64, c[2] as f64));
+    let emissive = colour(material.emissive).filter(|e| !e.near_zero());
+    let specular = colour(material.specular).filter(|s| !s.near_zero());
+    let diffuse = colour(material.diffuse).unwrap_or(Vec3::new(0.8, 0.8, 0.8));
+    let illumination = material.illumination_model.unwrap_or(2);
+
+    // Emissive materials become lights
+    if let Some(emit) = emissive {
+        return Ok(DiffuseLight::from_colour(emit.x, emit.y, emit.z));
+    }
+
+    // Transparent materials and the refractive illumination models become dielectrics
+    let transparent = material.dissolve.is_some_and(|d| d < 1.);
+    if transparent || matches!(illumination, 4 | 6 | 7 | 9) {
+        return Ok(Dielectric::new(material.optical_density.map_or(1.5, |n| n as f64)));
+    }
+
+    // Reflective illumination models become metals, with rougher metals for lower shininess
+    if let (Some(specular), 3 | 5 | 8) = (specular, illumination) {
+        let shininess = material.shininess.map_or(0., |s| s as f64);
+        let fuzz = 1. - f64::min(shininess, 1000.) / 1000.;
+        return Ok(Metal::new(specular, fuzz));
+    }
+
+    Ok(match &material.diffuse_texture {
+        Some(texture) => {
+            let path = base_dir.join(texture);
+            Lambertian::from_texture(Arc::new(ImageTexture::new(&path.to_string_lossy())?))
+        },
+        None => Lambertian::new(diffuse)
+    })
+}
@@ -0,0 +1,25 @@
+use crate::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy)]
+/// Orthonormal basis, used to turn directions sampled around the z axis into world space
+pub struct ONB {
+    pub u: Vec3,    // First tangent of the basis
+    pub v: Vec3,    // Second tangent of the basis
+    pub w: Vec3,    // Axis of the basis, which local z coordinates run along
+}
+
+impl ONB {
+    /// Constructs an orthonormal basis whose w axis points along a given vector
+    pub fn from_w(n: &Vec3) -> Self {
+        let w = n.unit();
+        let a = if w.x.abs() > 0.9 { Vec3::new(0., 1., 0.) } else { Vec3::new(1., 0., 0.) };
+        let v = Vec3::cross(&w, &a).unit();
+        let u = Vec3::cross(&w, &v);
+        ONB { u, v, w }
+    }
+
+    /// Converts local coordinates in the basis to a world space vector
+    pub fn local(&self, a: &Vec3) -> Vec3 {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+}
@@ -0,0 +1,229 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use exr::prelude::{AnyChannel, AnyChannels, FlatSamples, SmallVec, WritableImage};
+use image::{DynamicImage, ImageFormat, Rgb, Rgb32FImage};
+use image::codecs::hdr::HdrEncoder;
+use crate::mix_seed;
+use crate::aov::Aov;
+use crate::denoise::{denoise, DenoiseSettings};
+use crate::render_buffer::RenderBuffer;
+use crate::tone_map::{srgb_encode, ToneMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Number of bits stored for each channel of integer image formats
+pub enum BitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Settings controlling how a render is written to an image file
+pub struct OutputSettings {
+    pub tone_map: ToneMap,      // Conversion of radiance into display colours for integer image formats
+    pub bit_depth: BitDepth,    // Bit depth of integer image formats
+    pub dither: bool,           // Whether to dither integer image formats to hide banding
+    pub aovs: Vec<Aov>,         // AOVs to save along with the image
+    pub separate_aovs: bool,    // Whether to save AOVs as separate images even when writing OpenEXR
+    pub denoise: Option<DenoiseSettings>,   // Settings to denoise the image with before saving it, if any
+}
+
+/// Checks whether an image can be written to a path, with the format chosen by its extension
+pub fn check_output_path(path: &Path, settings: &OutputSettings) -> Result<(), String> {
+    let format = ImageFormat::from_path(path)
+        .map_err(|_| format!("could not determine an image format from '{}'", path.display()))?;
+
+    match format {
+        ImageFormat::OpenExr | ImageFormat::Hdr => Ok(()),
+        ImageFormat::Png | ImageFormat::Tiff | ImageFormat::Pnm => Ok(()),
+        _ if settings.bit_depth == BitDepth::Sixteen =>
+            Err(format!("writing 16-bit {format:?} images is not supported")),
+        _ if format.can_write() => Ok(()),
+        _ => Err(format!("writing {format:?} images is not supported")),
+    }
+}
+
+/// Saves a render to an image file with the format chosen by the path's extension. OpenEXR
+/// and Radiance HDR images store the linear radiance without any loss, while other formats
+/// store tone mapped colours at the chosen bit depth. AOVs are saved as layers of OpenEXR
+/// images, or otherwise as separate images named after the AOV
+pub fn save_image(buffer: &RenderBuffer, path: &Path, settings: &OutputSettings) -> Result<(), String> {
+    check_output_path(path, settings)?;
+    if !settings.aovs.is_empty() && buffer.aovs.is_none() {
+        return Err("the render didn't record any AOVs".to_string());
+    }
+
+    let denoised;
+    let buffer = match &settings.denoise {
+        Some(denoise_settings) => {
+            denoised = denoise(buffer, denoise_settings)?;
+            &denoised
+        },
+        None => buffer
+    };
+    let error = |e: image::ImageError| format!("could not write '{}': {e}", path.display());
+    let format = ImageFormat::from_path(path).map_err(error)?;
+
+    if format == ImageFormat::OpenExr && !settings.aovs.is_empty() && !settings.separate_aovs {
+        return save_layered_exr(buffer, path, &settings.aovs);
+    }
+
+    match format {
+        ImageFormat::OpenExr => buffer.to_linear_image().save(path).map_err(error)?,
+        ImageFormat::Hdr => save_hdr(&buffer.to_linear_image(), path)?,
+        _ => match settings.bit_depth {
+            BitDepth::Eight => buffer.to_image8(&settings.tone_map, settings.dither).save(path).map_err(error)?,
+            BitDepth::Sixteen => buffer.to_image16(&settings.tone_map, settings.dither).save(path).map_err(error)?,
+        }
+    }
+
+    for &aov in &settings.aovs {
+        save_aov(buffer, &aov_path(path, aov), format, aov, settings)?;
+    }
+    Ok(())
+}
+
+/// Saves a heatmap of the number of samples taken through each pixel. Floating point formats
+/// store the raw counts, while integer formats colour them from blue for the fewest samples
+/// to red for the most
+pub fn save_sample_heatmap(buffer: &RenderBuffer, path: &Path, settings: &OutputSettings) -> Result<(), String> {
+    check_output_path(path, settings)?;
+    let error = |e: image::ImageError| format!("could not write '{}': {e}", path.display());
+    let format = ImageFormat::from_path(path).map_err(error)?;
+    let counts = Rgb32FImage::from_fn(buffer.width as u32, buffer.height as u32, |x, y| {
+        Rgb([buffer.samples[y as usize * buffer.width + x as usize] as f32; 3])
+    });
+
+    match format {
+        ImageFormat::OpenExr => counts.save(path).map_err(error),
+        ImageFormat::Hdr => save_hdr(&counts, path),
+        _ => {
+            let image = DynamicImage::ImageRgb32F(visualise_samples(counts));
+            match settings.bit_depth {
+                BitDepth::Eight => image.into_rgb8().save(path).map_err(error),
+                BitDepth::Sixteen => image.into_rgb16().save(path).map_err(error),
+            }
+        }
+    }
+}
+
+/// Gets the path a separate AOV image is saved to, which adds the AOV's name before the
+/// extension of the main image's path
+pub fn aov_path(path: &Path, aov: Aov) -> PathBuf {
+    let mut name = path.file_stem().unwrap_or_default().to_owned();
+    name.push(format!(".{}", aov.name()));
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// Saves a linear image in the Radiance HDR format
+fn save_hdr(image: &Rgb32FImage, path: &Path) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("could not create '{}': {e}", path.display()))?;
+    let pixels: Vec<_> = image.pixels().copied().collect();
+    HdrEncoder::new(BufWriter::new(file)).encode(&pixels, image.width() as usize, image.height() as usize)
+        .map_err(|e| format!("could not write '{}': {e}", path.display()))
+}
+
+/// Saves an AOV as its own image. Floating point formats store its raw values, while integer
+/// formats store a visualisation of it
+fn save_aov(buffer: &RenderBuffer, path: &Path, format: ImageFormat, aov: Aov, settings: &OutputSettings) -> Result<(), String> {
+    let image = buffer.aov_image(aov).ok_or("the render didn't record any AOVs")?;
+    let error = |e: image::ImageError| format!("could not write '{}': {e}", path.display());
+
+    match format {
+        ImageFormat::OpenExr => image.save(path).map_err(error),
+        ImageFormat::Hdr => save_hdr(&image, path),
+        _ => {
+            let image = DynamicImage::ImageRgb32F(visualise_aov(aov, image));
+            match settings.bit_depth {
+                BitDepth::Eight => image.into_rgb8().save(path).map_err(error),
+                BitDepth::Sixteen => image.into_rgb16().save(path).map_err(error),
+            }
+        }
+    }
+}
+
+/// Maps an image of an AOV's raw values into the range [0,1] so that it can be viewed
+fn visualise_aov(aov: Aov, mut image: Rgb32FImage) -> Rgb32FImage {
+    // Ranges are found over every pixel, so depths and positions use the full range
+    let mut minimum = [f32::INFINITY; 3];
+    let mut maximum = [f32::NEG_INFINITY; 3];
+    for pixel in image.pixels() {
+        for c in 0..3 {
+            minimum[c] = f32::min(minimum[c], pixel.0[c]);
+            maximum[c] = f32::max(maximum[c], pixel.0[c]);
+        }
+    }
+    let normalise = |v: f32, c: usize| if maximum[c] > minimum[c] { (v - minimum[c]) / (maximum[c] - minimum[c]) } else { 0. };
+
+    for pixel in image.pixels_mut() {
+        let [r, g, b] = pixel.0;
+        pixel.0 = match aov {
+            Aov::Albedo => [r, g, b].map(|c| srgb_encode(c.clamp(0., 1.) as f64) as f32),
+            Aov::Normal => [r, g, b].map(|c| 0.5 * c + 0.5),
+            Aov::Depth => [r / f32::max(maximum[0], f32::MIN_POSITIVE); 3],
+            Aov::Position => [normalise(r, 0), normalise(g, 1), normalise(b, 2)],
+            Aov::Uv => [r, g, b],
+            Aov::ObjectId | Aov::MaterialId => id_colour(r),
+        };
+    }
+    image
+}
+
+/// Colours an image of sample counts along a ramp from blue through green to red, scaled
+/// between the fewest and most samples taken
+fn visualise_samples(mut image: Rgb32FImage) -> Rgb32FImage {
+    let minimum = image.pixels().map(|p| p.0[0]).fold(f32::INFINITY, f32::min);
+    let maximum = image.pixels().map(|p| p.0[0]).fold(f32::NEG_INFINITY, f32::max);
+
+    for pixel in image.pixels_mut() {
+        let t = if maximum > minimum { (pixel.0[0] - minimum) / (maximum - minimum) } else { 1. };
+        pixel.0 = heat_colour(t);
+    }
+    image
+}
+
+/// Gets the colour of a value between 0 and 1 along a ramp from blue through green to red
+pub(crate) fn heat_colour(t: f32) -> [f32; 3] {
+    const RAMP: [[f32; 3]; 5] = [[0., 0., 1.], [0., 1., 1.], [0., 1., 0.], [1., 1., 0.], [1., 0., 0.]];
+    let position = t.clamp(0., 1.) * (RAMP.len() - 1) as f32;
+    let k = usize::min(position as usize, RAMP.len() - 2);
+    let f = position - k as f32;
+    [0, 1, 2].map(|c| (1. - f) * RAMP[k][c] + f * RAMP[k + 1][c])
+}
+
+/// Gets a distinct colour for an ID, or black for pixels where nothing was hit
+pub(crate) fn id_colour(id: f32) -> [f32; 3] {
+    if id < 0. {
+        return [0.; 3];
+    }
+    let hash = mix_seed(id as u64);
+    let channel = |shift: u64| 0.2 + 0.8 * ((hash >> shift) & 0xFF) as f32 / 255.;
+    [channel(0), channel(8), channel(16)]
+}
+
+/// Saves a render as a single OpenEXR image, with the radiance in its RGB channels and each
+/// AOV in a layer of channels prefixed by its name
+fn save_layered_exr(buffer: &RenderBuffer, path: &Path, aovs: &[Aov]) -> Result<(), String> {
+    let no_aovs = || "the render didn't record any AOVs".to_string();
+    let beauty = buffer.to_linear_image();
+    let mut channels: Vec<AnyChannel<FlatSamples>> = ["R", "G", "B"].iter().enumerate().map(|(c, &name)| {
+        AnyChannel::new(name, FlatSamples::F32(beauty.pixels().map(|p| p.0[c]).collect()))
+    }).collect();
+
+    for &aov in aovs {
+        let values = buffer.aov_channels(aov).ok_or_else(no_aovs)?;
+        for (name, values) in aov.channels().iter().zip(values) {
+            channels.push(AnyChannel::new(format!("{}.{name}", aov.name()).as_str(), FlatSamples::F32(values)));
+        }
+    }
+
+    let channels = AnyChannels::sort(SmallVec::from_vec(channels));
+    exr::prelude::Image::from_channels((buffer.width, buffer.height), channels)
+        .write().to_file(path)
+        .map_err(|e| format!("could not write '{}': {e}", path.display()))
+}
@@ -1,4 +1,4 @@
-use ray_tracing::{random_double, random_int};
+use crate::random_int;
 use crate::vec3::Vec3;
 
 const PERLIN_POINT_COUNT: usize = 256;
@@ -12,6 +12,8 @@ pub struct Perlin {
 }
 
 impl Perlin {
+    // Each call builds a different random noise, which wouldn't be expected of a default
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Perlin {
         let mut random_vec: Vec<Vec3> = vec![];
         for _ in 0..PERLIN_POINT_COUNT {
@@ -36,10 +38,10 @@ impl Perlin {
 
         let mut c = [[[Vec3::zero(); 2]; 2]; 2];
 
-        for di in 0..2 {
-            for dj in 0..2 {
-                for dk in 0..2 {
-                    c[di][dj][dk] = self.random_vec[(
+        for (di, plane) in c.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    *corner = self.random_vec[(
                             self.perm_x[((i + di as i32) & 255) as usize] ^
                             self.perm_y[((j + dj as i32) & 255) as usize] ^
                             self.perm_z[((k + dk as i32) & 255) as usize]
@@ -62,12 +64,10 @@ impl Perlin {
         p
     }
 
-    pub fn permute(p: &mut Vec<i32>) {
+    pub fn permute(p: &mut [i32]) {
         for i in (1..PERLIN_POINT_COUNT-1).rev() {
             let target: usize = random_int(0, i as i32) as usize;
-            let temp = p[i];
-            p[i] = p[target];
-            p[target] = temp;
+            p.swap(i, target);
         }
     }
 
@@ -82,7 +82,7 @@ impl Perlin {
                     let i = i as f64;
                     let j = j as f64;
                     let k = k as f64;
-                    let weight_v = Vec3::new(u - i as f64, v - j as f64, w - k as f64);
+                    let weight_v = Vec3::new(u - i, v - j, w - k);
                     accum += (i * uu + (1. - i) * (1. - uu))
                             * (j * vv + (1. - j) * (1. - vv))
                             * (k * ww + (1. - k) * (1. - ww))
@@ -96,7 +96,7 @@ impl Perlin {
 
     pub fn turbulence(&self, p: &Vec3, depth: i32) -> f64 {
         let mut accum = 0.;
-        let mut temp_p = p.clone();
+        let mut temp_p = *p;
         let mut weight = 1.;
 
         for _ in 0..depth {
@@ -0,0 +1,195 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{random_double, random_int, INFINITY, PI};
+use crate::hittable::HittableTrait;
+use crate::material::MaterialTrait;
+use crate::ray::Ray;
+use crate::sampler::seed_random_stream;
+use crate::scatter_record::{Lobe, ScatterRecord};
+use crate::scene::Scene;
+use crate::vec3::Vec3;
+
+const MAX_BOUNCES: usize = 32;      // Most hits a photon takes before it's given up on, when the render sets no maximum depth
+const RADIUS_FRACTION: f64 = 0.004; // Default gather radius, as a fraction of the scene's extent
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Settings for the caustic photon map gathered at diffuse hits by the photon mapping integrator
+pub struct PhotonSettings {
+    pub photons: usize,         // Number of photons emitted from the scene's lights
+    pub radius: Option<f64>,    // Radius within which photons are gathered, or None to scale it to the scene
+}
+
+impl Default for PhotonSettings {
+    fn default() -> Self {
+        PhotonSettings { photons: 200_000, radius: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Light carried to a diffuse surface by a photon after passing through specular objects
+pub struct Photon {
+    pub point: Vec3,        // Position the photon landed at
+    pub direction: Vec3,    // Direction the photon was travelling in when it landed
+    pub power: Vec3,        // Light carried by the photon, so that every photon emitted sums to the lights' power
+    axis: usize,            // Axis the photon splits its subtree of the kd-tree on
+}
+
+impl Photon {
+    /// Constructs a new Photon, which isn't placed in any kd-tree yet
+    pub fn new(point: Vec3, direction: Vec3, power: Vec3) -> Self {
+        Photon { point, direction, power, axis: 0 }
+    }
+}
+
+/// Photons stored in a balanced kd-tree, laid out in a single array so that the median of
+/// every range of photons splits the rest of the range in two
+pub struct PhotonMap {
+    photons: Vec<Photon>,   // Photons in the order of the kd-tree
+    radius: f64,            // Radius within which photons are gathered
+}
+
+impl PhotonMap {
+    /// Builds a kd-tree of the given photons, which are gathered within a radius
+    pub fn new(mut photons: Vec<Photon>, radius: f64) -> Self {
+        balance(&mut photons);
+        PhotonMap { photons, radius }
+    }
+
+    /// Emits photons from random points on the scene's lights and follows them through
+    /// specular objects, storing those which land on diffuse surfaces afterwards. Light
+    /// reaching a diffuse surface straight from a light, or after a diffuse bounce, is left to
+    /// the path tracer. Camera paths take at least one hit to reach a photon, so photons take
+    /// at most one hit fewer than the render's maximum depth. Each photon has its own random
+    /// number stream derived from the seed, so the map doesn't depend on the number of threads
+    pub fn emit(scene: &Scene, settings: &PhotonSettings, seed: u64, max_depth: Option<i32>) -> Self {
+        let radius = settings.radius.unwrap_or(RADIUS_FRACTION * scene.extent());
+        let max_hits = max_depth.map_or(MAX_BOUNCES, |max_depth| usize::try_from(max_depth - 1).unwrap_or(0));
+        let photons: Vec<Photon> = (0..settings.photons as u64).into_par_iter().filter_map(|i| {
+            // No pixel has this index, so photons never share a stream with a sample
+            seed_random_stream(seed, u64::MAX, i);
+            trace_photon(scene, settings.photons, max_hits)
+        }).collect();
+        PhotonMap::new(photons, radius)
+    }
+
+    /// Gets the number of photons stored in the map
+    pub fn len(&self) -> usize {
+        self.photons.len()
+    }
+
+    /// Checks whether the map has no photons
+    pub fn is_empty(&self) -> bool {
+        self.photons.is_empty()
+    }
+
+    /// Gets the radius within which photons are gathered
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Gets every photon within the gather radius of a point
+    pub fn within(&self, point: &Vec3) -> Vec<Photon> {
+        let mut found = vec![];
+        visit(&self.photons, point, self.radius * self.radius, &mut |photon| found.push(*photon));
+        found
+    }
+
+    /// Estimates the light scattered by a diffuse lobe at a point from the photons landing
+    /// around it, each of which counts fully within the gather radius
+    pub fn gather(&self, point: &Vec3, lobe: &Lobe) -> Vec3 {
+        let (albedo, normal) = match lobe {
+            Lobe::Cosine { albedo, normal } => (*albedo, *normal),
+            Lobe::Uniform { .. } => return Vec3::zero(),
+        };
+
+        // Only photons arriving on the side of the surface being looked at are counted
+        let mut power = Vec3::zero();
+        visit(&self.photons, point, self.radius * self.radius, &mut |photon| {
+            if Vec3::dot(&photon.direction, &normal) < 0. {
+                power += photon.power;
+            }
+        });
+        albedo / PI * power / (PI * self.radius * self.radius)
+    }
+}
+
+/// Follows one photon from a random point on a random light, returning it once it lands on a
+/// diffuse surface after at least one specular bounce within the given number of hits, given
+/// the number of photons emitted
+fn trace_photon(scene: &Scene, photons: usize, max_hits: usize) -> Option<Photon> {
+    let lights = &scene.surface_lights.objects;
+    if lights.is_empty() {
+        return None;
+    }
+    let light = &lights[random_int(0, lights.len() as i32 - 1) as usize];
+    let origin = light.sample_surface()?;
+    let emitted = origin.material.emitted(origin.u, origin.v, &origin.point);
+
+    // Lights emit from both sides with a cosine distribution, which cancels the cosine term
+    let side = if random_double() < 0.5 { origin.normal } else { -origin.normal };
+    let direction = Lobe::Cosine { albedo: Vec3::one(), normal: side }.sample();
+    let mut power = emitted * 2. * PI * lights.len() as f64 * light.area() / photons as f64;
+    let mut ray = Ray::new(origin.point, direction, scene.camera.sample_time());
+
+    for bounce in 0..max_hits {
+        let hit_record = scene.world.hit(&ray, 0.001, INFINITY)?;
+        match hit_record.material.scatter(&ray, &hit_record)? {
+            ScatterRecord::Specular { attenuation, ray: scattered } => {
+                power = power * attenuation;
+                ray = scattered;
+            },
+            ScatterRecord::Diffuse(Lobe::Cosine { .. }) if bounce > 0 =>
+                return Some(Photon::new(hit_record.point, ray.direction.unit(), power)),
+            ScatterRecord::Diffuse(_) => return None,
+        }
+    }
+    None
+}
+
+/// Arranges photons into a kd-tree in place, splitting each range at its median along the
+/// axis it's most spread out on
+fn balance(photons: &mut [Photon]) {
+    if photons.len() <= 1 {
+        return;
+    }
+    let mut minimum = Vec3::new(INFINITY, INFINITY, INFINITY);
+    let mut maximum = -minimum;
+    for photon in photons.iter() {
+        for axis in 0..3 {
+            minimum[axis] = f64::min(minimum[axis], photon.point[axis]);
+            maximum[axis] = f64::max(maximum[axis], photon.point[axis]);
+        }
+    }
+    let extent = maximum - minimum;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+
+    let median = photons.len() / 2;
+    photons.select_nth_unstable_by(median, |a, b| a.point[axis].total_cmp(&b.point[axis]));
+    photons[median].axis = axis;
+    let (below, above) = photons.split_at_mut(median);
+    balance(below);
+    balance(&mut above[1..]);
+}
+
+/// Calls a function on every photon of a kd-tree within a squared distance of a point
+fn visit<F: FnMut(&Photon)>(photons: &[Photon], point: &Vec3, radius_squared: f64, f: &mut F) {
+    if photons.is_empty() {
+        return;
+    }
+    let median = photons.len() / 2;
+    let photon = &photons[median];
+    if (photon.point - *point).length_squared() <= radius_squared {
+        f(photon);
+    }
+
+    // The far side of the split only needs searching if the sphere around the point crosses it
+    let offset = point[photon.axis] - photon.point[photon.axis];
+    let (near, far) = match offset < 0. {
+        true => (&photons[..median], &photons[median + 1..]),
+        false => (&photons[median + 1..], &photons[..median]),
+    };
+    visit(near, point, radius_squared, f);
+    if offset * offset <= radius_squared {
+        visit(far, point, radius_squared, f);
+    }
+}
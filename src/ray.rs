@@ -4,13 +4,19 @@ use crate::vec3::Vec3;
 pub struct Ray {
     pub origin: Vec3,       // Origin of the ray
     pub direction: Vec3,    // Direction of the ray
-    pub time: f64           // Time the ray exists at
+    pub time: f64,          // Time the ray exists at
+    pub wavelength: Option<f64>,    // Wavelength in nanometres of the light carried, if rendering spectrally
 }
 
 impl Ray {
-    /// Constructs a new ray with a given origin, direction and time
+    /// Constructs a new ray with a given origin, direction and time, carrying no particular wavelength
     pub fn new(origin: Vec3, direction: Vec3, time: f64) -> Self {
-        Ray { origin, direction, time }
+        Ray { origin, direction, time, wavelength: None }
+    }
+
+    /// Constructs a copy of a ray carrying light of a given wavelength
+    pub fn with_wavelength(&self, wavelength: Option<f64>) -> Self {
+        Ray { wavelength, ..Ray::new(self.origin, self.direction, self.time) }
     }
 
     /// Gets the point on the ray at a given time 't'
@@ -0,0 +1,140 @@
+use image::{ImageBuffer, Rgb, Rgb32FImage, RgbImage};
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+use crate::clamp;
+use crate::adaptive::PixelVariance;
+use crate::aov::{Aov, AovPixel};
+use crate::tone_map::ToneMap;
+use crate::vec3::Vec3;
+
+#[derive(Debug)]
+/// Radiance accumulated by a render in progress, stored for each pixel from top to bottom
+pub struct RenderBuffer {
+    pub width: usize,           // Width of the image in pixels
+    pub height: usize,          // Height of the image in pixels
+    pub radiance: Vec<Vec3>,    // Sum of the filter weighted radiance of the samples splatted into each pixel
+    pub weights: Vec<f64>,      // Sum of the filter weights of the samples splatted into each pixel
+    pub samples: Vec<u32>,      // Number of samples taken through each pixel
+    pub variance: Vec<PixelVariance>,   // Running mean and variance of the luminance of each pixel's samples
+    pub aovs: Option<Vec<AovPixel>>,    // First hits of each pixel's samples, if AOVs are being recorded
+    pub light: Vec<Vec3>,       // Sum of the light traced straight from lights to the camera landing in each pixel
+    pub light_paths: u64,       // Number of light paths traced towards the camera, over the whole image
+}
+
+impl RenderBuffer {
+    /// Constructs a new empty RenderBuffer
+    pub fn new(width: usize, height: usize) -> Self {
+        RenderBuffer {
+            width,
+            height,
+            radiance: vec![Vec3::zero(); width * height],
+            weights: vec![0.; width * height],
+            samples: vec![0; width * height],
+            variance: vec![PixelVariance::default(); width * height],
+            aovs: None,
+            light: vec![Vec3::zero(); width * height],
+            light_paths: 0,
+        }
+    }
+
+    /// Constructs a new empty RenderBuffer which records AOVs
+    pub fn with_aovs(width: usize, height: usize) -> Self {
+        RenderBuffer { aovs: Some(vec![AovPixel::new(); width * height]), ..RenderBuffer::new(width, height) }
+    }
+
+    /// Gets the filtered radiance of a pixel from its index, including any light traced
+    /// straight to the camera, which is averaged over every light path rather than filtered
+    pub fn pixel(&self, i: usize) -> Vec3 {
+        let radiance = if self.weights[i] == 0. {
+            Vec3::zero()
+        } else {
+            self.radiance[i] / self.weights[i]
+        };
+        match self.light_paths {
+            0 => radiance,
+            light_paths => radiance + self.light[i] / light_paths as f64,
+        }
+    }
+
+    /// Gets the tone mapped display colour of a pixel from its index, in the range [0,1]
+    pub fn display_colour(&self, i: usize, tone_map: &ToneMap) -> Vec3 {
+        tone_map.apply(self.pixel(i))
+    }
+
+    /// Gets the display colour of each pixel in the range [0,255], using the default tone map
+    pub fn to_rgb(&self) -> Vec<Rgb<u8>> {
+        self.to_image().pixels().copied().collect()
+    }
+
+    /// Converts the buffer to an 8-bit image using the default tone map
+    pub fn to_image(&self) -> RgbImage {
+        self.to_image8(&ToneMap::default(), false)
+    }
+
+    /// Converts the buffer to a tone mapped 8-bit image, optionally dithered to hide banding
+    pub fn to_image8(&self, tone_map: &ToneMap, dither: bool) -> RgbImage {
+        let channels = self.quantise(u8::MAX as f64, tone_map, dither);
+        RgbImage::from_raw(self.width as u32, self.height as u32, channels.iter().map(|&c| c as u8).collect())
+            .expect("buffer size matches its dimensions")
+    }
+
+    /// Converts the buffer to a tone mapped 16-bit image, optionally dithered to hide banding
+    pub fn to_image16(&self, tone_map: &ToneMap, dither: bool) -> ImageBuffer<Rgb<u16>, Vec<u16>> {
+        let channels = self.quantise(u16::MAX as f64, tone_map, dither);
+        ImageBuffer::from_raw(self.width as u32, self.height as u32, channels.iter().map(|&c| c as u16).collect())
+            .expect("buffer size matches its dimensions")
+    }
+
+    /// Converts the buffer to an image of the filtered linear radiance of each pixel, without
+    /// any tone mapping
+    pub fn to_linear_image(&self) -> Rgb32FImage {
+        Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let colour = self.pixel(y as usize * self.width + x as usize);
+            Rgb([colour.x as f32, colour.y as f32, colour.z as f32])
+        })
+    }
+
+    /// Gets the values of each channel of an AOV for every pixel, or None if the buffer
+    /// doesn't record AOVs
+    pub fn aov_channels(&self, aov: Aov) -> Option<Vec<Vec<f32>>> {
+        let aovs = self.aovs.as_ref()?;
+        let mut channels = vec![Vec::with_capacity(aovs.len()); aov.channels().len()];
+        for (pixel, &samples) in aovs.iter().zip(&self.samples) {
+            let values = pixel.values(aov, samples);
+            for (channel, value) in channels.iter_mut().zip(values) {
+                channel.push(value);
+            }
+        }
+        Some(channels)
+    }
+
+    /// Converts an AOV to an image of its raw values, with single channels repeated across
+    /// red, green and blue and missing channels left at zero
+    pub fn aov_image(&self, aov: Aov) -> Option<Rgb32FImage> {
+        let channels = self.aov_channels(aov)?;
+        Some(Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let i = y as usize * self.width + x as usize;
+            match channels.len() {
+                1 => Rgb([channels[0][i]; 3]),
+                2 => Rgb([channels[0][i], channels[1][i], 0.]),
+                _ => Rgb([channels[0][i], channels[1][i], channels[2][i]]),
+            }
+        }))
+    }
+
+    /// Quantises the display colour channels of every pixel to integers in the range [0,max],
+    /// optionally adding triangular noise of up to one level to dither them
+    fn quantise(&self, max: f64, tone_map: &ToneMap, dither: bool) -> Vec<f64> {
+        let levels = max + 1.;
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut channels = Vec::with_capacity(3 * self.radiance.len());
+        for i in 0..self.radiance.len() {
+            let colour = self.display_colour(i, tone_map);
+            for c in [colour.x, colour.y, colour.z] {
+                let noise = if dither { rng.gen::<f64>() + rng.gen::<f64>() - 1. } else { 0. };
+                channels.push(f64::floor(levels * clamp(c, 0., 1.) + noise).clamp(0., max));
+            }
+        }
+        channels
+    }
+}
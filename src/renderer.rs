@@ -0,0 +1,682 @@
+use std::ops::Range;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use indicatif::ProgressBar;
+use crate::{random_double, INFINITY};
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::environment::{Background, Environment};
+use crate::render_buffer::RenderBuffer;
+use crate::filter::Filter;
+use crate::aov::AovPixel;
+use crate::adaptive::{AdaptiveSettings, PixelVariance};
+use crate::tone_map::luminance;
+use crate::hittable::{Hittable, HittableTrait};
+use crate::hittable_list::HittableList;
+use crate::hit_record::HitRecord;
+use crate::material::MaterialTrait;
+use crate::scatter_record::{Lobe, ScatterRecord};
+use crate::bidirectional::{bidirectional_colour, LightSplat};
+use crate::photon_map::{PhotonMap, PhotonSettings};
+use crate::debug_view::{debug_colour, DebugView};
+use crate::spectrum::SampledWavelengths;
+use crate::sampler::{end_sample, seed_random_stream, start_sample, start_vertex, Sampler};
+
+const PASS_GROWTH: u32 = 4;         // Factor the number of samples per pixel grows by after each pass
+const MAX_PASS_SAMPLES: u32 = 64;   // Maximum number of samples per pixel added by a single pass
+const MAX_STORED_SAMPLES: usize = 1 << 20;  // Most samples held at once while they wait to be splatted
+const MAX_SURVIVAL: f64 = 0.95;     // Highest chance of a path surviving Russian roulette, so that every path ends
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Algorithm used to find the light carried along each sample's ray
+pub enum Integrator {
+    #[default]
+    Path,           // Follows paths from the camera, sampling lights directly at diffuse bounces
+    Bidirectional,  // Joins paths from the camera to paths from the lights, which finds caustics
+    PhotonMapping(PhotonSettings),  // Follows paths from the camera, gathering caustics from a photon map
+    Spectral,       // Follows paths from the camera carrying sampled wavelengths of light rather than RGB
+    Debug(DebugView),               // Shows a property of the first surface seen instead of its light
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Settings controlling the size and quality of a render
+pub struct RenderSettings {
+    pub image_width: usize,         // Width of the output image in pixels
+    pub image_height: usize,        // Height of the output image in pixels
+    pub samples_per_pixel: i32,     // Number of rays traced for each pixel, or the most if sampling adaptively
+    pub min_depth: i32,             // Number of bounces before Russian roulette can end a path
+    pub max_depth: Option<i32>,     // Hard cap on the number of bounces for each path, if any
+    #[serde(with = "crate::checkpoint::hex_seed")]
+    pub seed: u64,                  // Seed from which every sample's random numbers are derived
+    pub tile_size: usize,           // Width and height of the tiles rendered by each thread
+    pub filter: Filter,             // Reconstruction filter used to splat samples into pixels
+    pub aovs: bool,                 // Whether to record the first hit of each sample for AOVs
+    pub adaptive: Option<AdaptiveSettings>, // Settings to stop sampling pixels once they converge, if any
+    pub light_sampling: bool,       // Whether diffuse surfaces sample the scene's lights directly
+    pub integrator: Integrator,     // Algorithm used to find the light carried along each ray
+    pub sampler: Sampler,           // Way the random numbers of each pixel's samples are chosen
+}
+
+impl Default for RenderSettings {
+    /// Settings matching the command line's defaults, with a fixed seed
+    fn default() -> Self {
+        RenderSettings {
+            image_width: 400,
+            image_height: 400,
+            samples_per_pixel: 500,
+            min_depth: 3,
+            max_depth: None,
+            seed: 0,
+            tile_size: 16,
+            filter: Filter::default(),
+            aovs: false,
+            adaptive: None,
+            light_sampling: true,
+            integrator: Integrator::default(),
+            sampler: Sampler::default(),
+        }
+    }
+}
+
+/// Gets the colour of a given ray in the world by following its path iteratively, keeping
+/// track of the throughput of every bounce so far. After `min_depth` bounces, Russian roulette
+/// ends paths with a chance depending on their throughput and scales up those which survive,
+/// so that no light is lost on average, while `max_depth` optionally caps the number of bounces.
+/// Diffuse surfaces sample the given lights directly with shadow rays, combined with the rays
+/// they scatter by multiple importance sampling, while an empty list leaves every light to be
+/// found by chance
+pub fn path_colour(
+    ray: &Ray, background: &Background, world: &HittableList, lights: &HittableList, min_depth: i32, max_depth: Option<i32>
+) -> Vec3 {
+    trace_path(ray, background, world, lights, None, None, min_depth, max_depth)
+}
+
+/// Gets the colour of a ray with the path tracer, carrying a few wavelengths sampled across
+/// the visible spectrum instead of red, green and blue. Colours of textures and lights are
+/// upsampled to spectra along the way, and the light found at each wavelength is converted
+/// back to RGB through CIE XYZ. Dispersive materials bend the path by its hero wavelength,
+/// after which only that wavelength is followed
+pub fn spectral_colour(
+    ray: &Ray, background: &Background, world: &HittableList, lights: &HittableList, min_depth: i32, max_depth: Option<i32>
+) -> Vec3 {
+    let mut wavelengths = SampledWavelengths::sample();
+    let radiance = trace_path(ray, background, world, lights, None, Some(&mut wavelengths), min_depth, max_depth);
+    wavelengths.to_rgb(radiance)
+}
+
+/// Gets the colour of a ray in a scene with the path tracer, adding the light of a caustic
+/// photon map at each diffuse surface. Light reaching a diffuse surface through specular
+/// objects from one of the scene's lights is found by the photons rather than the path, so
+/// that it isn't counted twice
+pub fn photon_colour(
+    ray: &Ray, scene: &Scene, lights: &HittableList, photon_map: &PhotonMap, min_depth: i32, max_depth: Option<i32>
+) -> Vec3 {
+    trace_path(ray, &scene.background, &scene.world, lights, Some((photon_map, &scene.surface_lights)), None, min_depth, max_depth)
+}
+
+/// Follows a ray's path as in `path_colour`, gathering caustics at diffuse surfaces from a
+/// photon map along with the lights its photons were emitted from, if given. Each channel
+/// carries one of the given wavelengths instead, if any, which a dispersive material can
+/// end all but the hero of
+#[allow(clippy::too_many_arguments)]
+fn trace_path(
+    ray: &Ray, background: &Background, world: &HittableList, lights: &HittableList,
+    caustics: Option<(&PhotonMap, &HittableList)>, mut spectrum: Option<&mut SampledWavelengths>,
+    min_depth: i32, max_depth: Option<i32>
+) -> Vec3 {
+    // The wavelengths themselves stay the same even if some stop being followed
+    let wavelengths = spectrum.as_deref().copied();
+    let hero = wavelengths.map(|wavelengths| wavelengths.hero());
+    let mut radiance = Vec3::zero();
+    let mut throughput = Vec3::one();
+    let mut ray = Ray::new(ray.origin, ray.direction, ray.time);
+    let mut lobe_pdf = None;
+    let mut depth = 0;
+
+    // Whether the path has only bounced specularly since gathering photons at a diffuse surface
+    let mut gathered = false;
+
+    while max_depth.is_none_or(|max_depth| depth < max_depth) {
+        // Each bounce draws from its own dimensions of the sampler, however many the last used
+        start_vertex(depth as u32);
+        let hit_record = match world.hit(&ray, 0.001, INFINITY) {
+            Some(hit_record) => hit_record,
+            None => {
+                radiance += throughput * upsample(wavelengths.as_ref(), weighted_background(&ray, background, lights, lobe_pdf));
+                break;
+            }
+        };
+        // Light reaching the last diffuse surface through specular objects was carried there by photons
+        let caustic = match caustics {
+            Some((_, emitters)) => gathered && lobe_pdf.is_none() && is_sampled_light(emitters, &ray, &hit_record),
+            None => false,
+        };
+        if !caustic {
+            radiance += throughput * upsample(wavelengths.as_ref(), weighted_emission(&ray, &hit_record, lights, lobe_pdf));
+        }
+
+        // Light split by a dispersive material only follows the hero wavelength's direction
+        let scatter_record = hit_record.material.scatter(&ray.with_wavelength(hero), &hit_record);
+        if let Some(spectrum) = spectrum.as_deref_mut() {
+            if hit_record.material.is_dispersive() && matches!(scatter_record, Some(ScatterRecord::Specular { .. })) {
+                spectrum.terminate_secondary();
+                throughput = Vec3::new(throughput.x, 0., 0.);
+            }
+        }
+
+        match scatter_record {
+            Some(ScatterRecord::Specular { attenuation, ray: scattered }) => {
+                throughput = throughput * upsample(wavelengths.as_ref(), attenuation);
+                ray = scattered;
+                lobe_pdf = None;
+            },
+            Some(ScatterRecord::Diffuse(lobe)) => {
+                // Light reached after the last bounce wouldn't be counted, so isn't sampled
+                if max_depth.is_none_or(|max_depth| depth + 1 < max_depth) && !lights.objects.is_empty() {
+                    radiance += throughput * sample_light(&ray, &hit_record, &lobe, world, lights, wavelengths.as_ref());
+                }
+
+                // Photons are only stored on surfaces, so media find caustics along their paths
+                if let Some((photon_map, _)) = caustics {
+                    gathered = matches!(lobe, Lobe::Cosine { .. });
+                    if gathered {
+                        radiance += throughput * photon_map.gather(&hit_record.point, &lobe);
+                    }
+                }
+
+                let scattered = Ray::new(hit_record.point, lobe.sample(), ray.time);
+                let pdf = lobe.pdf(&scattered.direction);
+                if pdf <= 0. {
+                    break;
+                }
+                throughput = throughput * upsample(wavelengths.as_ref(), lobe.eval(&scattered.direction)) / pdf;
+                ray = scattered;
+                lobe_pdf = Some(pdf);
+            },
+            None => break
+        }
+        depth += 1;
+
+        // Paths carrying little light are likely to end, while those surviving carry more
+        if depth >= min_depth {
+            let survival = f64::min(f64::max(throughput.x, f64::max(throughput.y, throughput.z)), MAX_SURVIVAL);
+            if random_double() >= survival {
+                break;
+            }
+            throughput /= survival;
+        }
+    }
+    radiance
+}
+
+/// Gets the colour of a given ray in the world by recursing at each bounce, up to a fixed
+/// depth beyond which paths are cut off and return black. This is kept as a reference for
+/// the iterative `path_colour`, which renders use instead
+pub fn ray_colour(ray: &Ray, background: &Background, world: &HittableList, lights: &HittableList, depth: i32) -> Vec3 {
+    trace_ray(ray, background, world, lights, depth, None)
+}
+
+/// Gets the colour of a ray, given the density with which the previous surface's lobe chose
+/// it, or None if the ray was chosen some other way such as by a specular reflection
+fn trace_ray(
+    ray: &Ray, background: &Background, world: &HittableList, lights: &HittableList, depth: i32, lobe_pdf: Option<f64>
+) -> Vec3 {
+    // Stops recursion once past the max depth
+    if depth <= 0 {
+        return Vec3::zero();
+    }
+
+    // Checks if the ray hit anything in the world
+    let hit_record = match world.hit(ray, 0.001, INFINITY) {
+        Some(hit_record) => hit_record,
+        None => return weighted_background(ray, background, lights, lobe_pdf)
+    };
+
+    let emitted = weighted_emission(ray, &hit_record, lights, lobe_pdf);
+
+    // If the ray hit anything, return the result of the scattered ray against the hit's material
+    match hit_record.material.scatter(ray, &hit_record) {
+        Some(ScatterRecord::Specular { attenuation, ray: scattered }) =>
+            emitted + attenuation * trace_ray(&scattered, background, world, lights, depth - 1, None),
+        Some(ScatterRecord::Diffuse(lobe)) => {
+            // Light reached after the last bounce wouldn't be counted, so isn't sampled
+            let direct = match depth > 1 && !lights.objects.is_empty() {
+                true => sample_light(ray, &hit_record, &lobe, world, lights, None),
+                false => Vec3::zero(),
+            };
+
+            let scattered = Ray::new(hit_record.point, lobe.sample(), ray.time);
+            let pdf = lobe.pdf(&scattered.direction);
+            if pdf <= 0. {
+                return emitted + direct;
+            }
+            let indirect = trace_ray(&scattered, background, world, lights, depth - 1, Some(pdf));
+            emitted + direct + lobe.eval(&scattered.direction) * indirect / pdf
+        },
+        None => emitted
+    }
+}
+
+/// Gets the light emitted by a ray's hit, weighted against the chance of sampling it directly
+/// given the density with which the previous surface's lobe chose the ray, if it did
+fn weighted_emission(ray: &Ray, hit_record: &HitRecord, lights: &HittableList, lobe_pdf: Option<f64>) -> Vec3 {
+    let emitted = hit_record.material.emitted(hit_record.u, hit_record.v, &hit_record.point);
+    match lobe_pdf {
+        // Light hit after a diffuse bounce could also have been found by sampling the lights
+        Some(lobe_pdf) if is_sampled_light(lights, ray, hit_record) =>
+            power_heuristic(lobe_pdf, lights.pdf_value(&ray.origin, &ray.direction)) * emitted,
+        _ => emitted
+    }
+}
+
+/// Gets the background light arriving along a ray which escaped the scene, weighted against
+/// the chance of sampling it directly as in `weighted_emission`
+fn weighted_background(ray: &Ray, background: &Background, lights: &HittableList, lobe_pdf: Option<f64>) -> Vec3 {
+    let light = background.value(&ray.direction);
+    match lobe_pdf {
+        // Environment maps can be among the sampled lights, while plain colours are only found by chance
+        Some(lobe_pdf) if environment(lights).is_some() =>
+            power_heuristic(lobe_pdf, lights.pdf_value(&ray.origin, &ray.direction)) * light,
+        _ => light
+    }
+}
+
+/// Estimates the light arriving at a diffuse hit directly from a light and scattered by its
+/// lobe, by tracing a shadow ray in a direction chosen by the lights. The light and lobe are
+/// each upsampled to the given wavelengths, if any, before being multiplied together
+fn sample_light(
+    ray: &Ray, hit_record: &HitRecord, lobe: &Lobe, world: &HittableList, lights: &HittableList,
+    wavelengths: Option<&SampledWavelengths>
+) -> Vec3 {
+    let direction = lights.random(&hit_record.point);
+    let shadow_ray = Ray::new(hit_record.point, direction, ray.time);
+    let light_pdf = lights.pdf_value(&hit_record.point, &direction);
+    let scattered = lobe.eval(&direction);
+    if light_pdf <= 0. || scattered.near_zero() {
+        return Vec3::zero();
+    }
+
+    // Only light from the sampled lights is counted, since other emitters are found by chance,
+    // while shadow rays escaping the scene reach the environment map if one is sampled
+    let emitted = match world.hit(&shadow_ray, 0.001, INFINITY) {
+        Some(light_hit) if is_sampled_light(lights, &shadow_ray, &light_hit) =>
+            light_hit.material.emitted(light_hit.u, light_hit.v, &light_hit.point),
+        None => match environment(lights) {
+            Some(environment) => environment.value(&direction),
+            None => return Vec3::zero()
+        },
+        _ => return Vec3::zero()
+    };
+    let (scattered, emitted) = (upsample(wavelengths, scattered), upsample(wavelengths, emitted));
+    power_heuristic(light_pdf, lobe.pdf(&direction)) * scattered * emitted / light_pdf
+}
+
+/// Gets the values of an RGB colour's spectrum at the given wavelengths, or the colour itself
+/// if rendering in RGB
+fn upsample(wavelengths: Option<&SampledWavelengths>, rgb: Vec3) -> Vec3 {
+    wavelengths.map_or(rgb, |wavelengths| wavelengths.upsample(rgb))
+}
+
+/// Weights a sample taken with one strategy against another strategy which could also have
+/// taken it, given the density of each choosing it
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    pdf * pdf / (pdf * pdf + other_pdf * other_pdf)
+}
+
+/// Checks whether a ray's hit is on one of the lights which are sampled directly
+fn is_sampled_light(lights: &HittableList, ray: &Ray, hit_record: &HitRecord) -> bool {
+    // Lights are hit at the same distance as in the world, give or take rounding
+    let tolerance = 1e-9 * f64::max(hit_record.t, 1.);
+    !lights.objects.is_empty() && lights.hit(ray, hit_record.t - tolerance, hit_record.t + tolerance).is_some()
+}
+
+/// Gets the environment map among the lights which are sampled directly, if there is one
+fn environment(lights: &HittableList) -> Option<&Environment> {
+    lights.objects.iter().find_map(|light| match light.as_ref() {
+        Hittable::Environment(environment) => Some(environment.as_ref()),
+        _ => None
+    })
+}
+
+/// Rectangle of pixels rendered together by one thread, in rows from the top of the image
+struct Tile {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+/// Splits an image into square tiles of a given size, with smaller tiles at the right and
+/// bottom edges
+fn tiles(width: usize, height: usize, tile_size: usize) -> Vec<Tile> {
+    let mut tiles = vec![];
+    for y0 in (0..height).step_by(tile_size) {
+        for x0 in (0..width).step_by(tile_size) {
+            tiles.push(Tile { x0, y0, x1: usize::min(x0 + tile_size, width), y1: usize::min(y0 + tile_size, height) });
+        }
+    }
+    tiles
+}
+
+/// Sample traced through a pixel, along with its position within the pixel
+struct Sample {
+    dx: f64,            // Horizontal offset of the sample from the pixel's left edge
+    dy: f64,            // Vertical offset of the sample from the pixel's bottom edge
+    radiance: Vec3,     // Radiance carried by the sample's ray
+    aovs: Option<AovPixel>, // First hit of the sample's ray, if AOVs are being recorded
+    splats: Vec<LightSplat>,    // Light the sample traced straight to the camera, landing in any pixel
+}
+
+/// Samples traced through every pixel of a tile at each index of a batch, along with the
+/// state of the tile's pixels once they've been taken. Samples are stored by index, then in
+/// rows from the top of the tile
+struct TracedTile {
+    radiance: Vec<Option<Vec3>>,    // Radiance of each sample, if the pixel took one at that index
+    weights: Vec<f64>,              // Each sample's filter weights for `span` columns then `span` rows
+    splats: Vec<Vec<LightSplat>>,   // Light each sample traced straight to the camera
+    samples: Vec<u32>,              // Number of samples taken through each of the tile's pixels
+    variance: Vec<PixelVariance>,   // Running variance of each of the tile's pixels
+    aovs: Option<Vec<AovPixel>>,    // First hits of each of the tile's pixels, if AOVs are being recorded
+}
+
+/// Batch of samples traced through every tile of an image, which pixels gather their filtered
+/// radiance from
+struct Batch {
+    first: u32,                 // Index of the first sample in the batch
+    span: usize,                // Number of rows and columns each sample can contribute to
+    tile_size: usize,           // Width and height of every tile except those at the right and bottom edges
+    tiles_across: usize,        // Number of tiles in each row of tiles
+    traced: Vec<TracedTile>,    // Samples traced through each tile, in the order of the tiles
+}
+
+impl Batch {
+    /// Gets the tile holding a pixel and the pixel's position within it
+    fn locate<'a>(&'a self, tiles: &[Tile], x: usize, row: usize) -> (&'a TracedTile, usize, usize) {
+        let index = (row / self.tile_size) * self.tiles_across + x / self.tile_size;
+        let tile = &tiles[index];
+        let pixels = (tile.x1 - tile.x0) * (tile.y1 - tile.y0);
+        (&self.traced[index], (row - tile.y0) * (tile.x1 - tile.x0) + x - tile.x0, pixels)
+    }
+}
+
+/// Traces a single sample through a pixel, given its column and its row from the top
+fn render_sample(
+    scene: &Scene, settings: &RenderSettings, photon_map: Option<&PhotonMap>, x: usize, row: usize, sample: u32
+) -> Sample {
+    let width = settings.image_width;
+    let height = settings.image_height;
+
+    // Each sample has its own random number stream, so that it doesn't matter which
+    // thread takes it or in which pass, and its own numbers from the sampler
+    let pixel = (row * width + x) as u64;
+    seed_random_stream(settings.seed, pixel, sample as u64);
+    start_sample(settings.sampler, settings.seed, pixel, sample, settings.samples_per_pixel as u32);
+
+    let y = height - 1 - row;
+    let dx = random_double();
+    let dy = random_double();
+    let u = (x as f64 + dx) / (width - 1) as f64;
+    let v = (y as f64 + dy) / (height - 1) as f64;
+    let r = scene.camera.get_ray(u, v);
+    // Light subpaths can only start from lights with surfaces
+    let lights = match (settings.light_sampling, settings.integrator) {
+        (true, Integrator::Bidirectional) => &scene.surface_lights,
+        (true, _) => &scene.lights,
+        (false, _) => &HittableList::new(),
+    };
+    let (radiance, splats) = match settings.integrator {
+        Integrator::Path =>
+            (path_colour(&r, &scene.background, &scene.world, lights, settings.min_depth, settings.max_depth), vec![]),
+        Integrator::Bidirectional =>
+            bidirectional_colour(&r, scene, lights, width, height, settings.min_depth, settings.max_depth),
+        Integrator::PhotonMapping(_) => {
+            let photon_map = photon_map.expect("photons are emitted before any pass is rendered");
+            (photon_colour(&r, scene, lights, photon_map, settings.min_depth, settings.max_depth), vec![])
+        },
+        Integrator::Spectral =>
+            (spectral_colour(&r, &scene.background, &scene.world, lights, settings.min_depth, settings.max_depth), vec![]),
+        Integrator::Debug(view) => (debug_colour(&r, scene, &view), vec![]),
+    };
+
+    end_sample();
+
+    // The first hit is found after the path is traced, so that it doesn't affect its random numbers
+    let aovs = settings.aovs.then(|| AovPixel::trace(scene, &r));
+    Sample { dx, dy, radiance, aovs, splats }
+}
+
+/// Renders a scene, returning the accumulated radiance of every pixel and incrementing the
+/// given progress bar as each sample is completed
+pub fn render(scene: &Scene, settings: &RenderSettings, progress_bar: &ProgressBar) -> RenderBuffer {
+    let mut buffer = match settings.aovs {
+        true => RenderBuffer::with_aovs(settings.image_width, settings.image_height),
+        false => RenderBuffer::new(settings.image_width, settings.image_height),
+    };
+    render_progressive(scene, settings, &mut buffer, progress_bar, |_| {});
+    buffer
+}
+
+/// Renders a scene into a buffer in passes of increasing sample counts (1, 4, 16, 64, 128, ...
+/// samples per pixel) until every pixel has the full number of samples or has converged,
+/// calling `on_pass` with the buffer after each pass. Samples already in the buffer are kept,
+/// so a render can be resumed, and the result doesn't depend on how the samples were split
+/// into passes. AOVs are only recorded if the buffer was created with them
+pub fn render_progressive<F: FnMut(&RenderBuffer)>(
+    scene: &Scene, settings: &RenderSettings, buffer: &mut RenderBuffer, progress_bar: &ProgressBar,
+    mut on_pass: F
+) {
+    let samples_per_pixel = settings.samples_per_pixel as u32;
+    let tiles = tiles(settings.image_width, settings.image_height, usize::max(settings.tile_size, 1));
+
+    // Passes which the buffer already has every sample of are skipped
+    let completed = fewest_samples(buffer, settings).unwrap_or(u32::MAX);
+
+    // Photons are emitted from the render's seed, so a resumed render rebuilds the same map
+    let photon_map = match settings.integrator {
+        Integrator::PhotonMapping(photons) if completed < samples_per_pixel => {
+            progress_bar.set_message("emitting photons");
+            Some(PhotonMap::emit(scene, &photons, settings.seed, settings.max_depth))
+        },
+        _ => None,
+    };
+
+    let mut target = 1;
+    loop {
+        target = u32::min(target, samples_per_pixel);
+        if target > completed {
+            progress_bar.set_message(format!("{target} spp"));
+            render_pass(scene, settings, photon_map.as_ref(), buffer, &tiles, target, progress_bar);
+            on_pass(buffer);
+        }
+
+        if target == samples_per_pixel {
+            break;
+        }
+        target = u32::min(target.saturating_mul(PASS_GROWTH), target.saturating_add(MAX_PASS_SAMPLES));
+    }
+}
+
+/// Checks whether a pixel has converged, so that adaptive sampling takes no more samples through it
+fn converged(settings: &RenderSettings, variance: &PixelVariance, samples: u32) -> bool {
+    settings.adaptive.is_some_and(|adaptive| adaptive.converged(variance, samples))
+}
+
+/// Gets the fewest samples taken through any pixel which hasn't converged, or None if every
+/// pixel has
+fn fewest_samples(buffer: &RenderBuffer, settings: &RenderSettings) -> Option<u32> {
+    (0..buffer.samples.len())
+        .filter(|&i| !converged(settings, &buffer.variance[i], buffer.samples[i]))
+        .map(|i| buffer.samples[i])
+        .min()
+}
+
+/// Renders each tile in parallel until all of its pixels have a target number of samples or
+/// have converged
+fn render_pass(
+    scene: &Scene, settings: &RenderSettings, photon_map: Option<&PhotonMap>, buffer: &mut RenderBuffer, tiles: &[Tile],
+    target: u32, progress_bar: &ProgressBar
+) {
+    let width = settings.image_width;
+    let tile_size = usize::max(settings.tile_size, 1);
+    let span = 2 * settings.filter.pixel_reach() + 1;
+
+    // Samples are traced in batches of indices small enough to be held until they're splatted
+    let batch_size = u32::max((MAX_STORED_SAMPLES / buffer.radiance.len().max(1)) as u32, 1);
+    let mut first = fewest_samples(buffer, settings).unwrap_or(target);
+    while first < target {
+        let end = u32::min(first.saturating_add(batch_size), target);
+
+        let current: &RenderBuffer = buffer;
+        let traced = tiles.par_iter()
+            .map(|tile| trace_tile(scene, settings, photon_map, current, tile, first..end, progress_bar))
+            .collect();
+        let batch = Batch { first, span, tile_size, tiles_across: width.div_ceil(tile_size), traced };
+
+        // Each pixel gathers the samples of its neighbours, including those in other tiles,
+        // into a buffer for its own tile
+        let splatted: Vec<Vec<(Vec3, f64)>> = tiles.par_iter().map(|tile| {
+            let mut sums = Vec::with_capacity((tile.x1 - tile.x0) * (tile.y1 - tile.y0));
+            for row in tile.y0..tile.y1 {
+                for x in tile.x0..tile.x1 {
+                    let i = row * width + x;
+                    sums.push(splat(&batch, tiles, settings, x, row, first..end, current.radiance[i], current.weights[i]));
+                }
+            }
+            sums
+        }).collect();
+
+        // Light traced straight to the camera is added in the order of the samples and then of
+        // the pixels which traced it, with one light path traced by each sample
+        if settings.integrator == Integrator::Bidirectional {
+            for sample in first..end {
+                for row in 0..settings.image_height {
+                    for x in 0..width {
+                        let (traced, j, pixels) = batch.locate(tiles, x, row);
+                        let k = (sample - first) as usize * pixels + j;
+                        for splat in &traced.splats[k] {
+                            buffer.light[splat.pixel] += splat.radiance;
+                        }
+                        buffer.light_paths += traced.radiance[k].is_some() as u64;
+                    }
+                }
+            }
+        }
+
+        // Tile buffers each cover their own pixels, so are merged by copying them back
+        for ((tile, traced), sums) in tiles.iter().zip(batch.traced).zip(splatted) {
+            let mut j = 0;
+            for row in tile.y0..tile.y1 {
+                for x in tile.x0..tile.x1 {
+                    let i = row * width + x;
+                    (buffer.radiance[i], buffer.weights[i]) = sums[j];
+                    buffer.samples[i] = traced.samples[j];
+                    buffer.variance[i] = traced.variance[j];
+                    if let (Some(pixel_aovs), Some(tile_aovs)) = (&mut buffer.aovs, &traced.aovs) {
+                        pixel_aovs[i] = tile_aovs[j];
+                    }
+                    j += 1;
+                }
+            }
+        }
+
+        first = end;
+    }
+}
+
+/// Traces a range of sample indices through every pixel of a tile which still needs them,
+/// checking each pixel for convergence between indices
+fn trace_tile(
+    scene: &Scene, settings: &RenderSettings, photon_map: Option<&PhotonMap>, buffer: &RenderBuffer, tile: &Tile,
+    indices: Range<u32>, progress_bar: &ProgressBar
+) -> TracedTile {
+    let width = settings.image_width;
+    let samples_per_pixel = settings.samples_per_pixel as u32;
+    let reach = settings.filter.pixel_reach();
+    let span = 2 * reach + 1;
+
+    // The tile's pixels are copied out so that they can be updated as samples are taken
+    let pixel_indices: Vec<usize> = (tile.y0..tile.y1).flat_map(|row| (tile.x0..tile.x1).map(move |x| row * width + x)).collect();
+    let mut samples: Vec<u32> = pixel_indices.iter().map(|&i| buffer.samples[i]).collect();
+    let mut variance: Vec<PixelVariance> = pixel_indices.iter().map(|&i| buffer.variance[i]).collect();
+    let mut aovs: Option<Vec<AovPixel>> = buffer.aovs.as_ref().map(|aovs| pixel_indices.iter().map(|&i| aovs[i]).collect());
+
+    let stored = pixel_indices.len() * indices.len();
+    let mut radiance = Vec::with_capacity(stored);
+    let mut splats = Vec::with_capacity(stored);
+    let mut weights = vec![0.; 2 * span * stored];
+    let mut weights_chunks = weights.chunks_mut(2 * span);
+    for sample in indices {
+        let mut taken = 0;
+        for (j, &i) in pixel_indices.iter().enumerate() {
+            let weights = weights_chunks.next().unwrap();
+            if samples[j] > sample || converged(settings, &variance[j], samples[j]) {
+                radiance.push(None);
+                splats.push(vec![]);
+                continue;
+            }
+            let (x, row) = (i % width, i / width);
+            let traced = render_sample(scene, settings, photon_map, x, row, sample);
+            radiance.push(Some(traced.radiance));
+            splats.push(traced.splats);
+
+            // AOVs and noise aren't filtered, so each sample only counts towards its own pixel
+            if let (Some(pixel_aovs), Some(sample_aovs)) = (&mut aovs, traced.aovs) {
+                pixel_aovs[j].add(&sample_aovs, sample == 0);
+            }
+            variance[j].add(luminance(traced.radiance), sample + 1);
+            samples[j] = sample + 1;
+            taken += 1;
+
+            // Samples skipped by converging still count towards the render's progress
+            if converged(settings, &variance[j], samples[j]) {
+                progress_bar.inc(samples_per_pixel.saturating_sub(sample + 1) as u64);
+            }
+
+            // The filter is separable, so its weights are found once for each nearby
+            // column and row. Rows count down the image, while `dy` points up it
+            for k in 0..span {
+                let offset = k as f64 - reach as f64;
+                weights[k] = settings.filter.evaluate_axis(traced.dx - 0.5 - offset);
+                weights[span + k] = settings.filter.evaluate_axis(traced.dy - 0.5 + offset);
+            }
+        }
+        progress_bar.inc(taken);
+    }
+
+    TracedTile { radiance, weights, splats, samples, variance, aovs }
+}
+
+/// Adds the weighted radiance of every sample in a batch which lies within the filter's
+/// radius of a pixel to the pixel's sums. Samples are added one index at a time and then in
+/// the order of the pixels which took them, so that every pixel adds its neighbours' samples
+/// in the same order however the render is split into passes and tiles
+#[allow(clippy::too_many_arguments)]
+fn splat(
+    batch: &Batch, tiles: &[Tile], settings: &RenderSettings, x: usize, row: usize, indices: Range<u32>,
+    mut radiance: Vec3, mut weight: f64
+) -> (Vec3, f64) {
+    let reach = settings.filter.pixel_reach();
+    let span = batch.span;
+    let rows = row.saturating_sub(reach)..usize::min(row + reach + 1, settings.image_height);
+    let columns = x.saturating_sub(reach)..usize::min(x + reach + 1, settings.image_width);
+
+    for sample in indices {
+        for sample_row in rows.clone() {
+            for sample_x in columns.clone() {
+                let (traced, j, pixels) = batch.locate(tiles, sample_x, sample_row);
+                let k = (sample - batch.first) as usize * pixels + j;
+                if let Some(sample_radiance) = traced.radiance[k] {
+                    let weights = &traced.weights[2 * span * k..2 * span * (k + 1)];
+                    let w = weights[x + reach - sample_x] * weights[span + row + reach - sample_row];
+                    if w != 0. {
+                        radiance += w * sample_radiance;
+                        weight += w;
+                    }
+                }
+            }
+        }
+    }
+    (radiance, weight)
+}
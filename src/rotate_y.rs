@@ -1,20 +1,24 @@
 use std::sync::Arc;
-use ray_tracing::{degrees_to_radians, INFINITY};
+use crate::{degrees_to_radians, INFINITY};
 use crate::aabb::AABB;
+use crate::bvh::BVHStatistics;
 use crate::hit_record::HitRecord;
 use crate::hittable::{Hittable, HittableTrait};
+use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
 #[derive(Debug)]
 pub struct RotateY {
     object: Arc<Hittable>,
+    angle: f64,
     sin_theta: f64,
     cos_theta: f64,
     bbox: Option<AABB>,
 }
 
 impl RotateY {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(object: Arc<Hittable>, angle: f64) -> Hittable {
         let radians = degrees_to_radians(angle);
         let sin_theta = radians.sin();
@@ -46,12 +50,9 @@ impl RotateY {
             }
         }
 
-        let bbox = match bbox{
-            None => None,
-            Some(_) => Some(AABB::new(min, max))
-        };
+        let bbox = bbox.map(|_| AABB::new(min, max));
 
-        Hittable::RotateY(RotateY {object, cos_theta, sin_theta, bbox})
+        Hittable::RotateY(RotateY {object, angle, cos_theta, sin_theta, bbox})
     }
 
     fn rotate_ray_vec(&self, v: &Vec3) -> Vec3 {
@@ -81,6 +82,7 @@ impl HittableTrait for RotateY {
                 let mut hit_record = HitRecord::new(
                     self.rotate_record_vec(&rec.point), &rec.material, rec.u, rec.v, rec.t
                 );
+                hit_record.object_id = rec.object_id;
                 hit_record.calculate_face_normal(&rotated_ray, self.rotate_record_vec(&rec.normal));
 
                 Some(hit_record)
@@ -89,11 +91,51 @@ impl HittableTrait for RotateY {
     }
 
     fn bounding_box(&self, _: f64, _: f64) -> Option<AABB> {
-        match &self.bbox {
-            None => None,
-            Some(b) => {
-                Some(AABB::new(b.minimum, b.maximum))
-            }
+        self.bbox.as_ref().map(|b| AABB::new(b.minimum, b.maximum))
+    }
+
+    fn object_count(&self) -> u32 {
+        self.object.object_count()
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        self.object.collect_materials(materials);
+    }
+
+    fn is_light(&self) -> bool {
+        self.object.is_light()
+    }
+
+    /// Lights within the object are rotated along with it
+    fn collect_lights(&self, lights: &mut Vec<Arc<Hittable>>) {
+        let mut object_lights = vec![];
+        self.object.collect_lights(&mut object_lights);
+        for light in object_lights {
+            lights.push(Arc::new(RotateY::new(light, self.angle)));
         }
     }
+
+    fn collect_statistics(&self, statistics: &mut Vec<BVHStatistics>) {
+        self.object.collect_statistics(statistics);
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(&self.rotate_ray_vec(origin), &self.rotate_ray_vec(direction))
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        self.rotate_record_vec(&self.object.random(&self.rotate_ray_vec(origin)))
+    }
+
+    fn area(&self) -> f64 {
+        self.object.area()
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        self.object.sample_surface().map(|mut hit_record| {
+            hit_record.point = self.rotate_record_vec(&hit_record.point);
+            hit_record.normal = self.rotate_record_vec(&hit_record.normal);
+            hit_record
+        })
+    }
 }
\ No newline at end of file
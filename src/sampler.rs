@@ -0,0 +1,207 @@
+use std::cell::Cell;
+use serde::{Deserialize, Serialize};
+use crate::{mix_seed, seed_random};
+
+pub const CAMERA_DIMENSIONS: u32 = 6;   // Dimensions used before the first bounce: pixel offset, lens, time and wavelength
+pub const VERTEX_DIMENSIONS: u32 = 8;   // Dimensions set aside for the random numbers used at each bounce of a path
+
+// Bases of the first dimensions of the Halton sequence, beyond which it falls back to independent numbers
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107,
+    109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193, 197, 199, 211, 223, 227, 229,
+    233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307, 311,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Way the random numbers of each sample through a pixel are chosen. Numbers are drawn in
+/// dimensions, the first few of which place the camera ray, followed by a fixed number for
+/// each bounce of the path, so that the same dimension always plays the same role. Samplers
+/// other than the independent one spread each dimension's numbers evenly over a pixel's
+/// samples, and numbers drawn beyond a bounce's dimensions are independent
+pub enum Sampler {
+    #[default]
+    Independent,    // Independent random numbers from each sample's stream
+    Stratified,     // One number in each of as many equal strata as there are samples per pixel, shuffled per dimension
+    Halton,         // Halton sequence in a prime base for each dimension, with Owen scrambling for each pixel
+    Sobol,          // Sobol sequence in pairs of dimensions, with Owen scrambling for each pixel and pair
+}
+
+impl Sampler {
+    /// Gets the number in one dimension of one of a pixel's samples, given a key identifying
+    /// the pixel and the seed, or None if the number should come from the sample's stream
+    pub fn sample(&self, key: u64, index: u32, samples: u32, dimension: u32) -> Option<f64> {
+        match self {
+            Sampler::Independent => None,
+            Sampler::Stratified => {
+                // Samples are shuffled between strata differently in each dimension
+                let samples = u32::max(samples, 1);
+                let stratum = permute(index % samples, samples, hash(key, dimension, 0) as u32);
+                Some((stratum as f64 + unit(hash(key, dimension, index as u64 + 1))) / samples as f64)
+            },
+            Sampler::Halton => {
+                let base = *PRIMES.get(dimension as usize)?;
+                Some(scrambled_radical_inverse(index, base, hash(key, dimension, 0)))
+            },
+            Sampler::Sobol => {
+                // Each pair of dimensions takes the first two dimensions of the Sobol sequence
+                // in its own shuffled order, so that every pair is well spread out
+                let pair = dimension / 2;
+                let shuffled = owen_scramble(index, hash(key, pair, 1) as u32);
+                let value = owen_scramble(sobol(shuffled, dimension % 2), hash(key, dimension, 2) as u32);
+                Some(value as f64 / 4294967296.)
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Sample whose random numbers are currently being drawn on a thread
+struct SampleState {
+    sampler: Sampler,   // Sampler choosing the numbers
+    key: u64,           // Key identifying the pixel and seed
+    index: u32,         // Index of the sample through its pixel
+    samples: u32,       // Number of samples taken through the pixel
+    dimension: u32,     // Next dimension to draw from
+    end: u32,           // Dimension at which the current bounce's dimensions run out
+}
+
+thread_local! {
+    // Sample being traced by the current thread, if its numbers come from a sampler
+    static SAMPLE: Cell<Option<SampleState>> = const { Cell::new(None) };
+}
+
+/// Seeds the current thread's random number generator with the stream for one sample of one
+/// pixel, so that the numbers used by each sample don't depend on which thread takes it
+pub fn seed_random_stream(seed: u64, pixel: u64, sample: u64) {
+    seed_random(mix_seed(mix_seed(mix_seed(seed) ^ pixel) ^ sample));
+}
+
+/// Starts drawing random numbers for one sample of a pixel from a sampler, beginning with the
+/// camera's dimensions. Independent samplers leave every number to the sample's stream
+pub fn start_sample(sampler: Sampler, seed: u64, pixel: u64, index: u32, samples: u32) {
+    let state = SampleState {
+        sampler, key: mix_seed(mix_seed(seed) ^ pixel), index, samples, dimension: 0, end: CAMERA_DIMENSIONS
+    };
+    SAMPLE.with(|sample| sample.set((sampler != Sampler::Independent).then_some(state)));
+}
+
+/// Stops drawing random numbers from a sampler, so that they all come from the thread's stream
+pub fn end_sample() {
+    SAMPLE.with(|sample| sample.set(None));
+}
+
+/// Moves on to the dimensions of a given bounce of the current sample's path
+pub fn start_vertex(depth: u32) {
+    SAMPLE.with(|sample| {
+        if let Some(mut state) = sample.get() {
+            state.dimension = CAMERA_DIMENSIONS + depth * VERTEX_DIMENSIONS;
+            state.end = state.dimension + VERTEX_DIMENSIONS;
+            sample.set(Some(state));
+        }
+    });
+}
+
+/// Draws the next number of the current sample from its sampler, or None if there's no
+/// sampler or the current bounce has run out of dimensions
+pub(crate) fn next_sample() -> Option<f64> {
+    SAMPLE.with(|sample| {
+        let mut state = sample.get()?;
+        if state.dimension >= state.end {
+            return None;
+        }
+        let value = state.sampler.sample(state.key, state.index, state.samples, state.dimension);
+        state.dimension += 1;
+        sample.set(Some(state));
+        value
+    })
+}
+
+/// Hashes a key, a dimension and a further value together
+fn hash(key: u64, dimension: u32, value: u64) -> u64 {
+    mix_seed(mix_seed(key ^ dimension as u64) ^ value)
+}
+
+/// Maps a hash to a number between 0 and 1
+fn unit(hash: u64) -> f64 {
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Gets the radical inverse of an index in a base, mirroring its digits about the point, with
+/// each digit randomly permuted depending on the digits before it. Digits continue past the
+/// index's last one until they're too small to matter, so that its trailing zeros are scrambled too
+fn scrambled_radical_inverse(mut index: u32, base: u32, seed: u64) -> f64 {
+    let inverse_base = 1. / base as f64;
+    let mut scale = inverse_base;
+    let mut result = 0.;
+    let mut prefix = seed;
+    while 1. - (base - 1) as f64 * scale < 1. {
+        let digit = permute(index % base, base, prefix as u32);
+        result += digit as f64 * scale;
+        prefix = mix_seed(prefix ^ digit as u64);
+        scale *= inverse_base;
+        index /= base;
+    }
+    f64::min(result, 1. - f64::EPSILON / 2.)
+}
+
+/// Gets the first or second dimension of a point of the Sobol sequence as a 32-bit fraction
+fn sobol(index: u32, dimension: u32) -> u32 {
+    let mut result = 0;
+    let mut direction: u32 = 1 << 31;
+    for bit in 0..32 {
+        if index >> bit & 1 == 1 {
+            result ^= match dimension {
+                0 => 1 << (31 - bit),
+                _ => direction,
+            };
+        }
+        direction ^= direction >> 1;
+    }
+    result
+}
+
+/// Applies a random nested uniform (Owen) scramble to a 32-bit fraction, using the hash-based
+/// permutation of Laine and Karras on its reversed bits
+fn owen_scramble(x: u32, seed: u32) -> u32 {
+    let mut x = x.reverse_bits().wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x.reverse_bits()
+}
+
+/// Maps an index below a length to a random permutation of the indices chosen by a seed,
+/// using the hash-based permutation of Kensler's correlated multi-jittered sampling
+fn permute(mut i: u32, length: u32, seed: u32) -> u32 {
+    let mut mask = length - 1;
+    mask |= mask >> 1;
+    mask |= mask >> 2;
+    mask |= mask >> 4;
+    mask |= mask >> 8;
+    mask |= mask >> 16;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & mask) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & mask) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & mask) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & mask) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= mask;
+        i ^= i >> 5;
+        if i < length {
+            return (i.wrapping_add(seed)) % length;
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::PI;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+/// Result of a material scattering a ray, which is either a specular reflection or refraction
+/// in a single direction, or a lobe which can be evaluated and sampled in any direction
+pub enum ScatterRecord {
+    Specular {
+        attenuation: Vec3,  // Colour the scattered ray's light is multiplied by
+        ray: Ray,           // Ray scattered by the material
+    },
+    Diffuse(Lobe),
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Distribution of the directions a non-specular material scatters light into
+pub enum Lobe {
+    Cosine {
+        albedo: Vec3,   // Fraction of light scattered
+        normal: Vec3,   // Normal of the surface, on the side light is scattered from
+    },
+    Uniform {
+        albedo: Vec3,   // Fraction of light scattered
+    },
+}
+
+impl Lobe {
+    /// Gets the colour scattered towards a direction per unit solid angle, including the
+    /// cosine term for surfaces, so that dividing by `pdf` gives a sample's weight
+    pub fn eval(&self, direction: &Vec3) -> Vec3 {
+        match self {
+            Lobe::Cosine { albedo, .. } => self.pdf(direction) * *albedo,
+            Lobe::Uniform { albedo } => *albedo / (4. * PI),
+        }
+    }
+
+    /// Chooses a random direction to scatter light into
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            Lobe::Cosine { normal, .. } => {
+                // Adding a unit vector to the normal gives directions with a cosine distribution
+                let direction = *normal + Vec3::random_unit_vector();
+                if direction.near_zero() { *normal } else { direction }
+            },
+            Lobe::Uniform { .. } => Vec3::random_in_unit_sphere(),
+        }
+    }
+
+    /// Gets the probability density, per unit solid angle, of `sample` choosing a direction
+    pub fn pdf(&self, direction: &Vec3) -> f64 {
+        match self {
+            Lobe::Cosine { normal, .. } => f64::max(Vec3::dot(normal, &direction.unit()), 0.) / PI,
+            Lobe::Uniform { .. } => 1. / (4. * PI),
+        }
+    }
+}
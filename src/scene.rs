@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::camera::Camera;
+use crate::hittable::{Hittable, HittableTrait};
+use crate::hittable_list::HittableList;
+use crate::material::Material;
+use crate::environment::Background;
+
+/// Object storing everything needed to render a scene
+pub struct Scene {
+    pub world: HittableList,                // Objects in the scene
+    pub camera: Camera,                     // Camera the scene is viewed through
+    pub background: Background,             // Light returned by rays which don't hit anything
+    pub lights: HittableList,               // Lights in the scene which can be sampled directly
+    pub surface_lights: HittableList,       // Lights with surfaces, which light subpaths and photons start from
+    material_ids: HashMap<usize, u32>,      // ID of each material in the scene, by its address
+    extent: f64,                            // Length of the diagonal of the box bounding the scene
+}
+
+impl Scene {
+    /// Constructs a new Scene from a world, camera and background colour or environment map
+    pub fn new(world: HittableList, camera: Camera, background: impl Into<Background>) -> Self {
+        let background = background.into();
+
+        // Materials are numbered in the order they're first used, so IDs are stable across renders
+        let mut materials = vec![];
+        world.collect_materials(&mut materials);
+        let mut material_ids = HashMap::new();
+        for material in &materials {
+            let next_id = material_ids.len() as u32;
+            material_ids.entry(Arc::as_ptr(material) as usize).or_insert(next_id);
+        }
+
+        let mut lights: Vec<Arc<Hittable>> = vec![];
+        world.collect_lights(&mut lights);
+        let surface_lights = HittableList { objects: lights.clone() };
+
+        // Environment maps are sampled along with the other lights, but have no surface to emit from
+        if let Background::Environment(environment) = &background {
+            lights.push(Arc::new(Hittable::Environment(Arc::clone(environment))));
+        }
+        let lights = HittableList { objects: lights };
+
+        // Scenes which can't be bounded are treated as being a unit across
+        let extent = world.bounding_box(0., 1.).map_or(1., |bounds| (bounds.maximum - bounds.minimum).length());
+
+        Scene { world, camera, background, lights, surface_lights, material_ids, extent }
+    }
+
+    /// Gets the ID of one of the scene's materials
+    pub fn material_id(&self, material: &Arc<Material>) -> Option<u32> {
+        self.material_ids.get(&(Arc::as_ptr(material) as usize)).copied()
+    }
+
+    /// Gets the length of the diagonal of the box bounding every object in the scene, which
+    /// gives a sense of its scale
+    pub fn extent(&self) -> f64 {
+        self.extent
+    }
+}
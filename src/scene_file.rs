@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use serde::Deserialize;
+use crate::vec3::Vec3;
+use crate::camera::Camera;
+use crate::scene::Scene;
+use crate::environment::{Background, Environment};
+use crate::hittable::Hittable;
+use crate::hittable_list::HittableList;
+use crate::bvh::{BVH, BVHBuildMethod};
+use crate::sphere::Sphere;
+use crate::moving_sphere::MovingSphere;
+use crate::aa_rect::{XYRect, XZRect, YZRect};
+use crate::aa_box::AABox;
+use crate::translate::Translate;
+use crate::rotate_y::RotateY;
+use crate::constant_medium::ConstantMedium;
+use crate::triangle::Triangle;
+use crate::obj_loader::{load_obj, load_obj_mesh};
+use crate::material::Material;
+use crate::lambertian::Lambertian;
+use crate::metal::Metal;
+use crate::dielectric::{Dielectric, RefractionIndex};
+use crate::diffuse_light::DiffuseLight;
+use crate::isotropic::Isotropic;
+use crate::texture::Texture;
+use crate::solid_colour::SolidColour;
+use crate::checker_texture::CheckerTexture;
+use crate::noise_texture::{NoiseTexture, NoiseType};
+use crate::image_texture::ImageTexture;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+/// Top level description of a scene file
+pub struct SceneDescription {
+    #[serde(default)]
+    pub background: BackgroundDescription,
+    pub camera: CameraDescription,
+    #[serde(default)]
+    pub textures: HashMap<String, TextureDescription>,
+    #[serde(default)]
+    pub materials: HashMap<String, MaterialDescription>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDescription>,
+    #[serde(default)]
+    pub bvh: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+/// Description of the parameters of a scene's camera
+pub struct CameraDescription {
+    pub look_from: [f64; 3],
+    pub look_at: [f64; 3],
+    #[serde(default = "default_up")]
+    pub up: [f64; 3],
+    pub vertical_fov: f64,
+    #[serde(default)]
+    pub aperture: f64,
+    #[serde(default = "default_focus_distance")]
+    pub focus_distance: f64,
+    #[serde(default = "default_viewport_height")]
+    pub viewport_height: f64,
+    #[serde(default)]
+    pub time0: f64,
+    #[serde(default)]
+    pub time1: f64,
+}
+
+fn default_up() -> [f64; 3] { [0., 1., 0.] }
+fn default_focus_distance() -> f64 { 10. }
+fn default_viewport_height() -> f64 { 2. }
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Reference to a texture, either as a plain colour or by the name of a declared texture
+pub enum TextureReference {
+    Colour([f64; 3]),
+    Named(String),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Light arriving along rays which don't hit anything, either as a plain colour or from an
+/// environment map
+pub enum BackgroundDescription {
+    Colour([f64; 3]),
+    Environment(EnvironmentDescription),
+}
+
+impl Default for BackgroundDescription {
+    fn default() -> Self {
+        BackgroundDescription::Colour([0., 0., 0.])
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+/// Equirectangular HDR image surrounding the scene, turned by a rotation in degrees about the
+/// vertical axis and scaled by an intensity
+pub struct EnvironmentDescription {
+    pub environment: String,
+    #[serde(default)]
+    pub rotation: f64,
+    #[serde(default = "default_intensity")]
+    pub intensity: f64,
+}
+
+fn default_intensity() -> f64 { 1. }
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Refraction index of a dielectric, either as a plain number or as coefficients of a formula
+/// in the wavelength
+pub enum RefractionIndexDescription {
+    Constant(f64),
+    Dispersive(DispersionDescription),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+/// Formulas giving a refraction index which varies with wavelength in micrometres
+pub enum DispersionDescription {
+    Cauchy { a: f64, b: f64 },
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Reference to a material, either by the name of a declared material or as an inline description
+pub enum MaterialReference {
+    Named(String),
+    Inline(MaterialDescription),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Noise types which can be used by a noise texture
+pub enum NoiseTypeDescription {
+    Random,
+    Turbulence,
+    Marbled,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+/// Description of each texture variation
+pub enum TextureDescription {
+    SolidColour { colour: [f64; 3] },
+    Checker { even: TextureReference, odd: TextureReference },
+    Noise { scale: f64, noise_type: NoiseTypeDescription },
+    Image { path: String },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+/// Description of each material variation
+pub enum MaterialDescription {
+    Lambertian { albedo: TextureReference },
+    Metal { albedo: [f64; 3], fuzz: f64 },
+    Dielectric { refraction_index: RefractionIndexDescription },
+    DiffuseLight { emit: TextureReference },
+    Isotropic { albedo: TextureReference },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+/// Description of each hittable object variation
+pub enum ObjectDescription {
+    Sphere { centre: [f64; 3], radius: f64, material: MaterialReference },
+    MovingSphere {
+        centre0: [f64; 3], centre1: [f64; 3], time0: f64, time1: f64, radius: f64, material: MaterialReference
+    },
+    XyRect { x0: f64, x1: f64, y0: f64, y1: f64, k: f64, material: MaterialReference },
+    XzRect { x0: f64, x1: f64, z0: f64, z1: f64, k: f64, material: MaterialReference },
+    YzRect { y0: f64, y1: f64, z0: f64, z1: f64, k: f64, material: MaterialReference },
+    Box { min: [f64; 3], max: [f64; 3], material: MaterialReference },
+    Translate { offset: [f64; 3], object: Box<ObjectDescription> },
+    RotateY { angle: f64, object: Box<ObjectDescription> },
+    ConstantMedium { boundary: Box<ObjectDescription>, density: f64, albedo: TextureReference },
+    Bvh { objects: Vec<ObjectDescription> },
+    Triangle { v0: [f64; 3], v1: [f64; 3], v2: [f64; 3], material: MaterialReference },
+    Obj { path: String, material: MaterialReference },
+    Mesh { path: String, material: MaterialReference },
+}
+
+/// Named textures and materials declared in a scene file, shared between the objects using them
+struct SceneResources<'a> {
+    base_dir: &'a Path,
+    textures: HashMap<String, Arc<Texture>>,
+    materials: HashMap<String, Arc<Material>>,
+    time0: f64,
+    time1: f64,
+    bvh_method: BVHBuildMethod,
+}
+
+impl SceneResources<'_> {
+    /// Gets the material for a material reference, creating a new material for inline descriptions
+    fn material(&self, reference: &MaterialReference) -> Result<Arc<Material>, String> {
+        match reference {
+            MaterialReference::Inline(description) => Ok(Arc::new(build_material(description, self)?)),
+            MaterialReference::Named(name) => match self.materials.get(name) {
+                Some(material) => Ok(Arc::clone(material)),
+                None => Err(format!("unknown material '{name}'"))
+            }
+        }
+    }
+
+    /// Gets the texture for a texture reference, creating a new solid colour for plain colours
+    fn texture(&self, reference: &TextureReference) -> Result<Arc<Texture>, String> {
+        match reference {
+            TextureReference::Colour(c) => Ok(Arc::new(SolidColour::new(Vec3::from(*c)))),
+            TextureReference::Named(name) => match self.textures.get(name) {
+                Some(texture) => Ok(Arc::clone(texture)),
+                None => Err(format!("unknown texture '{name}'"))
+            }
+        }
+    }
+}
+
+/// Loads a scene from a TOML scene description file, building its BVHs with the given method
+pub fn load_scene(path: &Path, aspect_ratio: f64, bvh_method: BVHBuildMethod) -> Result<Scene, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("could not read '{}': {e}", path.display()))?;
+    let description: SceneDescription = toml::from_str(&source)
+        .map_err(|e| format!("could not parse '{}': {e}", path.display()))?;
+
+    // Image paths are given relative to the scene file
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    build_scene_description(&description, base_dir, aspect_ratio, bvh_method)
+}
+
+/// Builds a scene from a parsed description, resolving image paths relative to a base directory
+pub fn build_scene_description(
+    description: &SceneDescription, base_dir: &Path, aspect_ratio: f64, bvh_method: BVHBuildMethod
+) -> Result<Scene, String> {
+    let cam = &description.camera;
+    let camera = Camera::new(
+        Vec3::from(cam.look_from), Vec3::from(cam.look_at), Vec3::from(cam.up), cam.vertical_fov,
+        cam.aperture, cam.focus_distance, aspect_ratio, cam.viewport_height, cam.time0, cam.time1
+    );
+
+    let mut resources = SceneResources {
+        base_dir, textures: HashMap::new(), materials: HashMap::new(),
+        time0: cam.time0, time1: cam.time1, bvh_method
+    };
+
+    // Textures may refer to each other, so they're built in dependency order
+    let mut remaining: Vec<&String> = description.textures.keys().collect();
+    remaining.sort();
+    while !remaining.is_empty() {
+        let before = remaining.len();
+        let mut unresolved = vec![];
+        for name in remaining {
+            match build_texture(&description.textures[name], &resources) {
+                Ok(texture) => { resources.textures.insert(name.clone(), Arc::new(texture)); },
+                Err(e) => unresolved.push((name, e)),
+            }
+        }
+        if unresolved.len() == before {
+            let (name, e) = &unresolved[0];
+            return Err(format!("texture '{name}': {e}"));
+        }
+        remaining = unresolved.into_iter().map(|(name, _)| name).collect();
+    }
+
+    // Materials are built in order of name, so the same error is reported on every run
+    let mut names: Vec<&String> = description.materials.keys().collect();
+    names.sort();
+    for name in names {
+        let material = build_material(&description.materials[name], &resources)
+            .map_err(|e| format!("material '{name}': {e}"))?;
+        resources.materials.insert(name.clone(), Arc::new(material));
+    }
+
+    let mut world = HittableList::new();
+    for (i, object) in description.objects.iter().enumerate() {
+        world.add(build_object(object, &resources).map_err(|e| format!("object {}: {e}", i + 1))?);
+    }
+
+    if description.bvh && !world.objects.is_empty() {
+        world = HittableList::from_objects(vec![
+            BVH::build(&world, cam.time0, cam.time1, bvh_method)
+        ]);
+    }
+
+    let background = match &description.background {
+        BackgroundDescription::Colour(colour) => Background::Colour(Vec3::from(*colour)),
+        BackgroundDescription::Environment(environment) => {
+            let path = base_dir.join(&environment.environment);
+            Background::Environment(Arc::new(Environment::load(&path, environment.rotation, environment.intensity)?))
+        },
+    };
+    Ok(Scene::new(world, camera, background))
+}
+
+/// Builds a texture from its description
+fn build_texture(
+    description: &TextureDescription, resources: &SceneResources
+) -> Result<Texture, String> {
+    Ok(match description {
+        TextureDescription::SolidColour { colour } => SolidColour::new(Vec3::from(*colour)),
+        TextureDescription::Checker { even, odd } =>
+            CheckerTexture::from_textures(resources.texture(even)?, resources.texture(odd)?),
+        TextureDescription::Noise { scale, noise_type } => {
+            let noise_type = match noise_type {
+                NoiseTypeDescription::Random => NoiseType::Random,
+                NoiseTypeDescription::Turbulence => NoiseType::Turbulence,
+                NoiseTypeDescription::Marbled => NoiseType::Marbled,
+            };
+            NoiseTexture::new(*scale, noise_type)
+        },
+        TextureDescription::Image { path } => {
+            let path = resources.base_dir.join(path);
+            ImageTexture::new(&path.to_string_lossy())?
+        },
+    })
+}
+
+/// Builds a material from its description
+fn build_material(
+    description: &MaterialDescription, resources: &SceneResources
+) -> Result<Material, String> {
+    Ok(match description {
+        MaterialDescription::Lambertian { albedo } =>
+            Lambertian::from_texture(resources.texture(albedo)?),
+        MaterialDescription::Metal { albedo, fuzz } => Metal::new(Vec3::from(*albedo), *fuzz),
+        MaterialDescription::Dielectric { refraction_index } => Dielectric::from_index(match refraction_index {
+            RefractionIndexDescription::Constant(n) => RefractionIndex::Constant(*n),
+            RefractionIndexDescription::Dispersive(DispersionDescription::Cauchy { a, b }) =>
+                RefractionIndex::Cauchy { a: *a, b: *b },
+            RefractionIndexDescription::Dispersive(DispersionDescription::Sellmeier { b, c }) =>
+                RefractionIndex::Sellmeier { b: *b, c: *c },
+        }),
+        MaterialDescription::DiffuseLight { emit } => DiffuseLight::new(resources.texture(emit)?),
+        MaterialDescription::Isotropic { albedo } => Isotropic::new(resources.texture(albedo)?),
+    })
+}
+
+/// Builds a hittable object from its description
+fn build_object(description: &ObjectDescription, resources: &SceneResources) -> Result<Hittable, String> {
+    Ok(match description {
+        ObjectDescription::Sphere { centre, radius, material } =>
+            Sphere::new(Vec3::from(*centre), *radius, resources.material(material)?),
+        ObjectDescription::MovingSphere { centre0, centre1, time0, time1, radius, material } =>
+            MovingSphere::new(
+                Vec3::from(*centre0), Vec3::from(*centre1), *time0, *time1,
+                *radius, resources.material(material)?
+            ),
+        ObjectDescription::XyRect { x0, x1, y0, y1, k, material } =>
+            XYRect::new(*x0, *x1, *y0, *y1, *k, resources.material(material)?),
+        ObjectDescription::XzRect { x0, x1, z0, z1, k, material } =>
+            XZRect::new(*x0, *x1, *z0, *z1, *k, resources.material(material)?),
+        ObjectDescription::YzRect { y0, y1, z0, z1, k, material } =>
+            YZRect::new(*y0, *y1, *z0, *z1, *k, resources.material(material)?),
+        ObjectDescription::Box { min, max, material } =>
+            AABox::new(Vec3::from(*min), Vec3::from(*max), resources.material(material)?),
+        ObjectDescription::Translate { offset, object } =>
+            Translate::new(Arc::new(build_object(object, resources)?), Vec3::from(*offset)),
+        ObjectDescription::RotateY { angle, object } =>
+            RotateY::new(Arc::new(build_object(object, resources)?), *angle),
+        ObjectDescription::ConstantMedium { boundary, density, albedo } =>
+            ConstantMedium::new(
+                Arc::new(build_object(boundary, resources)?), *density, resources.texture(albedo)?
+            ),
+        ObjectDescription::Bvh { objects } => {
+            if objects.is_empty() {
+                return Err("bvh must contain at least one object".to_string());
+            }
+            let list = build_list(objects, resources)?;
+            BVH::build(&list, resources.time0, resources.time1, resources.bvh_method)
+        },
+        ObjectDescription::Triangle { v0, v1, v2, material } =>
+            Triangle::new(Vec3::from(*v0), Vec3::from(*v1), Vec3::from(*v2), resources.material(material)?),
+        ObjectDescription::Obj { path, material } =>
+            load_obj(&resources.base_dir.join(path), resources.material(material)?, resources.bvh_method)?,
+        ObjectDescription::Mesh { path, material } =>
+            load_obj_mesh(&resources.base_dir.join(path), resources.material(material)?)?,
+    })
+}
+
+/// Builds a list of hittable objects from their descriptions
+fn build_list(objects: &[ObjectDescription], resources: &SceneResources) -> Result<HittableList, String> {
+    let mut list = HittableList::new();
+    for object in objects {
+        list.add(build_object(object, resources)?);
+    }
+    Ok(list)
+}
@@ -0,0 +1,457 @@
+use std::sync::Arc;
+use crate::{random_double, random_range};
+use crate::vec3::Vec3;
+use crate::camera::Camera;
+use crate::scene::Scene;
+use crate::environment::Background;
+use crate::hittable_list::HittableList;
+use crate::bvh::{BVH, BVHBuildMethod};
+use crate::sphere::Sphere;
+use crate::moving_sphere::MovingSphere;
+use crate::aa_rect::{XYRect, XZRect, YZRect};
+use crate::aa_box::AABox;
+use crate::translate::Translate;
+use crate::rotate_y::RotateY;
+use crate::constant_medium::ConstantMedium;
+use crate::lambertian::Lambertian;
+use crate::metal::Metal;
+use crate::dielectric::Dielectric;
+use crate::diffuse_light::DiffuseLight;
+use crate::checker_texture::CheckerTexture;
+use crate::image_texture::ImageTexture;
+use crate::noise_texture::{NoiseTexture, NoiseType};
+
+/// Generates the final scene from 'Ray Tracing in a Weekend'
+pub fn in_a_weekend_scene(bvh_method: BVHBuildMethod) -> HittableList {
+    // Creates world list
+    let mut world = HittableList::new();
+
+    // Sets up ground
+    let ground_material = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&ground_material)));
+
+    // Generates random spheres
+    for a in -11..=11 {
+       for b in -11..=11 {
+           let choose_mat = random_double();
+           let centre = Vec3::new(
+               a as f64 + 0.9 * random_double(),
+               0.2,
+               b as f64 + 0.9 * random_double()
+           );
+           if (centre - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
+               if choose_mat < 0.8 {
+                   let albedo = Vec3::random(0., 1.) * Vec3::random(0., 1.);
+                   let sphere_material = Arc::new(Lambertian::new(albedo));
+                   world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
+               } else if choose_mat < 0.95 {
+                   let albedo = Vec3::random(0.5, 1.);
+                   let fuzz = random_range(0.0, 0.5);
+                   let sphere_material = Arc::new(Metal::new(albedo, fuzz));
+                   world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
+               } else {
+                   let sphere_material = Arc::new(Dielectric::new(1.5));
+                   world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
+               }
+           }
+        }
+    }
+
+    // Adds three main spheres
+    let material1 = Arc::new(Dielectric::new(1.5));
+    let material2 = Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)));
+    let material3 = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.));
+    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., Arc::clone(&material1)));
+    world.add(Sphere::new(Vec3::new(-4., 1., 0.), 1., Arc::clone(&material2)));
+    world.add(Sphere::new(Vec3::new(4., 1., 0.), 1., Arc::clone(&material3)));
+
+
+    HittableList::from_objects(vec![BVH::build(&world, 0., 1., bvh_method)])
+}
+
+/// Generates the camera and background for the 'Ray Tracing in a Weekend' scene
+pub fn in_a_weekend_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(13., 2., 3.);
+    let look_at = Vec3::new(0., 0., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.1;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
+    );
+    (camera, Background::Colour(Vec3::new(0.7, 0.8, 1.)))
+}
+
+/// Generates scene with bouncing balls and a checkered texture
+pub fn bouncing_balls_scene(bvh_method: BVHBuildMethod) -> HittableList {
+    // Creates world list
+    let mut world = HittableList::new();
+
+    // Sets up ground
+    let texture = Arc::new(CheckerTexture::new(
+        Vec3::new(0.2, 0.3, 0.1), Vec3::new(0.9, 0.9, 0.9)));
+    let ground_material = Arc::new(Lambertian::from_texture(Arc::clone(&texture)));
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&ground_material)));
+
+    // Generates random spheres
+    for a in -11..=11 {
+        for b in -11..=11 {
+            let choose_mat = random_double();
+            let centre = Vec3::new(
+                a as f64 + 0.9 * random_double(),
+                0.2,
+                b as f64 + 0.9 * random_double()
+            );
+            if (centre - Vec3::new(4., 0.2, 0.)).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    let albedo = Vec3::random(0., 1.) * Vec3::random(0., 1.);
+                    let sphere_material = Arc::new(Lambertian::new(albedo));
+                    let centre2 = centre + Vec3::new(0., random_range(0., 0.5), 0.);
+                    world.add(MovingSphere::new(centre, centre2, 0., 1., 0.2, Arc::clone(&sphere_material)));
+                } else if choose_mat < 0.95 {
+                    let albedo = Vec3::random(0.5, 1.);
+                    let fuzz = random_range(0.0, 0.5);
+                    let sphere_material = Arc::new(Metal::new(albedo, fuzz));
+                    world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
+                } else {
+                    let sphere_material = Arc::new(Dielectric::new(1.5));
+                    world.add(Sphere::new(centre, 0.2, Arc::clone(&sphere_material)));
+                }
+            }
+        }
+    }
+
+    // Adds three main spheres
+    let material1 = Arc::new(Dielectric::new(1.5));
+    let material2 = Arc::new(Lambertian::new(Vec3::new(0.4, 0.2, 0.1)));
+    let material3 = Arc::new(Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.));
+    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., Arc::clone(&material1)));
+    world.add(Sphere::new(Vec3::new(-4., 1., 0.), 1., Arc::clone(&material2)));
+    world.add(Sphere::new(Vec3::new(4., 1., 0.), 1., Arc::clone(&material3)));
+
+    HittableList::from_objects(vec![BVH::build(&world, 0., 1., bvh_method)])
+}
+
+/// Generates camera and background for bouncing balls scene
+pub fn bouncing_balls_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(13., 2., 3.);
+    let look_at = Vec3::new(0., 0., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.1;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 1.
+    );
+    (camera, Background::Colour(Vec3::new(0.7, 0.8, 1.)))
+}
+
+/// Generates a scene with two checkered spheres
+pub fn two_spheres_scene() -> HittableList {
+    let mut world = HittableList::new();
+
+    let checker = Arc::new(CheckerTexture::new(
+        Vec3::new(0.2, 0.3, 0.1), Vec3::new(0.9, 0.9, 0.9)));
+    let material = Arc::new(Lambertian::from_texture(Arc::clone(&checker)));
+    world.add(Sphere::new(Vec3::new(0., -10., 0.), 10., Arc::clone(&material)));
+    world.add(Sphere::new(Vec3::new(0., 10., 0.), 10., Arc::clone(&material)));
+
+    world
+}
+
+/// Generates the camera and background for the two spheres scene
+pub fn two_spheres_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(13., 2., 3.);
+    let look_at = Vec3::new(0., 0., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
+    );
+    (camera, Background::Colour(Vec3::new(0.7, 0.8, 1.)))
+}
+
+/// Generates a scene with two perlin noise spheres
+pub fn two_perlin_spheres_scene() -> HittableList {
+    let mut world = HittableList::new();
+
+    let perlin_texture = Arc::new(NoiseTexture::new(4., NoiseType::Marbled));
+    let material = Arc::new(Lambertian::from_texture(
+        Arc::clone(&perlin_texture)));
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&material)));
+    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., Arc::clone(&material)));
+
+    world
+}
+
+/// Generates the camera and background for the two perlin spheres scene
+pub fn two_perlin_spheres_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(13., 2., 3.);
+    let look_at = Vec3::new(0., 0., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
+    );
+    (camera, Background::Colour(Vec3::new(0.7, 0.8, 1.)))
+}
+
+/// Generates scene with a sphere using an earth image texture, or reports why the image
+/// couldn't be loaded
+pub fn earth_scene() -> Result<HittableList, String> {
+    let mut world = HittableList::new();
+
+    let earth_texture = Arc::new(ImageTexture::new("earthmap.jpg")?);
+    let earth_material = Arc::new(Lambertian::from_texture(Arc::clone(&earth_texture)));
+    world.add(Sphere::new(Vec3::zero(), 2., Arc::clone(&earth_material)));
+
+    Ok(world)
+}
+
+/// Generates the camera and background for the earth scene
+pub fn earth_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(13., 2., 3.);
+    let look_at = Vec3::new(0., 0., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
+    );
+    (camera, Background::Colour(Vec3::new(0.7, 0.8, 1.)))
+}
+
+/// Generates scene with a simple light
+pub fn simple_light_scene() -> HittableList {
+    let mut world = HittableList::new();
+
+    let perlin_texture = Arc::new(NoiseTexture::new(4., NoiseType::Marbled));
+    let material = Arc::new(Lambertian::from_texture(
+        Arc::clone(&perlin_texture)));
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&material)));
+    world.add(Sphere::new(Vec3::new(0., 2., 0.), 2., Arc::clone(&material)));
+
+    let light_mat = Arc::new(DiffuseLight::from_colour(4., 4., 4.));
+    world.add(XYRect::new(3., 5., 1., 3., -2., Arc::clone(&light_mat)));
+    world.add(Sphere::new(Vec3::new(0., 6.5, 0.), 2., Arc::clone(&light_mat)));
+
+    world
+}
+
+/// Generates the camera and background for the simple light scene
+pub fn simple_light_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(26., 3., 6.);
+    let look_at = Vec3::new(0., 2., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 20., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
+    );
+    (camera, Background::Colour(Vec3::zero()))
+}
+
+/// Generates Cornell Box scene
+pub fn cornell_box_scene() -> HittableList {
+    let mut world = HittableList::new();
+
+    let red = Arc::new(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::from_colour(15., 15., 15.));
+
+    world.add(YZRect::new(0., 555., 0., 555., 555., Arc::clone(&green)));
+    world.add(YZRect::new(0., 555., 0., 555., 0., Arc::clone(&red)));
+    world.add(XZRect::new(213., 343., 227., 332., 554., Arc::clone(&light)));
+    world.add(XZRect::new(0., 555., 0., 555., 0., Arc::clone(&white)));
+    world.add(XZRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
+    world.add(XYRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
+
+    let box1 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 330., 165.), Arc::clone(&white)));
+    let box1 = Arc::new(RotateY::new(Arc::clone(&box1), 15.));
+    world.add(Translate::new(Arc::clone(&box1), Vec3::new(265., 0., 295.)));
+
+    let box2 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 165., 165.), Arc::clone(&white)));
+    let box2 = Arc::new(RotateY::new(Arc::clone(&box2), -18.));
+    world.add(Translate::new(Arc::clone(&box2), Vec3::new(130., 0., 65.)));
+
+    world
+}
+
+/// Generates the camera and background for the Cornell Box scene
+pub fn cornell_box_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(278., 278., -800.);
+    let look_at = Vec3::new(278., 278., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 40., aperture, dist_to_focus, aspect_ratio, 2., 0., 0.
+    );
+    (camera, Background::Colour(Vec3::zero()))
+}
+
+/// Generates Cornell Box scene with fog
+pub fn cornell_box_smoke_scene() -> HittableList {
+    let mut world = HittableList::new();
+
+    let red = Arc::new(Lambertian::new(Vec3::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(Lambertian::new(Vec3::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLight::from_colour(7., 7., 7.));
+
+    world.add(YZRect::new(0., 555., 0., 555., 555., Arc::clone(&green)));
+    world.add(YZRect::new(0., 555., 0., 555., 0., Arc::clone(&red)));
+    world.add(XZRect::new(113., 443., 127., 432., 554., Arc::clone(&light)));
+    world.add(XZRect::new(0., 555., 0., 555., 0., Arc::clone(&white)));
+    world.add(XZRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
+    world.add(XYRect::new(0., 555., 0., 555., 555., Arc::clone(&white)));
+
+    let box1 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 330., 165.), Arc::clone(&white)));
+    let box1 = Arc::new(RotateY::new(Arc::clone(&box1), 15.));
+    let box1 = Arc::new(Translate::new(Arc::clone(&box1), Vec3::new(265., 0., 295.)));
+
+    let box2 = Arc::new(AABox::new(Vec3::new(0., 0., 0.), Vec3::new(165., 165., 165.), Arc::clone(&white)));
+    let box2 = Arc::new(RotateY::new(Arc::clone(&box2), -18.));
+    let box2 = Arc::new(Translate::new(Arc::clone(&box2), Vec3::new(130., 0., 65.)));
+
+    world.add(ConstantMedium::from_colour(Arc::clone(&box1), 0.01, Vec3::zero()));
+    world.add(ConstantMedium::from_colour(Arc::clone(&box2), 0.01, Vec3::one()));
+
+    world
+}
+
+/// Generates the camera and background for the Cornell Box scene
+pub fn cornell_box_smoke_camera(aspect_ratio: f64) -> (Camera, Background) {
+    cornell_box_camera(aspect_ratio)
+}
+
+/// Generates the final scene of 'Ray Tracing The Next Week', or reports why its earth image
+/// couldn't be loaded
+pub fn the_next_week_scene(bvh_method: BVHBuildMethod) -> Result<HittableList, String> {
+    let mut world = HittableList::new();
+
+    let ground = Arc::new(Lambertian::new(Vec3::new(0.48, 0.83, 0.53)));
+
+    let mut boxes1 = HittableList::new();
+    const BOXES_PER_SIDE: i32 = 20;
+    for i in 0..BOXES_PER_SIDE {
+        for j in 0..BOXES_PER_SIDE {
+            let w = 100.;
+            let x0 = -1000. + i as f64 * w;
+            let y0 = 0.;
+            let z0 = -1000. + j as f64 * w;
+            let x1 = x0 + w;
+            let y1 = random_range(1., 101.);
+            let z1 = z0 + w;
+
+            boxes1.add(AABox::new(
+                Vec3::new(x0, y0, z0), Vec3::new(x1, y1, z1),
+                Arc::clone(&ground))
+            );
+        }
+    }
+
+    world.add(BVH::build(&boxes1, 0., 1., bvh_method));
+
+    let light = Arc::new(DiffuseLight::from_colour(7., 7., 7.));
+    world.add(XZRect::new(123., 423., 147., 412., 554., Arc::clone(&light)));
+
+    let centre1 = Vec3::new(400., 400., 200.);
+    let centre2 = centre1 + Vec3::new(30., 0., 0.);
+    let moving_sphere_mat = Arc::new(Lambertian::new(Vec3::new(0.7, 0.3, 0.1)));
+    world.add(MovingSphere::new(centre1, centre2, 0., 1., 50., Arc::clone(&moving_sphere_mat)));
+
+    let glass = Arc::new(Dielectric::new(1.5));
+    world.add(Sphere::new(Vec3::new(260., 150., 45.), 50., Arc::clone(&glass)));
+    world.add(Sphere::new(Vec3::new(0., 150., 145.), 50., Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.9), 1.))));
+
+    let boundary = Arc::new(Sphere::new(Vec3::new(360., 150., 145.), 70., Arc::clone(&glass)));
+    world.add_arc(Arc::clone(&boundary));
+    world.add(ConstantMedium::from_colour(Arc::clone(&boundary), 0.2, Vec3::new(0.2, 0.4, 0.9)));
+    let boundary = Arc::new(Sphere::new(Vec3::new(0., 0., 0.), 5000., Arc::clone(&glass)));
+    world.add(ConstantMedium::from_colour(Arc::clone(&boundary), 0.0001, Vec3::one()));
+
+    let earth_mat = Arc::new(Lambertian::from_texture(Arc::new(ImageTexture::new("earthmap.jpg")?)));
+    world.add(Sphere::new(Vec3::new(400., 200., 400.), 100., Arc::clone(&earth_mat)));
+    let noise_mat = Arc::new(Lambertian::from_texture( Arc::new(NoiseTexture::new(0.1, NoiseType::Marbled))));
+    world.add(Sphere::new(Vec3::new(220., 280., 300.), 80., Arc::clone(&noise_mat)));
+
+    let mut boxes2 = HittableList::new();
+    let white = Arc::new(Lambertian::new(Vec3::new(0.73, 0.73, 0.73)));
+    for _ in 0..1000 {
+        boxes2.add(Sphere::new(Vec3::random(0., 165.), 10., Arc::clone(&white)));
+    }
+
+    world.add(Translate::new(
+        Arc::new(RotateY::new(
+            Arc::new(BVH::build(&boxes2, 0., 1., bvh_method)), 15.
+        )),
+        Vec3::new(-100., 270., 395.))
+    );
+
+    Ok(HittableList::from_objects(vec![BVH::build(&world, 0., 1., bvh_method)]))
+}
+
+/// Generates the camera and background for the 'Ray Tracing The Next Week' scene
+pub fn the_next_week_camera(aspect_ratio: f64) -> (Camera, Background) {
+    let look_from = Vec3::new(478., 278., -600.);
+    let look_at = Vec3::new(278., 278., 0.);
+    let up = Vec3::new(0., 1., 0.);
+    let dist_to_focus = 10.;
+    let aperture = 0.0;
+
+    let camera = Camera::new(
+        look_from, look_at, up, 40., aperture, dist_to_focus, aspect_ratio, 2., 0., 1.
+    );
+    (camera, Background::Colour(Vec3::zero()))
+}
+
+/// Names and descriptions of each of the built-in scenes
+pub const SCENES: [(&str, &str); 9] = [
+    ("in-a-weekend", "Final scene of 'Ray Tracing in One Weekend'"),
+    ("bouncing-balls", "Bouncing balls with motion blur on a checkered ground"),
+    ("two-spheres", "Two checkered spheres"),
+    ("two-perlin-spheres", "Two spheres with a marbled Perlin noise texture"),
+    ("earth", "A sphere with an image texture of the earth"),
+    ("simple-light", "Perlin noise spheres lit by a rectangle and sphere light"),
+    ("cornell-box", "Standard Cornell Box"),
+    ("cornell-box-smoke", "Cornell Box with blocks of smoke"),
+    ("the-next-week", "Final scene of 'Ray Tracing: The Next Week'"),
+];
+
+/// Builds the built-in scene with the given name using the given BVH build method, or reports
+/// why it couldn't be built
+pub fn build_scene(name: &str, aspect_ratio: f64, bvh_method: BVHBuildMethod) -> Result<Scene, String> {
+    let (world, (camera, background)) = match name {
+        "in-a-weekend" =>
+            (in_a_weekend_scene(bvh_method), in_a_weekend_camera(aspect_ratio)),
+        "bouncing-balls" =>
+            (bouncing_balls_scene(bvh_method), bouncing_balls_camera(aspect_ratio)),
+        "two-spheres" =>
+            (two_spheres_scene(), two_spheres_camera(aspect_ratio)),
+        "two-perlin-spheres" =>
+            (two_perlin_spheres_scene(), two_perlin_spheres_camera(aspect_ratio)),
+        "earth" =>
+            (earth_scene()?, earth_camera(aspect_ratio)),
+        "simple-light" =>
+            (simple_light_scene(), simple_light_camera(aspect_ratio)),
+        "cornell-box" =>
+            (cornell_box_scene(), cornell_box_camera(aspect_ratio)),
+        "cornell-box-smoke" =>
+            (cornell_box_smoke_scene(), cornell_box_smoke_camera(aspect_ratio)),
+        "the-next-week" =>
+            (the_next_week_scene(bvh_method)?, the_next_week_camera(aspect_ratio)),
+        _ => return Err(format!("unknown scene '{name}'"))
+    };
+
+    Ok(Scene::new(world, camera, background))
+}
@@ -7,6 +7,7 @@ pub struct SolidColour {
 }
 
 impl SolidColour {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(colour_value: Vec3) -> Texture {
         Texture::SolidColour(SolidColour { colour_value })
     }
@@ -0,0 +1,184 @@
+use std::sync::OnceLock;
+use crate::random_double;
+use crate::vec3::Vec3;
+
+pub const WAVELENGTHS: usize = 3;           // Number of wavelengths carried by each path, one per channel of a Vec3
+pub const MIN_WAVELENGTH: f64 = 360.;       // Shortest wavelength sampled, in nanometres
+pub const MAX_WAVELENGTH: f64 = 830.;       // Longest wavelength sampled, in nanometres
+const INTEGRATION_STEP: f64 = 0.5;          // Step in nanometres of the sums standing in for integrals over wavelength
+const BLUE_EDGE: f64 = 490.;                // Wavelength where the blue basis spectrum gives way to the green one
+const RED_EDGE: f64 = 590.;                 // Wavelength where the green basis spectrum gives way to the red one
+const EDGE_WIDTH: f64 = 10.;                // Width in nanometres over which neighbouring basis spectra cross over
+
+// Matrix from CIE XYZ to linear sRGB, one row for each of red, green and blue
+const XYZ_TO_RGB: [[f64; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+#[derive(Debug, Clone, Copy)]
+/// Wavelengths of light followed along a single path, each carried in one channel of the
+/// path's colours. The first is the hero wavelength, which is the only one followed once
+/// the path passes through a dispersive material
+pub struct SampledWavelengths {
+    wavelengths: [f64; WAVELENGTHS],    // Wavelengths in nanometres
+    pdfs: [f64; WAVELENGTHS],           // Density each wavelength was sampled with, or zero if it's no longer followed
+}
+
+impl SampledWavelengths {
+    /// Samples wavelengths spread evenly through the visible spectrum from one random number,
+    /// concentrated where the eye is most sensitive
+    pub fn sample() -> Self {
+        let u = random_double();
+        let mut wavelengths = [0.; WAVELENGTHS];
+        let mut pdfs = [0.; WAVELENGTHS];
+        for i in 0..WAVELENGTHS {
+            let u = (u + i as f64 / WAVELENGTHS as f64).fract();
+            wavelengths[i] = sample_visible(u);
+            pdfs[i] = visible_pdf(wavelengths[i]);
+        }
+        SampledWavelengths { wavelengths, pdfs }
+    }
+
+    /// Gets the hero wavelength, which dispersive materials bend light by
+    pub fn hero(&self) -> f64 {
+        self.wavelengths[0]
+    }
+
+    /// Gets every wavelength, in the order of the channels carrying them
+    pub fn wavelengths(&self) -> [f64; WAVELENGTHS] {
+        self.wavelengths
+    }
+
+    /// Stops following every wavelength except the hero, which then stands for them all
+    pub fn terminate_secondary(&mut self) {
+        if self.secondary_terminated() {
+            return;
+        }
+        for pdf in &mut self.pdfs[1..] {
+            *pdf = 0.;
+        }
+        self.pdfs[0] /= WAVELENGTHS as f64;
+    }
+
+    /// Checks whether only the hero wavelength is still followed
+    pub fn secondary_terminated(&self) -> bool {
+        self.pdfs[1..].iter().all(|&pdf| pdf == 0.)
+    }
+
+    /// Gets the values at each wavelength of the spectrum upsampled from an RGB colour
+    pub fn upsample(&self, rgb: Vec3) -> Vec3 {
+        Vec3::from(self.wavelengths.map(|wavelength| rgb_to_spectrum(rgb, wavelength)))
+    }
+
+    /// Converts the values of a spectrum at each wavelength back to a linear sRGB colour
+    pub fn to_rgb(&self, values: Vec3) -> Vec3 {
+        let mut xyz = Vec3::zero();
+        for i in 0..WAVELENGTHS {
+            if self.pdfs[i] > 0. {
+                xyz += values[i] / self.pdfs[i] * cie_xyz(self.wavelengths[i]);
+            }
+        }
+        xyz_to_rgb(xyz / (WAVELENGTHS as f64 * conversion().y_integral))
+    }
+}
+
+/// Maps a random number to a wavelength in the visible spectrum, with a density roughly
+/// following the eye's sensitivity
+pub fn sample_visible(u: f64) -> f64 {
+    538. - 138.888889 * f64::atanh(0.85691062 - 1.82750197 * u)
+}
+
+/// Gets the density with which `sample_visible` chooses a wavelength
+pub fn visible_pdf(wavelength: f64) -> f64 {
+    if !(MIN_WAVELENGTH..=MAX_WAVELENGTH).contains(&wavelength) {
+        return 0.;
+    }
+    0.0039398042 / f64::powi(f64::cosh(0.0072 * (wavelength - 538.)), 2)
+}
+
+/// Gets the CIE 1931 colour matching functions at a wavelength, using the piecewise gaussian
+/// fit of Wyman, Sloan and Shirley
+pub fn cie_xyz(wavelength: f64) -> Vec3 {
+    // Gaussian with a different width either side of its peak
+    let g = |mean: f64, below: f64, above: f64| {
+        let t = (wavelength - mean) / if wavelength < mean { below } else { above };
+        f64::exp(-0.5 * t * t)
+    };
+    Vec3::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// Converts a CIE XYZ colour to linear sRGB, balanced so that a spectrum equal at every
+/// wavelength comes out white
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    let white = conversion().white;
+    let rgb = unbalanced_rgb(xyz);
+    Vec3::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z)
+}
+
+/// Gets the value at a wavelength of a smooth spectrum which converts back to an RGB colour.
+/// Spectra are sums of red, green and blue basis spectra which add up to one, so white and
+/// greys are flat, and colours more saturated than the basis can dip below zero
+pub fn rgb_to_spectrum(rgb: Vec3, wavelength: f64) -> f64 {
+    let weights = conversion().basis_weights;
+    let basis = basis_spectra(wavelength);
+    (0..3).map(|i| Vec3::dot(&weights[i], &rgb) * basis[i]).sum()
+}
+
+/// Values of the red, green and blue basis spectra at a wavelength
+fn basis_spectra(wavelength: f64) -> Vec3 {
+    let step = |edge: f64| 1. / (1. + f64::exp(-(wavelength - edge) / EDGE_WIDTH));
+    let red = step(RED_EDGE);
+    let blue = 1. - step(BLUE_EDGE);
+    Vec3::new(red, 1. - red - blue, blue)
+}
+
+/// Applies the XYZ to sRGB matrix without any white balance
+fn unbalanced_rgb(xyz: Vec3) -> Vec3 {
+    Vec3::from(XYZ_TO_RGB.map(|row| Vec3::dot(&Vec3::from(row), &xyz)))
+}
+
+/// Constants found by integrating over the visible spectrum, which are worked out once
+struct Conversion {
+    y_integral: f64,            // Integral of the CIE luminance curve, which scales XYZ so that a flat spectrum has Y = 1
+    white: Vec3,                // Unbalanced sRGB colour of a flat spectrum
+    basis_weights: [Vec3; 3],   // Rows of the matrix giving the weight of each basis spectrum for an RGB colour
+}
+
+/// Gets the constants of the conversion between spectra and RGB
+fn conversion() -> &'static Conversion {
+    static CONVERSION: OnceLock<Conversion> = OnceLock::new();
+    CONVERSION.get_or_init(|| {
+        // Midpoint sums over the sampled range stand in for the integrals
+        let steps = ((MAX_WAVELENGTH - MIN_WAVELENGTH) / INTEGRATION_STEP) as usize;
+        let integrate = |f: &dyn Fn(f64) -> Vec3| -> Vec3 {
+            (0..steps).map(|i| {
+                let wavelength = MIN_WAVELENGTH + (i as f64 + 0.5) * INTEGRATION_STEP;
+                INTEGRATION_STEP * f(wavelength)
+            }).fold(Vec3::zero(), |sum, value| sum + value)
+        };
+        let xyz_integral = integrate(&cie_xyz);
+        let y_integral = xyz_integral.y;
+        let white = unbalanced_rgb(xyz_integral / y_integral);
+
+        // Each basis spectrum converts to a column of balanced RGB, and inverting the matrix
+        // of those columns gives the basis weights which convert back to a given colour
+        let columns: Vec<Vec3> = (0..3).map(|i| {
+            let rgb = unbalanced_rgb(integrate(&|wavelength| basis_spectra(wavelength)[i] * cie_xyz(wavelength)) / y_integral);
+            Vec3::new(rgb.x / white.x, rgb.y / white.y, rgb.z / white.z)
+        }).collect();
+        let (a, b, c) = (columns[0], columns[1], columns[2]);
+        let determinant = Vec3::dot(&a, &Vec3::cross(&b, &c));
+        let basis_weights = [
+            Vec3::cross(&b, &c) / determinant,
+            Vec3::cross(&c, &a) / determinant,
+            Vec3::cross(&a, &b) / determinant,
+        ];
+        Conversion { y_integral, white, basis_weights }
+    })
+}
@@ -1,9 +1,10 @@
 use std::sync::Arc;
-use ray_tracing::PI;
+use crate::{random_double, INFINITY, PI};
 use crate::aabb::AABB;
 use crate::hit_record::HitRecord;
 use crate::hittable::{Hittable, HittableTrait};
 use crate::material::Material;
+use crate::onb::ONB;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
@@ -17,6 +18,7 @@ pub struct Sphere {
 
 impl Sphere {
     /// Constructs a new Sphere object, wrapped in the Hittable enum
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(centre: Vec3, radius: f64, material: Arc<Material>) -> Hittable {
         Hittable::Sphere(Sphere { centre, radius, material })
     }
@@ -71,4 +73,54 @@ impl HittableTrait for Sphere {
         let r_vec = Vec3::new(self.radius, self.radius, self.radius);
         Some(AABB::new(self.centre - r_vec, self.centre + r_vec))
     }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.push(Arc::clone(&self.material));
+    }
+
+    fn is_light(&self) -> bool {
+        matches!(self.material.as_ref(), Material::DiffuseLight(_))
+    }
+
+    /// Directions are sampled uniformly within the cone the sphere subtends, which is only
+    /// defined from outside of it
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let distance_squared = (self.centre - *origin).length_squared();
+        if distance_squared <= self.radius * self.radius
+            || self.hit(&Ray::new(*origin, *direction, 0.), 0.001, INFINITY).is_none() {
+            return 0.;
+        }
+
+        let cos_theta_max = f64::sqrt(1. - self.radius * self.radius / distance_squared);
+        1. / (2. * PI * (1. - cos_theta_max))
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let direction = self.centre - *origin;
+        let distance_squared = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return direction;
+        }
+
+        // Picks a direction uniformly within the cone around the z axis, then turns it to
+        // point at the sphere
+        let cos_theta_max = f64::sqrt(1. - self.radius * self.radius / distance_squared);
+        let z = 1. + random_double() * (cos_theta_max - 1.);
+        let phi = 2. * PI * random_double();
+        let sin_theta = f64::sqrt(1. - z * z);
+        ONB::from_w(&direction).local(&Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
+
+    fn area(&self) -> f64 {
+        4. * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        let outward_normal = Vec3::random_unit_vector();
+        let (u, v) = Sphere::get_sphere_uv(outward_normal);
+        let mut hit_record = HitRecord::new(self.centre + self.radius * outward_normal, &self.material, u, v, 0.);
+        hit_record.normal = outward_normal;
+        hit_record.front_face = true;
+        Some(hit_record)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,99 @@
+use crate::vec3::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Curve used to compress the range of radiance into displayable colours
+pub enum ToneMapOperator {
+    #[default]
+    Linear,             // Leaves radiance unchanged, clipping anything brighter than 1
+    Reinhard,           // Compresses luminance L to L / (1 + L)
+    ExtendedReinhard,   // Reinhard's operator, but reaching 1 exactly at a chosen white point
+    Aces,               // Narkowicz's fit of the ACES filmic curve
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Encoding applied to tone mapped colours before they are quantised
+pub enum TransferFunction {
+    #[default]
+    Srgb,       // The exact piecewise sRGB curve
+    Gamma2,     // A square root, as used by 'Ray Tracing in One Weekend'
+}
+
+#[derive(Debug, Clone, Copy)]
+/// Settings for converting radiance into display colours
+pub struct ToneMap {
+    pub operator: ToneMapOperator,      // Curve applied to the exposed radiance
+    pub exposure: f64,                  // Number of stops to brighten (or darken) the radiance by
+    pub white_point: f64,               // Luminance mapped to white by the extended Reinhard operator
+    pub transfer: TransferFunction,     // Encoding of the final colours
+}
+
+impl Default for ToneMap {
+    fn default() -> Self {
+        ToneMap {
+            operator: ToneMapOperator::Linear,
+            exposure: 0.,
+            white_point: 4.,
+            transfer: TransferFunction::Srgb,
+        }
+    }
+}
+
+impl ToneMap {
+    /// Converts a radiance value into an encoded display colour in the range [0,1]
+    pub fn apply(&self, radiance: Vec3) -> Vec3 {
+        let exposed = f64::powf(2., self.exposure) * radiance;
+
+        let mapped = match self.operator {
+            ToneMapOperator::Linear => exposed,
+            ToneMapOperator::Reinhard => {
+                let luminance = luminance(exposed);
+                scale_luminance(exposed, luminance, luminance / (1. + luminance))
+            },
+            ToneMapOperator::ExtendedReinhard => {
+                let luminance = luminance(exposed);
+                let white_squared = self.white_point * self.white_point;
+                let mapped = luminance * (1. + luminance / white_squared) / (1. + luminance);
+                scale_luminance(exposed, luminance, mapped)
+            },
+            ToneMapOperator::Aces => Vec3::new(aces(exposed.x), aces(exposed.y), aces(exposed.z)),
+        };
+
+        let encode = |c: f64| {
+            let c = c.clamp(0., 1.);
+            match self.transfer {
+                TransferFunction::Srgb => srgb_encode(c),
+                TransferFunction::Gamma2 => c.sqrt(),
+            }
+        };
+        Vec3::new(encode(mapped.x), encode(mapped.y), encode(mapped.z))
+    }
+}
+
+/// Gets the relative luminance of a linear sRGB colour
+pub fn luminance(colour: Vec3) -> f64 {
+    0.2126 * colour.x + 0.7152 * colour.y + 0.0722 * colour.z
+}
+
+/// Scales a colour so that its luminance changes to a new value, keeping its hue
+fn scale_luminance(colour: Vec3, luminance: f64, new_luminance: f64) -> Vec3 {
+    if luminance > 0. {
+        (new_luminance / luminance) * colour
+    } else {
+        Vec3::zero()
+    }
+}
+
+/// Applies Narkowicz's ACES filmic curve fit to one channel, including the fit's 0.6 exposure
+fn aces(x: f64) -> f64 {
+    let x = 0.6 * x;
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+/// Encodes a linear value in the range [0,1] with the sRGB transfer curve
+pub fn srgb_encode(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
@@ -1,7 +1,9 @@
 use std::sync::Arc;
 use crate::aabb::AABB;
+use crate::bvh::BVHStatistics;
 use crate::hit_record::HitRecord;
 use crate::hittable::{Hittable, HittableTrait};
+use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::Vec3;
 
@@ -12,6 +14,7 @@ pub struct Translate {
 }
 
 impl Translate {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(object: Arc<Hittable>, offset: Vec3) -> Hittable {
         Hittable::Translate(Translate { object, offset })
     }
@@ -23,10 +26,11 @@ impl HittableTrait for Translate {
 
         match self.object.hit(&moved_ray, t_min, t_max) {
             None => None,
-            Some(mut rec) => {
+            Some(rec) => {
                 let mut hit_record = HitRecord::new(
                     rec.point + self.offset, &rec.material, rec.u, rec.v, rec.t
                 );
+                hit_record.object_id = rec.object_id;
                 hit_record.calculate_face_normal(&moved_ray, rec.normal);
 
                 Some(hit_record)
@@ -35,14 +39,55 @@ impl HittableTrait for Translate {
     }
 
     fn bounding_box(&self, time0: f64, time1: f64) -> Option<AABB> {
-        match self.object.bounding_box(time0, time1) {
-            None => None,
-            Some(output_box) => {
-                Some(AABB::new(
-                    output_box.minimum + self.offset,
-                    output_box.maximum + self.offset
-                ))
-            }
+        self.object.bounding_box(time0, time1).map(|output_box| {
+            AABB::new(
+                output_box.minimum + self.offset,
+                output_box.maximum + self.offset
+            )
+        })
+    }
+
+    fn object_count(&self) -> u32 {
+        self.object.object_count()
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        self.object.collect_materials(materials);
+    }
+
+    fn is_light(&self) -> bool {
+        self.object.is_light()
+    }
+
+    /// Lights within the object are moved along with it
+    fn collect_lights(&self, lights: &mut Vec<Arc<Hittable>>) {
+        let mut object_lights = vec![];
+        self.object.collect_lights(&mut object_lights);
+        for light in object_lights {
+            lights.push(Arc::new(Translate::new(light, self.offset)));
         }
     }
+
+    fn collect_statistics(&self, statistics: &mut Vec<BVHStatistics>) {
+        self.object.collect_statistics(statistics);
+    }
+
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        self.object.pdf_value(&(*origin - self.offset), direction)
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        self.object.random(&(*origin - self.offset))
+    }
+
+    fn area(&self) -> f64 {
+        self.object.area()
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        self.object.sample_surface().map(|mut hit_record| {
+            hit_record.point += self.offset;
+            hit_record
+        })
+    }
 }
\ No newline at end of file
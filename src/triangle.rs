@@ -0,0 +1,188 @@
+use std::sync::Arc;
+use crate::{random_double, INFINITY};
+use crate::aa_rect::rect_pdf_value;
+use crate::aabb::AABB;
+use crate::hit_record::HitRecord;
+use crate::hittable::{Hittable, HittableTrait};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+
+#[derive(Debug)]
+/// Object to store a Triangle object
+pub struct Triangle {
+    vertices: [Vec3; 3],                // Positions of the triangle's vertices
+    normals: Option<[Vec3; 3]>,         // Optional normals of each vertex, for smooth shading
+    uvs: Option<[(f64, f64); 3]>,       // Optional texture coordinates of each vertex
+    material: Arc<Material>,            // Material of the triangle
+}
+
+impl Triangle {
+    /// Constructs a new Triangle object from its vertices, wrapped in the Hittable enum
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(v0: Vec3, v1: Vec3, v2: Vec3, material: Arc<Material>) -> Hittable {
+        Hittable::Triangle(Triangle { vertices: [v0, v1, v2], normals: None, uvs: None, material })
+    }
+
+    /// Constructs a new Triangle object with optional per-vertex normals and texture coordinates,
+    /// wrapped in the Hittable enum
+    pub fn with_attributes(
+        vertices: [Vec3; 3], normals: Option<[Vec3; 3]>, uvs: Option<[(f64, f64); 3]>,
+        material: Arc<Material>
+    ) -> Hittable {
+        Hittable::Triangle(Triangle { vertices, normals, uvs, material })
+    }
+
+    /// Intersects a ray with the triangle defined by three points using the Möller–Trumbore
+    /// algorithm, returning the hit time and barycentric coordinates of the hit
+    pub fn intersect(
+        ray: &Ray, v0: Vec3, v1: Vec3, v2: Vec3, t_min: f64, t_max: f64
+    ) -> Option<(f64, f64, f64)> {
+        let edge1 = v1 - v0;
+        let edge2 = v2 - v0;
+
+        // Rays parallel to the triangle's plane can't hit it
+        let p = Vec3::cross(&ray.direction, &edge2);
+        let determinant = Vec3::dot(&edge1, &p);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let inv_determinant = 1. / determinant;
+
+        // Calculates the barycentric coordinates of the hit, returning None if outside the triangle
+        let s = ray.origin - v0;
+        let b1 = Vec3::dot(&s, &p) * inv_determinant;
+        if !(0. ..=1.).contains(&b1) {
+            return None;
+        }
+
+        let q = Vec3::cross(&s, &edge1);
+        let b2 = Vec3::dot(&ray.direction, &q) * inv_determinant;
+        if b2 < 0. || b1 + b2 > 1. {
+            return None;
+        }
+
+        let t = Vec3::dot(&edge2, &q) * inv_determinant;
+        if t < t_min || t > t_max {
+            return None;
+        }
+
+        Some((t, b1, b2))
+    }
+
+    /// Gets the bounding box of the triangle defined by three points
+    pub fn bounds(v0: Vec3, v1: Vec3, v2: Vec3) -> AABB {
+        let mut minimum = Vec3::zero();
+        let mut maximum = Vec3::zero();
+        for a in 0..3 {
+            // Pads the box slightly so that axis-aligned triangles don't have zero thickness
+            minimum[a] = f64::min(v0[a], f64::min(v1[a], v2[a])) - 0.0001;
+            maximum[a] = f64::max(v0[a], f64::max(v1[a], v2[a])) + 0.0001;
+        }
+        AABB::new(minimum, maximum)
+    }
+
+    /// Chooses a random point uniformly over the triangle defined by three points, returning
+    /// its barycentric coordinates for the second and third points
+    pub fn sample_point(v0: Vec3, v1: Vec3, v2: Vec3) -> (Vec3, f64, f64) {
+        // Points outside the triangle are folded back into it across the far edge
+        let (mut b1, mut b2) = (random_double(), random_double());
+        if b1 + b2 > 1. {
+            (b1, b2) = (1. - b1, 1. - b2);
+        }
+        ((1. - b1 - b2) * v0 + b1 * v1 + b2 * v2, b1, b2)
+    }
+
+    /// Gets the area of the triangle defined by three points
+    pub fn area_of(v0: Vec3, v1: Vec3, v2: Vec3) -> f64 {
+        0.5 * Vec3::cross(&(v1 - v0), &(v2 - v0)).length()
+    }
+
+    /// Gets the geometric normal of the triangle defined by three points, which faces the side
+    /// its vertices run anticlockwise around
+    pub fn face_normal(v0: Vec3, v1: Vec3, v2: Vec3) -> Vec3 {
+        Vec3::cross(&(v1 - v0), &(v2 - v0)).unit()
+    }
+
+    /// Gets the barycentric-interpolated texture coordinates and outward normal of a point on
+    /// the triangle
+    fn attributes(&self, b1: f64, b2: f64) -> ((f64, f64), Vec3) {
+        let [v0, v1, v2] = self.vertices;
+        let b0 = 1. - b1 - b2;
+
+        // Uses the vertex texture coordinates if given, otherwise the barycentric coordinates
+        let uv = match &self.uvs {
+            Some([uv0, uv1, uv2]) => (
+                b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1
+            ),
+            None => (b1, b2)
+        };
+
+        // Interpolates the vertex normals if given, otherwise uses the face's geometric normal
+        let outward_normal = match &self.normals {
+            Some([n0, n1, n2]) => (b0 * *n0 + b1 * *n1 + b2 * *n2).unit(),
+            None => Triangle::face_normal(v0, v1, v2)
+        };
+
+        (uv, outward_normal)
+    }
+}
+
+impl HittableTrait for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let [v0, v1, v2] = self.vertices;
+        let (t, b1, b2) = Triangle::intersect(ray, v0, v1, v2, t_min, t_max)?;
+        let ((u, v), outward_normal) = self.attributes(b1, b2);
+
+        let mut hit_record = HitRecord::new(ray.at(t), &self.material, u, v, t);
+        hit_record.calculate_face_normal(ray, outward_normal);
+
+        Some(hit_record)
+    }
+
+    fn bounding_box(&self, _: f64, _: f64) -> Option<AABB> {
+        let [v0, v1, v2] = self.vertices;
+        Some(Triangle::bounds(v0, v1, v2))
+    }
+
+    fn collect_materials(&self, materials: &mut Vec<Arc<Material>>) {
+        materials.push(Arc::clone(&self.material));
+    }
+
+    fn is_light(&self) -> bool {
+        matches!(self.material.as_ref(), Material::DiffuseLight(_))
+    }
+
+    /// Light leaves the flat triangle, so its density uses the face normal rather than any
+    /// interpolated vertex normals
+    fn pdf_value(&self, origin: &Vec3, direction: &Vec3) -> f64 {
+        let [v0, v1, v2] = self.vertices;
+        let hit_record = self.hit(&Ray::new(*origin, *direction, 0.), 0.001, INFINITY).map(|mut hit_record| {
+            hit_record.normal = Triangle::face_normal(v0, v1, v2);
+            hit_record
+        });
+        rect_pdf_value(hit_record, direction, self.area())
+    }
+
+    fn random(&self, origin: &Vec3) -> Vec3 {
+        let [v0, v1, v2] = self.vertices;
+        Triangle::sample_point(v0, v1, v2).0 - *origin
+    }
+
+    fn area(&self) -> f64 {
+        let [v0, v1, v2] = self.vertices;
+        Triangle::area_of(v0, v1, v2)
+    }
+
+    fn sample_surface(&self) -> Option<HitRecord> {
+        let [v0, v1, v2] = self.vertices;
+        let (point, b1, b2) = Triangle::sample_point(v0, v1, v2);
+        let ((u, v), _) = self.attributes(b1, b2);
+        let mut hit_record = HitRecord::new(point, &self.material, u, v, 0.);
+        hit_record.normal = Triangle::face_normal(v0, v1, v2);
+        hit_record.front_face = true;
+        Some(hit_record)
+    }
+}
+
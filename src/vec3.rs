@@ -1,6 +1,6 @@
 use std::fmt::{Display, Formatter};
 use std::ops::{Add, AddAssign, Neg, Sub, SubAssign, Mul, MulAssign, Div, DivAssign, Index, IndexMut};
-use ray_tracing::{ random_range };
+use crate::{random_range, PI};
 
 #[derive(Debug)]
 /// Object to represent a column vector in 3 dimensions
@@ -46,15 +46,20 @@ impl Vec3 {
         }
     }
 
-    /// Returns a random vector in a unit disk
+    /// Returns a random vector in a unit disk, mapped from exactly two random numbers so that
+    /// evenly spread numbers give evenly spread points
     pub fn random_in_unit_disk() -> Vec3 {
-        loop {
-            let p = Vec3::new(random_range(-1., 1.), random_range(-1., 1.), 0.);
-            if p.length_squared() >= 1. {
-                continue
-            }
-            return p;
+        // Concentric mapping of squares around the centre onto circles
+        let a = random_range(-1., 1.);
+        let b = random_range(-1., 1.);
+        if a == 0. && b == 0. {
+            return Vec3::zero();
         }
+        let (radius, theta) = match a.abs() > b.abs() {
+            true => (a, PI / 4. * b / a),
+            false => (b, PI / 2. - PI / 4. * a / b),
+        };
+        Vec3::new(radius * theta.cos(), radius * theta.sin(), 0.)
     }
 
     /// Returns a random unit vector
@@ -101,11 +106,18 @@ impl Vec3 {
 /// Allows vectors to be cloned directly by constructing a vector with the same x, y, and z
 impl Clone for Vec3 {
     fn clone(&self) -> Self {
-        Vec3::new(self.x, self.y, self.z)
+        *self
     }
 }
 impl Copy for Vec3 { }
 
+/// Allows vectors to be constructed from an array of their x, y, and z values
+impl From<[f64; 3]> for Vec3 {
+    fn from(v: [f64; 3]) -> Self {
+        Vec3::new(v[0], v[1], v[2])
+    }
+}
+
 /// Implements vector addition
 impl Add for Vec3 {
     type Output = Self;
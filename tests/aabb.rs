@@ -0,0 +1,45 @@
+use ray_tracing::*;
+
+/// Unit cube at the origin
+fn unit_cube() -> AABB {
+    AABB::new(Vec3::new(0., 0., 0.), Vec3::new(1., 1., 1.))
+}
+
+#[test]
+fn rays_through_the_box_hit_it() {
+    let cube = unit_cube();
+    let through = |origin: Vec3, target: Vec3| cube.hit(&Ray::new(origin, target - origin, 0.), 0.001, f64::INFINITY);
+    assert!(through(Vec3::new(-1., 0.5, 0.5), Vec3::new(0.5, 0.5, 0.5)));
+    assert!(through(Vec3::new(3., 4., -2.), Vec3::new(0.2, 0.9, 0.6)));
+    assert!(through(Vec3::new(0.5, 0.5, 0.5), Vec3::new(2., 3., 4.)));
+
+    // Only the part of the ray between t_min and t_max counts
+    let ray = Ray::new(Vec3::new(-2., 0.5, 0.5), Vec3::new(1., 0., 0.), 0.);
+    assert!(cube.hit(&ray, 0.001, 2.5));
+    assert!(!cube.hit(&ray, 0.001, 1.5));
+    assert!(!cube.hit(&ray, 3.5, f64::INFINITY));
+}
+
+#[test]
+fn rays_crossing_each_slab_at_different_times_miss() {
+    // Passes through the x slab for t in [1, 2] and the y slab for t in [3, 4], so never
+    // inside both at once, and lies within the z slab throughout
+    let cube = unit_cube();
+    let ray = Ray::new(Vec3::new(-1., -3., 0.5), Vec3::new(1., 1., 0.), 0.);
+    assert!(!cube.hit(&ray, 0.001, f64::INFINITY));
+
+    // The same holds along every pair of axes and in either direction
+    for axis in 0..3 {
+        let (a, b, c) = (axis, (axis + 1) % 3, (axis + 2) % 3);
+        for sign in [1., -1.] {
+            let mut origin = Vec3::new(0.5, 0.5, 0.5);
+            let mut direction = Vec3::new(0., 0., 0.);
+            origin[a] = 0.5 - 1.5 * sign;
+            origin[b] = 0.5 - 3.5 * sign;
+            origin[c] = 0.5;
+            direction[a] = sign;
+            direction[b] = sign;
+            assert!(!cube.hit(&Ray::new(origin, direction, 0.), 0.001, f64::INFINITY), "axes {a} and {b}, sign {sign}");
+        }
+    }
+}
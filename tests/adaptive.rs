@@ -0,0 +1,86 @@
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Builds a small render of the Cornell box with adaptive sampling
+fn cornell_box(samples_per_pixel: i32, tile_size: usize) -> (Scene, RenderSettings) {
+    seed_random(11);
+    let scene = build_scene("cornell-box", 1., BVHBuildMethod::SAH).unwrap();
+    let settings = RenderSettings {
+        image_width: 20,
+        image_height: 20,
+        samples_per_pixel,
+        max_depth: Some(10),
+        seed: 11,
+        tile_size,
+        filter: Filter::new(FilterShape::Tent),
+        adaptive: Some(AdaptiveSettings { noise_threshold: 0.1, min_samples: 8 }),
+        ..Default::default()
+    };
+    (scene, settings)
+}
+
+#[test]
+fn running_variance_matches_the_samples() {
+    let values = [0.5, 2., 0., 1.25, 3.5, 0.75];
+    let mut variance = PixelVariance::default();
+    for (n, &value) in values.iter().enumerate() {
+        variance.add(value, n as u32 + 1);
+    }
+
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let expected = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.);
+    assert!((variance.mean - mean).abs() < 1e-12);
+    assert!((variance.variance(values.len() as u32) - expected).abs() < 1e-12);
+    assert!((variance.standard_error(values.len() as u32) - (expected / n).sqrt()).abs() < 1e-12);
+}
+
+#[test]
+fn pixels_stop_once_converged() {
+    let (scene, settings) = cornell_box(64, 8);
+    let buffer = render(&scene, &settings, &ProgressBar::hidden());
+    let adaptive = settings.adaptive.unwrap();
+
+    // Every pixel takes the minimum, and only stops short of the maximum once converged
+    for i in 0..buffer.samples.len() {
+        assert!(buffer.samples[i] >= adaptive.min_samples && buffer.samples[i] <= 64);
+        if buffer.samples[i] < 64 {
+            assert!(adaptive.converged(&buffer.variance[i], buffer.samples[i]));
+        }
+    }
+
+    // The black background converges straight away, while the lit interior keeps sampling
+    assert!(buffer.samples.contains(&adaptive.min_samples));
+    assert!(buffer.samples.contains(&64));
+}
+
+#[test]
+fn adaptive_renders_do_not_depend_on_passes_or_tiles() {
+    let (scene, settings) = cornell_box(48, 8);
+    let single = render(&scene, &settings, &ProgressBar::hidden());
+
+    let (scene, mut settings) = cornell_box(10, 3);
+    let mut buffer = RenderBuffer::new(settings.image_width, settings.image_height);
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+    settings.samples_per_pixel = 48;
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+
+    assert_eq!(single.samples, buffer.samples);
+    assert!(single.radiance.iter().zip(&buffer.radiance).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z));
+}
+
+#[test]
+fn sample_heatmaps_store_the_samples_taken() {
+    let (scene, settings) = cornell_box(32, 8);
+    let buffer = render(&scene, &settings, &ProgressBar::hidden());
+
+    let path = std::env::temp_dir().join(format!("ray_tracing_heatmap_{}.exr", std::process::id()));
+    save_sample_heatmap(&buffer, &path, &OutputSettings::default()).unwrap();
+    let image = image::open(&path).unwrap().into_rgb32f();
+    std::fs::remove_file(&path).unwrap();
+
+    for (pixel, &samples) in image.pixels().zip(&buffer.samples) {
+        assert_eq!(pixel.0, [samples as f32; 3]);
+    }
+}
@@ -0,0 +1,104 @@
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Builds a small render of the Cornell box, optionally recording AOVs
+fn cornell_box(aovs: bool) -> (Scene, RenderSettings) {
+    seed_random(3);
+    let scene = build_scene("cornell-box", 1., BVHBuildMethod::SAH).unwrap();
+    let settings = RenderSettings {
+        image_width: 20,
+        image_height: 20,
+        samples_per_pixel: 3,
+        max_depth: Some(10),
+        seed: 3,
+        tile_size: 8,
+        filter: Filter::new(FilterShape::Tent),
+        aovs,
+        ..Default::default()
+    };
+    (scene, settings)
+}
+
+#[test]
+fn recording_aovs_does_not_change_the_image() {
+    let (scene, settings) = cornell_box(false);
+    let without = render(&scene, &settings, &ProgressBar::hidden());
+    let (scene, settings) = cornell_box(true);
+    let with = render(&scene, &settings, &ProgressBar::hidden());
+
+    assert!(without.aovs.is_none() && with.aovs.is_some());
+    assert!(without.radiance.iter().zip(&with.radiance).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z));
+}
+
+#[test]
+fn aovs_describe_the_first_hit() {
+    let (scene, settings) = cornell_box(true);
+    let buffer = render(&scene, &settings, &ProgressBar::hidden());
+
+    // Pixels whose first sample missed have no IDs, while the rest hit something in front
+    // of the camera with a normal facing it
+    let depth = &buffer.aov_channels(Aov::Depth).unwrap()[0];
+    let object_ids = &buffer.aov_channels(Aov::ObjectId).unwrap()[0];
+    let normal = buffer.aov_channels(Aov::Normal).unwrap();
+    assert!(object_ids.iter().filter(|&&id| id >= 0.).count() > depth.len() / 2);
+    for i in 0..depth.len() {
+        if object_ids[i] >= 0. {
+            let length = (normal[0][i].powi(2) + normal[1][i].powi(2) + normal[2][i].powi(2)).sqrt();
+            assert!(depth[i] > 0. && length > 0.1 && length < 1.001);
+        }
+    }
+
+    // The walls, light and boxes are separate objects, while the walls share materials
+    let count_distinct = |aov: Aov| {
+        let mut ids = buffer.aov_channels(aov).unwrap().remove(0);
+        ids.sort_by(f32::total_cmp);
+        ids.dedup();
+        assert!(ids.iter().all(|&id| id >= -1. && id.fract() == 0.));
+        ids.len()
+    };
+    assert!(count_distinct(Aov::ObjectId) > count_distinct(Aov::MaterialId));
+    assert!(count_distinct(Aov::MaterialId) > 1);
+}
+
+#[test]
+fn aovs_are_saved_as_exr_layers() {
+    let (scene, settings) = cornell_box(true);
+    let buffer = render(&scene, &settings, &ProgressBar::hidden());
+    let output = OutputSettings { aovs: Aov::ALL.to_vec(), ..OutputSettings::default() };
+    let path = std::env::temp_dir().join(format!("ray-tracing-test-aovs-{}.exr", std::process::id()));
+    save_image(&buffer, &path, &output).unwrap();
+
+    let image = exr::prelude::read_all_flat_layers_from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let channels = &image.layer_data[0].channel_data.list;
+    let channel = |name: &str| channels.iter().find(|c| c.name.to_string() == name).map(|c| c.sample_data.values_as_f32().collect::<Vec<_>>());
+
+    let linear = buffer.to_linear_image();
+    assert_eq!(channel("G").unwrap(), linear.pixels().map(|p| p.0[1]).collect::<Vec<_>>());
+    for aov in Aov::ALL {
+        let expected = buffer.aov_channels(aov).unwrap();
+        for (name, values) in aov.channels().iter().zip(expected) {
+            assert_eq!(channel(&format!("{}.{name}", aov.name())).unwrap(), values);
+        }
+    }
+}
+
+#[test]
+fn aovs_are_kept_by_checkpoints() {
+    let (scene, settings) = cornell_box(true);
+    let buffer = render(&scene, &settings, &ProgressBar::hidden());
+    let header = CheckpointHeader {
+        scene: SceneSource::BuiltIn("cornell-box".to_string()),
+        bvh_method: BVHBuildMethod::SAH,
+        settings,
+    };
+    let path = std::env::temp_dir().join(format!("ray-tracing-test-aovs-{}.rtc", std::process::id()));
+    save_checkpoint(&path, &header, &buffer).unwrap();
+    let (_, loaded) = load_checkpoint(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    for aov in Aov::ALL {
+        assert_eq!(buffer.aov_channels(aov), loaded.aov_channels(aov));
+    }
+}
@@ -0,0 +1,122 @@
+use std::path::Path;
+use std::sync::Arc;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Settings for a small render with a given integrator
+fn settings(size: usize, samples_per_pixel: i32, max_depth: Option<i32>, integrator: Integrator) -> RenderSettings {
+    RenderSettings {
+        image_width: size,
+        image_height: size,
+        samples_per_pixel,
+        max_depth,
+        seed: 23,
+        tile_size: 8,
+        integrator,
+        ..Default::default()
+    }
+}
+
+/// Mean radiance of every channel of every pixel of a render
+fn mean(buffer: &RenderBuffer) -> f64 {
+    let sum: f64 = (0..buffer.radiance.len()).map(|i| {
+        let colour = buffer.pixel(i);
+        colour.x + colour.y + colour.z
+    }).sum();
+    sum / (3 * buffer.radiance.len()) as f64
+}
+
+/// Root mean square difference between the radiance of two renders, clamped to what a display
+/// shows so that pixels on the edges of bright lights don't outweigh the rest of the image
+fn rmse(a: &RenderBuffer, b: &RenderBuffer) -> f64 {
+    let clamp = |c: Vec3| Vec3::new(c.x.min(1.), c.y.min(1.), c.z.min(1.));
+    let sum: f64 = (0..a.radiance.len()).map(|i| (clamp(a.pixel(i)) - clamp(b.pixel(i))).length_squared()).sum();
+    (sum / (3 * a.radiance.len()) as f64).sqrt()
+}
+
+/// Checks that two renders are identical down to the bit
+fn identical(a: &RenderBuffer, b: &RenderBuffer) -> bool {
+    let same = |a: &[Vec3], b: &[Vec3]| a.iter().zip(b).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z);
+    a.samples == b.samples && a.light_paths == b.light_paths && same(&a.radiance, &b.radiance) && same(&a.light, &b.light)
+}
+
+#[test]
+fn bidirectional_renders_match_the_path_tracer() {
+    for (name, max_depth) in [("simple-light", Some(4)), ("cornell-box-smoke", Some(4)), ("cornell-box", None)] {
+        seed_random(23);
+        let scene = build_scene(name, 1., BVHBuildMethod::SAH).unwrap();
+        let path = mean(&render(&scene, &settings(12, 256, max_depth, Integrator::Path), &ProgressBar::hidden()));
+        let bidirectional = mean(&render(&scene, &settings(12, 64, max_depth, Integrator::Bidirectional), &ProgressBar::hidden()));
+        assert!((path - bidirectional).abs() < 0.03 * path, "{name}: {bidirectional} differs from {path}");
+    }
+}
+
+#[test]
+fn bidirectional_renders_resolve_caustics() {
+    // Light focused by the glass sphere can only be found by paths leaving the light
+    let scene = load_scene(Path::new("scenes/caustics.toml"), 1., BVHBuildMethod::SAH).unwrap();
+    let render_with = |samples_per_pixel, integrator| {
+        render(&scene, &settings(16, samples_per_pixel, Some(6), integrator), &ProgressBar::hidden())
+    };
+    let reference = render_with(256, Integrator::Bidirectional);
+    let path = rmse(&render_with(16, Integrator::Path), &reference);
+    let bidirectional = rmse(&render_with(16, Integrator::Bidirectional), &reference);
+    assert!(bidirectional < 0.5 * path, "{bidirectional} is not much less noisy than {path}");
+}
+
+#[test]
+fn light_paths_splat_onto_the_image() {
+    seed_random(23);
+    let scene = build_scene("cornell-box", 1., BVHBuildMethod::SAH).unwrap();
+    let buffer = render(&scene, &settings(8, 4, Some(4), Integrator::Bidirectional), &ProgressBar::hidden());
+    assert_eq!(buffer.light_paths, 8 * 8 * 4);
+    assert!(buffer.light.iter().any(|light| light.length_squared() > 0.));
+
+    // The path tracer leaves the light image empty
+    let buffer = render(&scene, &settings(8, 4, Some(4), Integrator::Path), &ProgressBar::hidden());
+    assert_eq!(buffer.light_paths, 0);
+}
+
+#[test]
+fn bidirectional_renders_are_reproducible() {
+    seed_random(23);
+    let scene = build_scene("cornell-box-smoke", 1., BVHBuildMethod::SAH).unwrap();
+    let mut settings = settings(10, 4, Some(5), Integrator::Bidirectional);
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap()
+        .install(|| render(&scene, &settings, &ProgressBar::hidden()));
+
+    // Renders two samples per pixel first, then continues to four with a different tile size
+    settings.samples_per_pixel = 2;
+    settings.tile_size = 3;
+    let mut buffer = RenderBuffer::new(settings.image_width, settings.image_height);
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+    settings.samples_per_pixel = 4;
+    settings.tile_size = 5;
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+
+    assert!(identical(&single, &buffer));
+}
+
+#[test]
+fn sampled_surface_points_lie_on_lights() {
+    let light = Arc::new(DiffuseLight::from_colour(1., 1., 1.));
+    let sphere = Sphere::new(Vec3::new(1., 2., 0.5), 0.75, Arc::clone(&light));
+    let rect = XZRect::new(-1., 2., -0.5, 1., 1., Arc::clone(&light));
+    let moved = Translate::new(Arc::new(RotateY::new(Arc::new(YZRect::new(0., 1., 0., 2., 0., light)), 30.)), Vec3::new(2., 0., 1.));
+
+    assert!((sphere.area() - 4. * PI * 0.75 * 0.75).abs() < 1e-9);
+    assert!((rect.area() - 4.5).abs() < 1e-9);
+    assert!((moved.area() - 2.).abs() < 1e-9);
+
+    seed_random(5);
+    for object in [&sphere, &rect, &moved] {
+        for _ in 0..100 {
+            // A ray back along the normal finds the sampled point
+            let sample = object.sample_surface().unwrap();
+            let ray = Ray::new(sample.point + sample.normal, -sample.normal, 0.);
+            let hit = object.hit(&ray, 0.001, INFINITY).unwrap();
+            assert!((hit.point - sample.point).length() < 1e-6);
+        }
+    }
+}
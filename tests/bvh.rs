@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use ray_tracing::*;
+use ray_tracing::bvh::{count_traversal_steps, reset_traversal_steps, traversal_steps};
+
+/// Grey material shared by the objects below
+fn grey() -> Arc<Material> {
+    Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+}
+
+/// Spheres of random sizes scattered in clusters, which the SAH should separate
+fn clustered_spheres(count: usize) -> HittableList {
+    seed_random(31);
+    let centres = [Vec3::new(-40., 0., 0.), Vec3::new(0., 30., 5.), Vec3::new(25., -10., -20.)];
+    let mut list = HittableList::new();
+    for i in 0..count {
+        let centre = centres[i % centres.len()] + Vec3::random(-4., 4.);
+        list.add(Sphere::new(centre, random_range(0.05, 1.), grey()));
+    }
+    list
+}
+
+/// Gets the statistics of a hittable which is a BVH
+fn statistics(bvh: &Hittable) -> BVHStatistics {
+    match bvh {
+        Hittable::BVH(bvh) => bvh.statistics(),
+        _ => panic!("not a BVH"),
+    }
+}
+
+/// Checks the counts of a set of statistics agree with each other
+fn assert_consistent(statistics: &BVHStatistics, object_count: usize) {
+    assert_eq!(statistics.primitive_count, object_count);
+    assert_eq!(statistics.node_count, 2 * statistics.leaf_count - 1);
+    assert_eq!(statistics.leaf_sizes.iter().sum::<usize>(), statistics.leaf_count);
+    assert_eq!(statistics.leaf_sizes.iter().enumerate().map(|(size, count)| size * count).sum::<usize>(), object_count);
+    assert!(statistics.total_leaf_depth <= statistics.leaf_count * statistics.max_depth);
+}
+
+#[test]
+fn sah_bounds_surround_every_object() {
+    let list = clustered_spheres(300);
+    let expected = list.bounding_box(0., 1.).unwrap();
+    for method in [BVHBuildMethod::RandomAxis, BVHBuildMethod::SAH] {
+        let bvh = BVH::build(&list, 0., 1., method);
+        let bounds = bvh.bounding_box(0., 1.).unwrap();
+        assert!((bounds.minimum - expected.minimum).near_zero() && (bounds.maximum - expected.maximum).near_zero());
+
+        // Rays aimed at the centre of each sphere find it, or something in front of it
+        for object in &list.objects {
+            let centre = object.bounding_box(0., 1.).unwrap().centroid();
+            let origin = Vec3::new(0., 0., 200.);
+            let ray = Ray::new(origin, centre - origin, 0.);
+            let hit = bvh.hit(&ray, 0.001, INFINITY).unwrap();
+            assert!(hit.t <= object.hit(&ray, 0.001, INFINITY).unwrap().t);
+        }
+    }
+}
+
+#[test]
+fn sah_separates_clusters_more_cheaply() {
+    let list = clustered_spheres(300);
+    let random_axis = statistics(&BVH::build(&list, 0., 1., BVHBuildMethod::RandomAxis));
+    let sah = statistics(&BVH::build(&list, 0., 1., BVHBuildMethod::SAH));
+    assert_consistent(&random_axis, 300);
+    assert_consistent(&sah, 300);
+    assert!(sah.traversal_cost < random_axis.traversal_cost, "{} isn't below {}", sah.traversal_cost, random_axis.traversal_cost);
+
+    // Leaves never hold more objects than can be tested cheaply
+    assert!(sah.leaf_sizes.len() <= 5 && random_axis.leaf_sizes.len() <= 5);
+}
+
+#[test]
+fn statistics_count_the_nodes_of_small_trees() {
+    let mut list = HittableList::new();
+    list.add(Sphere::new(Vec3::new(-10., 0., 0.), 0.5, grey()));
+    list.add(Sphere::new(Vec3::new(10., 0., 0.), 0.5, grey()));
+
+    // Median splits keep small lists as one leaf, which tests every object
+    let random_axis = statistics(&BVH::build(&list, 0., 1., BVHBuildMethod::RandomAxis));
+    assert_eq!((random_axis.node_count, random_axis.leaf_count, random_axis.max_depth), (1, 1, 1));
+    assert_eq!(random_axis.leaf_sizes, vec![0, 0, 1]);
+    assert!((random_axis.traversal_cost - 2.).abs() < 1e-9);
+
+    // The SAH splits distant objects apart, as testing a small box each is cheaper. The root's
+    // 21 by 1 by 1 box is always tested, and each sphere's cube with the chance of hitting it
+    let sah = statistics(&BVH::build(&list, 0., 1., BVHBuildMethod::SAH));
+    assert_eq!((sah.node_count, sah.leaf_count, sah.max_depth, sah.total_leaf_depth), (3, 2, 2, 4));
+    assert_eq!(sah.leaf_sizes, vec![0, 2]);
+    assert!((sah.traversal_cost - (1. + 2. * 6. / 86.)).abs() < 1e-9, "{}", sah.traversal_cost);
+}
+
+#[test]
+fn statistics_are_collected_from_nested_hierarchies() {
+    let inner = Arc::new(BVH::build(&clustered_spheres(20), 0., 1., BVHBuildMethod::SAH));
+    let mesh = Mesh::from_buffers(
+        vec![[0., 0., 0.], [1., 0., 0.], [0., 1., 0.], [1., 1., 0.]], vec![[0, 1, 2], [1, 3, 2]], grey()
+    );
+
+    let mut list = HittableList::new();
+    list.add(Translate::new(Arc::new(RotateY::new(inner, 30.)), Vec3::new(0., 5., 0.)));
+    list.add(mesh);
+    list.add(Sphere::new(Vec3::zero(), 1., grey()));
+    let world = BVH::build(&list, 0., 1., BVHBuildMethod::SAH);
+
+    // The outer BVH comes first, followed by those within its objects in the order they were given
+    let mut collected = vec![];
+    world.collect_statistics(&mut collected);
+    let descriptions: Vec<&str> = collected.iter().map(|s| s.description.as_str()).collect();
+    assert_eq!(descriptions, ["BVH of 3 objects", "BVH of 20 objects", "Mesh of 2 triangles"]);
+    assert_consistent(&collected[0], 3);
+    assert_consistent(&collected[1], 20);
+    assert_consistent(&collected[2], 2);
+
+    // Objects without hierarchies have no statistics
+    let mut none = vec![];
+    Sphere::new(Vec3::zero(), 1., grey()).collect_statistics(&mut none);
+    assert!(none.is_empty());
+}
+
+#[test]
+fn bvh_hits_match_a_plain_list() {
+    // Overlapping spheres, rectangles and triangles of every size, some nested in their own
+    // BVHs which are moved away from the copies of their spheres in the outer list
+    let mut list = clustered_spheres(200);
+    for i in 0..4 {
+        let nested = BVH::build(&clustered_spheres(12), 0., 1., BVHBuildMethod::SAH);
+        list.add(Translate::new(Arc::new(nested), Vec3::new(0., 10. * i as f64 + 5., 3.)));
+    }
+    seed_random(29);
+    for _ in 0..20 {
+        let (x, z) = (random_range(-40., 30.), random_range(-25., 10.));
+        list.add(XZRect::new(x, x + random_range(0.5, 10.), z, z + random_range(0.5, 10.), random_range(-10., 30.), grey()));
+        let corner = Vec3::random(-40., 30.);
+        list.add(Triangle::new(corner, corner + Vec3::random(-5., 5.), corner + Vec3::random(-5., 5.), grey()));
+    }
+
+    for method in [BVHBuildMethod::RandomAxis, BVHBuildMethod::SAH] {
+        seed_random(37);
+        let bvh = BVH::build(&list, 0., 1., method);
+
+        // Rays start inside and outside the scene, and point at objects or every other way,
+        // including along the axes
+        let mut hits = 0;
+        for i in 0..20_000 {
+            let origin = Vec3::random(-60., 50.);
+            let mut direction = Vec3::random_unit_vector();
+            if i % 2 == 1 {
+                let object = &list.objects[random_int(0, list.objects.len() as i32 - 1) as usize];
+                direction = object.bounding_box(0., 1.).unwrap().centroid() + Vec3::random(-1., 1.) - origin;
+            } else if i % 10 == 0 {
+                direction = Vec3::zero();
+                direction[i / 10 % 3] = if i % 20 == 0 { 1. } else { -1. };
+            }
+            let ray = Ray::new(origin, direction, 0.);
+            let t_max = if i % 3 == 0 { random_range(1., 80.) } else { INFINITY };
+            match (bvh.hit(&ray, 0.001, t_max), list.hit(&ray, 0.001, t_max)) {
+                (Some(a), Some(b)) => {
+                    assert_eq!(a.t, b.t);
+                    assert_eq!(a.object_id, b.object_id);
+                    assert!((a.normal - b.normal).near_zero() && Arc::ptr_eq(&a.material, &b.material));
+                    hits += 1;
+                },
+                (None, None) => {},
+                (a, b) => panic!("{method:?} BVH hit {:?} but the list hit {:?}", a.map(|h| h.t), b.map(|h| h.t)),
+            }
+        }
+        assert!(hits > 5000, "only {hits} rays hit anything");
+    }
+}
+
+#[test]
+fn random_axis_builds_draw_as_many_numbers_as_the_original_builder() {
+    // The next number drawn after building BVHs of each size from the same seed, as recorded
+    // with the original recursive builder, so that seeded scenes keep their layouts
+    let recorded = [
+        (1, 0.6164833950629205), (2, 0.5253119209364541), (3, 0.18055345972966963), (4, 0.5419443569584587),
+        (5, 0.5046621233234796), (7, 0.6089345433079398), (9, 0.9468768940802229), (13, 0.565380066845941),
+        (100, 0.9767422135251131), (301, 0.7875778846426388),
+    ];
+    for (count, next) in recorded {
+        seed_random(5);
+        let mut list = HittableList::new();
+        for _ in 0..count {
+            list.add(Sphere::new(Vec3::random(-10., 10.), 0.5, grey()));
+        }
+        BVH::build(&list, 0., 1., BVHBuildMethod::RandomAxis);
+        assert_eq!(random_double(), next, "{count} objects");
+    }
+}
+
+#[test]
+fn traversal_steps_are_only_counted_while_a_guard_lives() {
+    let bvh = BVH::build(&clustered_spheres(50), 0., 1., BVHBuildMethod::SAH);
+    let ray = Ray::new(Vec3::new(-60., 0., 0.), Vec3::new(1., 0., 0.), 0.);
+    let steps = || {
+        reset_traversal_steps();
+        bvh.hit(&ray, 0.001, INFINITY);
+        traversal_steps()
+    };
+
+    // No other test in this file counts steps, so none are counted outside the guards here
+    assert_eq!(steps(), 0);
+    let counting = count_traversal_steps();
+    let nested = count_traversal_steps();
+    assert!(steps() > 0);
+    drop(nested);
+    assert!(steps() > 0);
+    drop(counting);
+    assert_eq!(steps(), 0);
+}
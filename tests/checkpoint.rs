@@ -0,0 +1,75 @@
+use indicatif::ProgressBar;
+use ray_tracing::*;
+
+/// Builds the header of a small render of the Cornell box with smoke
+fn header(samples_per_pixel: i32, integrator: Integrator) -> CheckpointHeader {
+    CheckpointHeader {
+        scene: SceneSource::BuiltIn("cornell-box-smoke".to_string()),
+        bvh_method: BVHBuildMethod::SAH,
+        settings: RenderSettings {
+            image_width: 16,
+            image_height: 12,
+            samples_per_pixel,
+            max_depth: Some(10),
+            seed: u64::MAX - 5,
+            tile_size: 8,
+            integrator,
+            ..Default::default()
+        },
+    }
+}
+
+/// Builds the scene of a checkpoint header
+fn scene(header: &CheckpointHeader) -> Scene {
+    seed_random(header.settings.seed);
+    header.scene.build(4. / 3., header.bvh_method).unwrap()
+}
+
+/// Checks that a render resumed from a checkpoint matches one rendered without stopping
+fn check_resumed_render(integrator: Integrator) {
+    let full = header(6, integrator);
+    let expected = render(&scene(&full), &full.settings, &ProgressBar::hidden());
+
+    // Renders part of the samples and saves them to a checkpoint
+    let partial = header(2, integrator);
+    let buffer = render(&scene(&partial), &partial.settings, &ProgressBar::hidden());
+    let path = std::env::temp_dir().join(format!("ray-tracing-test-{integrator:?}-{}.rtc", std::process::id()));
+    save_checkpoint(&path, &partial, &buffer).unwrap();
+
+    // Resumes from the checkpoint with more samples
+    let (mut header, mut buffer) = load_checkpoint(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(header.settings.seed, partial.settings.seed);
+    header.settings.samples_per_pixel = 6;
+    render_progressive(&scene(&header), &header.settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+
+    let same = |a: &[Vec3], b: &[Vec3]| a.iter().zip(b).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z);
+    assert_eq!(expected.samples, buffer.samples);
+    assert!(same(&expected.radiance, &buffer.radiance));
+    assert_eq!(expected.light_paths, buffer.light_paths);
+    assert!(same(&expected.light, &buffer.light));
+}
+
+#[test]
+fn resumed_renders_match_uninterrupted_renders() {
+    check_resumed_render(Integrator::Path);
+}
+
+#[test]
+fn resumed_bidirectional_renders_match_uninterrupted_renders() {
+    check_resumed_render(Integrator::Bidirectional);
+}
+
+#[test]
+fn invalid_checkpoints_are_rejected() {
+    let path = std::env::temp_dir().join(format!("ray-tracing-test-invalid-{}.rtc", std::process::id()));
+    std::fs::write(&path, b"RTCKPT05\x05").unwrap();
+    let result = load_checkpoint(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(result.unwrap_err().contains("truncated"));
+}
+
+#[test]
+fn resumed_photon_mapping_renders_match_uninterrupted_renders() {
+    check_resumed_render(Integrator::PhotonMapping(PhotonSettings { photons: 1000, radius: Some(20.) }));
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::bvh::{count_traversal_steps, reset_traversal_steps, traversal_steps};
+use ray_tracing::scenes::build_scene;
+
+/// Builds a scene from a list of objects, viewed from along the z axis
+fn scene_of(objects: Vec<Hittable>) -> Scene {
+    let camera = Camera::new(
+        Vec3::new(0., 0., 5.), Vec3::zero(), Vec3::new(0., 1., 0.), 40., 0., 5., 1., 2., 0., 1.
+    );
+    Scene::new(HittableList::from_objects(objects), camera, Vec3::new(0.7, 0.8, 1.))
+}
+
+/// Builds a scene of a unit sphere at the origin
+fn sphere_scene() -> Scene {
+    scene_of(vec![Sphere::new(Vec3::zero(), 1., Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))))])
+}
+
+#[test]
+fn surfaces_show_their_properties() {
+    let scene = sphere_scene();
+    let ray = Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 0., -2.), 0.);
+
+    // The sphere is hit on its outside at (0, 0, 1), 4 units from the camera
+    let normal = debug_colour(&ray, &scene, &DebugView::Normal);
+    assert!((normal - Vec3::new(0.5, 0.5, 1.)).length() < 1e-9);
+    let distance = debug_colour(&ray, &scene, &DebugView::Distance);
+    assert!((distance.x - 4. / scene.extent()).abs() < 1e-9);
+    assert!((scene.extent() - 2. * f64::sqrt(3.)).abs() < 1e-9);
+    let front_face = debug_colour(&ray, &scene, &DebugView::FrontFace);
+    assert_eq!((front_face.x, front_face.y), (0., 1.));
+
+    // From inside, the sphere is seen from its back
+    let inside = Ray::new(Vec3::zero(), Vec3::new(0., 1., 0.), 0.);
+    let back_face = debug_colour(&inside, &scene, &DebugView::FrontFace);
+    assert_eq!((back_face.x, back_face.y), (1., 0.));
+}
+
+#[test]
+fn rays_missing_the_scene_are_black() {
+    let scene = sphere_scene();
+    let ray = Ray::new(Vec3::new(0., 0., 5.), Vec3::new(0., 1., 0.), 0.);
+    for view in [DebugView::Normal, DebugView::Uv, DebugView::Distance, DebugView::MaterialId, DebugView::FrontFace] {
+        assert_eq!(debug_colour(&ray, &scene, &view).length(), 0.);
+    }
+
+    // Missing rays still cost something to trace
+    let cost = debug_colour(&ray, &scene, &DebugView::TraversalCost { max_steps: 100 });
+    assert!(cost.length() > 0.);
+}
+
+#[test]
+fn ambient_occlusion_depends_on_its_radius() {
+    // A floor with a ceiling one unit above it
+    let material = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+    let scene = scene_of(vec![
+        XZRect::new(-100., 100., -100., 100., 0., Arc::clone(&material)),
+        XZRect::new(-100., 100., -100., 100., 1., material),
+    ]);
+    let ray = Ray::new(Vec3::new(0., 0.5, 0.), Vec3::new(0., -1., 0.), 0.);
+
+    seed_random(3);
+    let occlusion = |radius| {
+        (0..100).map(|_| debug_colour(&ray, &scene, &DebugView::AmbientOcclusion { radius: Some(radius) }).x).sum::<f64>()
+    };
+    assert_eq!(occlusion(0.5), 100.);
+    assert_eq!(occlusion(200.), 0.);
+}
+
+#[test]
+fn hierarchies_need_fewer_traversal_steps() {
+    seed_random(5);
+    let material = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+    let spheres: Vec<Hittable> = (0..500)
+        .map(|_| Sphere::new(Vec3::random(-20., 20.), 0.5, Arc::clone(&material)))
+        .collect();
+    let list = HittableList::from_objects(spheres);
+    let bvh = BVH::build(&list, 0., 1., BVHBuildMethod::SAH);
+
+    let _counting = count_traversal_steps();
+    let steps = |object: &dyn Fn(&Ray)| {
+        reset_traversal_steps();
+        for _ in 0..100 {
+            object(&Ray::new(Vec3::zero(), Vec3::random_unit_vector(), 0.));
+        }
+        traversal_steps()
+    };
+    let list_steps = steps(&|ray| { list.hit(ray, 0.001, INFINITY); });
+    let bvh_steps = steps(&|ray| { bvh.hit(ray, 0.001, INFINITY); });
+    assert_eq!(list_steps, 500 * 100);
+    assert!(bvh_steps < list_steps / 4, "{bvh_steps} steps is not much fewer than {list_steps}");
+}
+
+#[test]
+fn debug_views_render_through_the_usual_pipeline() {
+    seed_random(7);
+    let scene = build_scene("cornell-box", 1., BVHBuildMethod::SAH).unwrap();
+    let settings = RenderSettings {
+        image_width: 16,
+        image_height: 16,
+        samples_per_pixel: 4,
+        max_depth: Some(10),
+        seed: 7,
+        tile_size: 8,
+        integrator: Integrator::Debug(DebugView::Normal),
+        ..Default::default()
+    };
+    let buffer = render(&scene, &settings, &ProgressBar::hidden());
+
+    // Normals map into the unit cube, and the middle of the image sees the inside of the box
+    for i in 0..buffer.radiance.len() {
+        let colour = buffer.pixel(i);
+        assert!([colour.x, colour.y, colour.z].iter().all(|c| (0. ..=1.).contains(c)));
+    }
+    assert!((8 * 16 + 2..8 * 16 + 14).all(|i| buffer.pixel(i).length() > 0.));
+}
@@ -0,0 +1,57 @@
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Renders a small image of the smoky Cornell box with AOVs at a number of samples per pixel
+fn cornell_box(samples_per_pixel: i32) -> RenderBuffer {
+    seed_random(5);
+    let scene = build_scene("cornell-box-smoke", 1., BVHBuildMethod::SAH).unwrap();
+    let settings = RenderSettings {
+        image_width: 48,
+        image_height: 48,
+        samples_per_pixel,
+        max_depth: Some(10),
+        seed: 5,
+        tile_size: 8,
+        aovs: true,
+        ..Default::default()
+    };
+    render(&scene, &settings, &ProgressBar::hidden())
+}
+
+/// Root mean square difference between the tone mapped colours of two renders
+fn rmse(a: &RenderBuffer, b: &RenderBuffer) -> f64 {
+    let display = |c: Vec3| Vec3::new(c.x.clamp(0., 1.), c.y.clamp(0., 1.), c.z.clamp(0., 1.));
+    let sum: f64 = (0..a.radiance.len()).map(|i| (display(a.pixel(i)) - display(b.pixel(i))).length_squared()).sum();
+    (sum / (3 * a.radiance.len()) as f64).sqrt()
+}
+
+#[test]
+fn denoising_reduces_the_error_of_a_noisy_render() {
+    let reference = cornell_box(128);
+    let noisy = cornell_box(4);
+    let denoised = denoise(&noisy, &DenoiseSettings::default()).unwrap();
+
+    let noisy_error = rmse(&noisy, &reference);
+    let denoised_error = rmse(&denoised, &reference);
+    assert!(denoised_error < 0.75 * noisy_error, "{denoised_error} is not much lower than {noisy_error}");
+}
+
+#[test]
+fn denoising_keeps_a_constant_image() {
+    let mut buffer = cornell_box(1);
+    for i in 0..buffer.radiance.len() {
+        buffer.radiance[i] = buffer.weights[i] * Vec3::new(0.25, 0.5, 0.75);
+    }
+    let denoised = denoise(&buffer, &DenoiseSettings::default()).unwrap();
+    for i in 0..buffer.radiance.len() {
+        assert!((denoised.pixel(i) - buffer.pixel(i)).length() < 1e-9);
+    }
+}
+
+#[test]
+fn denoising_needs_aovs() {
+    let mut buffer = cornell_box(1);
+    buffer.aovs = None;
+    assert!(denoise(&buffer, &DenoiseSettings::default()).is_err());
+}
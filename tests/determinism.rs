@@ -0,0 +1,73 @@
+use image::Rgb;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Builds a small version of the final scene with a given seed, along with its render settings
+fn scene_with(seed: u64, samples_per_pixel: i32, tile_size: usize) -> (Scene, RenderSettings) {
+    seed_random(seed);
+    let scene = build_scene("the-next-week", 1., BVHBuildMethod::RandomAxis).unwrap();
+    let settings = RenderSettings {
+        image_width: 24,
+        image_height: 24,
+        samples_per_pixel,
+        max_depth: Some(10),
+        seed,
+        tile_size,
+        ..Default::default()
+    };
+    (scene, settings)
+}
+
+/// Builds and renders a small version of the final scene with a given seed and thread count
+fn render_with(seed: u64, threads: usize) -> Vec<Rgb<u8>> {
+    let (scene, settings) = scene_with(seed, 4, 16);
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    pool.install(|| render(&scene, &settings, &ProgressBar::hidden()).to_rgb())
+}
+
+#[test]
+fn renders_are_reproducible_across_thread_counts() {
+    let single_threaded = render_with(42, 1);
+    assert_eq!(single_threaded, render_with(42, 1));
+    assert_eq!(single_threaded, render_with(42, 4));
+}
+
+#[test]
+fn different_seeds_give_different_renders() {
+    assert_ne!(render_with(1, 2), render_with(2, 2));
+}
+
+#[test]
+fn passes_and_tiles_do_not_change_the_result() {
+    let (scene, settings) = scene_with(7, 5, 16);
+    let single = render(&scene, &settings, &ProgressBar::hidden());
+
+    // Renders two samples per pixel first, then continues to five with a different tile size
+    let (scene, mut settings) = scene_with(7, 2, 5);
+    let mut buffer = RenderBuffer::new(settings.image_width, settings.image_height);
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+    settings.samples_per_pixel = 5;
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+
+    assert_eq!(single.samples, buffer.samples);
+    assert!(single.radiance.iter().zip(&buffer.radiance).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z));
+}
+
+#[test]
+fn splatting_does_not_depend_on_passes_or_tiles() {
+    // Wide filters splat samples across tile edges, which mustn't change the order they're summed in
+    let (scene, mut settings) = scene_with(11, 5, 16);
+    settings.filter = Filter::new(FilterShape::Lanczos);
+    let single = render(&scene, &settings, &ProgressBar::hidden());
+
+    let (scene, mut settings) = scene_with(11, 3, 7);
+    settings.filter = Filter::new(FilterShape::Lanczos);
+    let mut buffer = RenderBuffer::new(settings.image_width, settings.image_height);
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+    settings.samples_per_pixel = 5;
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+
+    assert_eq!(single.weights, buffer.weights);
+    assert!(single.radiance.iter().zip(&buffer.radiance).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z));
+}
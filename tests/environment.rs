@@ -0,0 +1,224 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+use image::Rgb;
+use image::codecs::hdr::HdrEncoder;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+
+/// Sky of a constant colour with a small, very bright sun above the horizon
+fn sunlit_sky(rotation: f64) -> Environment {
+    let (width, height) = (32, 16);
+    let mut pixels = vec![Vec3::new(0.3, 0.4, 0.6); width * height];
+    pixels[4 * width + 10] = Vec3::new(4000., 3600., 3000.);
+    Environment::new(width, height, pixels, rotation, 1.)
+}
+
+/// Diffuse sphere resting on a ground sphere, lit only by a sunlit sky
+fn sunlit_scene() -> Scene {
+    let mut world = HittableList::new();
+    let grey = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&grey)));
+    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., grey));
+    let camera = Camera::new(
+        Vec3::new(6., 2., 3.), Vec3::new(0., 1., 0.), Vec3::new(0., 1., 0.), 30., 0., 10., 1., 2., 0., 1.
+    );
+    Scene::new(world, camera, Background::Environment(Arc::new(sunlit_sky(0.))))
+}
+
+/// Diffuse sphere on the ground under a lamp, in an evenly lit sky
+fn lamp_lit_scene() -> Scene {
+    let mut world = HittableList::new();
+    let grey = Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)));
+    world.add(Sphere::new(Vec3::new(0., -1000., 0.), 1000., Arc::clone(&grey)));
+    world.add(Sphere::new(Vec3::new(0., 1., 0.), 1., grey));
+    world.add(XZRect::new(-1., 1., -1., 1., 3., Arc::new(DiffuseLight::from_colour(8., 8., 8.))));
+    let camera = Camera::new(
+        Vec3::new(6., 2., 3.), Vec3::new(0., 1., 0.), Vec3::new(0., 1., 0.), 30., 0., 10., 1., 2., 0., 1.
+    );
+    let sky = Environment::new(8, 4, vec![Vec3::new(0.3, 0.4, 0.6); 32], 0., 1.);
+    Scene::new(world, camera, Background::Environment(Arc::new(sky)))
+}
+
+/// Renders a small image of a scene, with or without sampling its lights directly
+fn render_scene(scene: &Scene, samples_per_pixel: i32, light_sampling: bool, seed: u64) -> RenderBuffer {
+    render_with(scene, samples_per_pixel, light_sampling, seed, Integrator::Path)
+}
+
+/// Renders a small image of a scene with a given integrator
+fn render_with(
+    scene: &Scene, samples_per_pixel: i32, light_sampling: bool, seed: u64, integrator: Integrator
+) -> RenderBuffer {
+    let settings = RenderSettings {
+        image_width: 12,
+        image_height: 12,
+        samples_per_pixel,
+        max_depth: Some(6),
+        seed,
+        tile_size: 8,
+        light_sampling,
+        integrator,
+        ..Default::default()
+    };
+    render(scene, &settings, &ProgressBar::hidden())
+}
+
+/// Root mean square difference between the radiance of two renders
+fn rmse(a: &RenderBuffer, b: &RenderBuffer) -> f64 {
+    let sum: f64 = (0..a.radiance.len()).map(|i| (a.pixel(i) - b.pixel(i)).length_squared()).sum();
+    (sum / (3 * a.radiance.len()) as f64).sqrt()
+}
+
+/// Mean radiance of every channel of every pixel of a render
+fn mean(buffer: &RenderBuffer) -> f64 {
+    let sum: f64 = (0..buffer.radiance.len()).map(|i| {
+        let colour = buffer.pixel(i);
+        colour.x + colour.y + colour.z
+    }).sum();
+    sum / (3 * buffer.radiance.len()) as f64
+}
+
+/// Writes a Radiance HDR image whose top half is twice as bright as its bottom half
+fn write_hdr(path: &Path) {
+    let (width, height) = (8, 4);
+    let pixels: Vec<Rgb<f32>> = (0..width * height)
+        .map(|i| if i < width * height / 2 { Rgb([2., 4., 6.]) } else { Rgb([1., 2., 3.]) })
+        .collect();
+    let file = fs::File::create(path).unwrap();
+    HdrEncoder::new(BufWriter::new(file)).encode(&pixels, width, height).unwrap();
+}
+
+#[test]
+fn directions_look_up_the_equirectangular_image() {
+    // Columns run from -x through +z, +x and -z, and rows from straight up to straight down
+    let pixels = (0..8).map(|i| Vec3::new(i as f64, 0., 0.)).collect();
+    let environment = Environment::new(4, 2, pixels, 0., 2.);
+    let value = |direction: Vec3| environment.value(&direction).x;
+    assert_eq!(value(Vec3::new(-1., 0.5, 0.1)), 0.);
+    assert_eq!(value(Vec3::new(0., 0.5, 1.)), 2.);
+    assert_eq!(value(Vec3::new(1., 0.5, 0.)), 4.);
+    assert_eq!(value(Vec3::new(0., 0.5, -1.)), 6.);
+    assert_eq!(value(Vec3::new(1., -0.5, 0.)), 12.);
+
+    // Turning the map by a quarter turn moves every column round by one
+    let pixels = (0..8).map(|i| Vec3::new(i as f64, 0., 0.)).collect();
+    let rotated = Environment::new(4, 2, pixels, 90., 1.);
+    assert_eq!(rotated.value(&Vec3::new(0., 0.5, 1.)).x, 0.);
+    assert_eq!(rotated.value(&Vec3::new(1., 0.5, 0.)).x, 1.);
+    assert_eq!(rotated.value(&Vec3::new(0., 0.5, -1.)).x, 2.);
+
+    // Plain backgrounds are the same in every direction
+    let background = Background::from(Vec3::new(0.1, 0.2, 0.3));
+    assert!((background.value(&Vec3::new(0., -1., 0.)) - Vec3::new(0.1, 0.2, 0.3)).near_zero());
+}
+
+#[test]
+fn sampling_density_integrates_to_one() {
+    // A fine grid of directions over the sphere sums the density times each cell's solid angle
+    let (rows, columns) = (512, 1024);
+    for rotation in [0., 75.] {
+        let environment = sunlit_sky(rotation);
+        let mut integral = 0.;
+        for row in 0..rows {
+            let theta = PI * (row as f64 + 0.5) / rows as f64;
+            for column in 0..columns {
+                let phi = 2. * PI * (column as f64 + 0.5) / columns as f64;
+                let direction = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                integral += environment.pdf(&direction) * theta.sin() * (PI / rows as f64) * (2. * PI / columns as f64);
+            }
+        }
+        assert!((integral - 1.).abs() < 0.01, "density integrates to {integral}");
+    }
+}
+
+#[test]
+fn sampling_favours_bright_pixels() {
+    seed_random(41);
+    let environment = sunlit_sky(30.);
+    let sun = environment.value(&environment.sample());
+    let trials = 10_000;
+    let sunny = (0..trials).filter(|_| environment.value(&environment.sample()).x > 1000.).count();
+    assert!(sunny as f64 > 0.95 * trials as f64, "only {sunny} of {trials} samples found the sun");
+    assert!(sun.x > 1000.);
+
+    // Sampled directions are unit vectors which the density covers
+    for _ in 0..100 {
+        let direction = environment.sample();
+        assert!(environment.pdf(&direction) > 0.);
+        assert!((direction.length() - 1.).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn sampling_the_environment_converges_faster() {
+    // Both estimators converge to the same image, but hitting the sun by chance is rare
+    let scene = sunlit_scene();
+    let reference = render_scene(&scene, 1024, true, 5);
+    let sampled = render_scene(&scene, 32, true, 6);
+    let unsampled = render_scene(&scene, 32, false, 6);
+    let (sampled_error, unsampled_error) = (rmse(&sampled, &reference), rmse(&unsampled, &reference));
+    assert!(sampled_error < 0.5 * unsampled_error, "{sampled_error} isn't well below {unsampled_error}");
+
+    let unsampled = render_scene(&scene, 4096, false, 7);
+    let (expected, found) = (mean(&reference), mean(&unsampled));
+    assert!((expected - found).abs() < 0.05 * expected, "{found} differs from {expected}");
+}
+
+#[test]
+fn light_subpaths_start_from_surfaces_under_an_environment() {
+    // The sky is sampled directly by the path tracer, but has no surface to start light subpaths from
+    let scene = lamp_lit_scene();
+    assert_eq!(scene.lights.objects.len(), 2);
+    assert_eq!(scene.surface_lights.objects.len(), 1);
+    assert!(matches!(scene.surface_lights.objects[0].as_ref(), Hittable::XZRect(_)));
+
+    let reference = render_scene(&scene, 1024, true, 5);
+    let bidirectional = render_with(&scene, 256, true, 6, Integrator::Bidirectional);
+    let (expected, found) = (mean(&reference), mean(&bidirectional));
+    assert!((expected - found).abs() < 0.03 * expected, "{found} differs from {expected}");
+}
+
+#[test]
+fn scene_files_load_environment_maps() {
+    let directory = std::env::temp_dir().join(format!("ray-tracing-test-environment-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    write_hdr(&directory.join("sky.hdr"));
+    let load = |background: &str| {
+        let path = directory.join("scene.toml");
+        let camera = "[camera]\nlook_from = [0.0, 0.0, 1.0]\nlook_at = [0.0, 0.0, 0.0]\nvertical_fov = 40.0\n";
+        fs::write(&path, format!("background = {background}\n{camera}")).unwrap();
+        load_scene(&path, 1., BVHBuildMethod::SAH)
+    };
+
+    // Values are kept beyond the range of 8-bit colours, and scaled by the intensity
+    let scene = load(r#"{ environment = "sky.hdr", rotation = 45.0, intensity = 2.0 }"#).unwrap();
+    assert!(matches!(scene.background, Background::Environment(_)));
+    assert!((scene.background.value(&Vec3::new(0., 1., 0.)) - Vec3::new(4., 8., 12.)).near_zero());
+    assert!((scene.background.value(&Vec3::new(0., -1., 0.)) - Vec3::new(2., 4., 6.)).near_zero());
+    assert_eq!(scene.lights.objects.len(), 1);
+
+    let scene = load("[0.5, 0.5, 0.5]").unwrap();
+    assert!(matches!(scene.background, Background::Colour(_)));
+    assert!(scene.lights.objects.is_empty());
+
+    // Missing images, other formats and unknown fields are reported
+    let missing = load(r#"{ environment = "missing.hdr" }"#).err().unwrap();
+    assert!(missing.contains("missing.hdr"), "{missing}");
+    let unsupported = load(r#"{ environment = "sky.png" }"#).err().unwrap();
+    assert!(unsupported.contains("Radiance HDR or OpenEXR"), "{unsupported}");
+    assert!(load(r#"{ environment = "sky.hdr", brightness = 2.0 }"#).is_err());
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn every_integrator_sees_the_environment() {
+    let scene = load_scene(Path::new("scenes/environment.toml"), 1., BVHBuildMethod::SAH).unwrap();
+    let photons = PhotonSettings { photons: 1000, radius: None };
+    for integrator in [Integrator::Path, Integrator::Bidirectional, Integrator::PhotonMapping(photons), Integrator::Spectral] {
+        let buffer = render_with(&scene, 4, true, 3, integrator);
+        assert!((0..buffer.radiance.len()).all(|i| buffer.pixel(i).x.is_finite()));
+        assert!(mean(&buffer) > 0.1, "{integrator:?} didn't see the sky");
+    }
+}
@@ -0,0 +1,27 @@
+use ray_tracing::*;
+
+const SHAPES: [FilterShape; 5] = [
+    FilterShape::Box, FilterShape::Tent, FilterShape::Gaussian, FilterShape::Mitchell, FilterShape::Lanczos
+];
+
+#[test]
+fn filters_vanish_outside_their_radius() {
+    for shape in SHAPES {
+        for radius in [0.5, 1., 2.5] {
+            let filter = Filter { shape, radius };
+            assert!(filter.evaluate(0., 0.) > 0., "{shape:?} has no weight at its centre");
+            for offset in [radius, radius + 0.3, -radius - 1.] {
+                assert_eq!(filter.evaluate(offset, 0.), 0., "{shape:?} has weight at {offset}");
+                assert_eq!(filter.evaluate(0., offset), 0., "{shape:?} has weight at {offset}");
+            }
+        }
+    }
+}
+
+#[test]
+fn filters_only_reach_pixels_within_their_radius() {
+    assert_eq!(Filter::new(FilterShape::Box).pixel_reach(), 0);
+    assert_eq!(Filter::new(FilterShape::Tent).pixel_reach(), 1);
+    assert_eq!(Filter::new(FilterShape::Lanczos).pixel_reach(), 3);
+    assert_eq!(Filter { shape: FilterShape::Gaussian, radius: 0.2 }.pixel_reach(), 0);
+}
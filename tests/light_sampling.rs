@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Renders a small image of a built-in scene, with or without sampling its lights directly
+fn render_scene(name: &str, samples_per_pixel: i32, light_sampling: bool) -> RenderBuffer {
+    seed_random(17);
+    let scene = build_scene(name, 1., BVHBuildMethod::SAH).unwrap();
+    let settings = RenderSettings {
+        image_width: 12,
+        image_height: 12,
+        samples_per_pixel,
+        max_depth: Some(6),
+        seed: 17,
+        tile_size: 8,
+        light_sampling,
+        ..Default::default()
+    };
+    render(&scene, &settings, &ProgressBar::hidden())
+}
+
+/// Root mean square difference between the radiance of two renders
+fn rmse(a: &RenderBuffer, b: &RenderBuffer) -> f64 {
+    let sum: f64 = (0..a.radiance.len()).map(|i| (a.pixel(i) - b.pixel(i)).length_squared()).sum();
+    (sum / (3 * a.radiance.len()) as f64).sqrt()
+}
+
+/// Mean radiance of every channel of every pixel of a render
+fn mean(buffer: &RenderBuffer) -> f64 {
+    let sum: f64 = (0..buffer.radiance.len()).map(|i| {
+        let colour = buffer.pixel(i);
+        colour.x + colour.y + colour.z
+    }).sum();
+    sum / (3 * buffer.radiance.len()) as f64
+}
+
+/// Estimates the integral of a light's density over every direction from an origin, which
+/// should be one
+fn integrate_pdf(light: &Hittable, origin: Vec3) -> f64 {
+    seed_random(3);
+    let n = 500_000;
+    let sum: f64 = (0..n).map(|_| light.pdf_value(&origin, &Vec3::random_unit_vector())).sum();
+    4. * PI * sum / n as f64
+}
+
+#[test]
+fn scenes_collect_their_lights() {
+    let lights = |name: &str| build_scene(name, 1., BVHBuildMethod::SAH).unwrap().lights.objects.len();
+    assert_eq!(lights("cornell-box"), 1);
+    assert_eq!(lights("simple-light"), 2);
+    assert_eq!(lights("the-next-week"), 1);
+    assert_eq!(lights("in-a-weekend"), 0);
+}
+
+#[test]
+fn light_densities_integrate_to_one() {
+    let light = Arc::new(DiffuseLight::from_colour(1., 1., 1.));
+    let sphere = Sphere::new(Vec3::new(1., 2., 0.5), 0.75, Arc::clone(&light));
+    let rect = XZRect::new(-1., 2., -0.5, 1., 1., Arc::clone(&light));
+    let moved = Translate::new(Arc::new(RotateY::new(Arc::new(YZRect::new(0., 1., 0., 2., 0., light)), 30.)), Vec3::new(2., 0., 1.));
+
+    let origin = Vec3::new(0.2, 0.1, -0.3);
+    for object in [&sphere, &rect, &moved] {
+        let integral = integrate_pdf(object, origin);
+        assert!((integral - 1.).abs() < 0.05, "{integral} is not close to 1");
+
+        // Every direction chosen towards the light has a density
+        seed_random(5);
+        assert!((0..100).all(|_| object.pdf_value(&origin, &object.random(&origin)) > 0.));
+    }
+}
+
+#[test]
+fn light_sampling_converges_to_the_same_image() {
+    let reference = render_scene("simple-light", 1024, false);
+    let sampled = render_scene("simple-light", 256, true);
+    assert!((mean(&reference) - mean(&sampled)).abs() < 0.02 * mean(&reference));
+}
+
+#[test]
+fn light_sampling_converges_near_lights() {
+    // The ceiling around the Cornell box's light sees it at grazing angles, where multiple
+    // importance sampling leaves it to the scattered rays
+    let reference = render_scene("cornell-box", 1024, false);
+    let sampled = render_scene("cornell-box", 256, true);
+    assert!((mean(&reference) - mean(&sampled)).abs() < 0.02 * mean(&reference));
+}
+
+#[test]
+fn light_sampling_reduces_noise() {
+    let reference = render_scene("cornell-box", 1024, false);
+    let without = render_scene("cornell-box", 16, false);
+    let with = render_scene("cornell-box", 16, true);
+    assert!(rmse(&with, &reference) < rmse(&without, &reference));
+}
+
+#[test]
+fn lobes_weigh_samples_by_their_albedo() {
+    seed_random(9);
+    let albedo = Vec3::new(0.2, 0.5, 0.8);
+    let lobes = [
+        Lobe::Cosine { albedo, normal: Vec3::new(0., 1., 0.) },
+        Lobe::Uniform { albedo },
+    ];
+    for lobe in lobes {
+        for _ in 0..100 {
+            // Sampled directions have a density and a weight of exactly the albedo
+            let direction = lobe.sample();
+            let pdf = lobe.pdf(&direction);
+            assert!(pdf > 0.);
+            assert!((lobe.eval(&direction) / pdf - albedo).length() < 1e-9);
+        }
+    }
+
+    // Cosine lobes don't scatter light through the surface
+    let lobe = Lobe::Cosine { albedo, normal: Vec3::new(0., 1., 0.) };
+    assert_eq!(lobe.pdf(&Vec3::new(0.3, -1., 0.)), 0.);
+}
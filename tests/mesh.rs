@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+
+/// Bumpy square grid of triangles with vertex normals and texture coordinates, whose
+/// triangles alternate between two materials
+struct Grid {
+    positions: Vec<[f32; 3]>,
+    normals: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    indices: Vec<[u32; 3]>,
+    material_ids: Vec<u32>,
+    materials: Vec<Arc<Material>>,
+}
+
+impl Grid {
+    fn new(size: u32, materials: Vec<Arc<Material>>) -> Grid {
+        seed_random(11);
+        let (mut positions, mut normals, mut uvs) = (vec![], vec![], vec![]);
+        for row in 0..=size {
+            for column in 0..=size {
+                positions.push([column as f32, random_range(-0.3, 0.3) as f32, row as f32]);
+                let normal = Vec3::new(random_range(-0.2, 0.2), 1., random_range(-0.2, 0.2)).unit();
+                normals.push([normal.x as f32, normal.y as f32, normal.z as f32]);
+                uvs.push([column as f32 / size as f32, row as f32 / size as f32]);
+            }
+        }
+
+        let mut indices = vec![];
+        for row in 0..size {
+            for column in 0..size {
+                let corner = row * (size + 1) + column;
+                indices.push([corner, corner + size + 1, corner + 1]);
+                indices.push([corner + 1, corner + size + 1, corner + size + 2]);
+            }
+        }
+        let material_ids = (0..indices.len() as u32).map(|t| t % materials.len() as u32).collect();
+
+        Grid { positions, normals, uvs, indices, material_ids, materials }
+    }
+
+    /// Builds the grid as a mesh
+    fn mesh(&self) -> Hittable {
+        Mesh::new(
+            self.positions.clone(), self.normals.clone(), self.uvs.clone(),
+            self.indices.clone(), self.material_ids.clone(), self.materials.clone()
+        )
+    }
+
+    /// Builds the grid as a list of separate triangles
+    fn list(&self) -> HittableList {
+        let position = |i: u32| Vec3::from(self.positions[i as usize].map(f64::from));
+        let normal = |i: u32| Vec3::from(self.normals[i as usize].map(f64::from));
+        let uv = |i: u32| (self.uvs[i as usize][0] as f64, self.uvs[i as usize][1] as f64);
+
+        let mut list = HittableList::new();
+        for (t, &[i0, i1, i2]) in self.indices.iter().enumerate() {
+            list.add(Triangle::with_attributes(
+                [position(i0), position(i1), position(i2)], Some([normal(i0), normal(i1), normal(i2)]),
+                Some([uv(i0), uv(i1), uv(i2)]), Arc::clone(&self.materials[self.material_ids[t] as usize])
+            ));
+        }
+        list
+    }
+}
+
+#[test]
+fn meshes_are_hit_like_their_triangles() {
+    let grid = Grid::new(12, vec![
+        Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))),
+        Arc::new(Metal::new(Vec3::new(0.8, 0.8, 0.8), 0.)),
+    ]);
+    let mesh = grid.mesh();
+    let list = grid.list();
+
+    // Rays from above and below the grid, and grazing along it, see the same closest hit
+    seed_random(23);
+    let mut hits = 0;
+    for _ in 0..5000 {
+        let origin = Vec3::new(random_range(-2., 14.), random_range(-3., 3.), random_range(-2., 14.));
+        let target = Vec3::new(random_range(0., 12.), random_range(-0.3, 0.3), random_range(0., 12.));
+        let ray = Ray::new(origin, target - origin, 0.);
+        match (mesh.hit(&ray, 0.001, INFINITY), list.hit(&ray, 0.001, INFINITY)) {
+            (Some(a), Some(b)) => {
+                assert!((a.t - b.t).abs() < 1e-9, "{} differs from {}", a.t, b.t);
+                assert!((a.normal - b.normal).near_zero() && a.front_face == b.front_face);
+                assert!((a.u - b.u).abs() < 1e-9 && (a.v - b.v).abs() < 1e-9);
+                assert!(Arc::ptr_eq(&a.material, &b.material));
+                hits += 1;
+            },
+            (None, None) => {},
+            (a, b) => panic!("mesh hit {:?} but triangles hit {:?}", a.map(|h| h.t), b.map(|h| h.t)),
+        }
+    }
+    assert!(hits > 2500, "only {hits} rays hit the grid");
+
+    // The mesh's box surrounds every triangle's
+    let bounds = mesh.bounding_box(0., 1.).unwrap();
+    let expected = list.bounding_box(0., 1.).unwrap();
+    assert!((bounds.minimum - expected.minimum).near_zero() && (bounds.maximum - expected.maximum).near_zero());
+}
+
+#[test]
+fn emitting_triangles_are_sampled_as_lights() {
+    // Partly emitting meshes add each emitting triangle as its own light
+    let lamp = Arc::new(DiffuseLight::from_colour(4., 4., 4.));
+    let grid = Grid::new(3, vec![Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5))), Arc::clone(&lamp)]);
+    let mut world = HittableList::new();
+    world.add(grid.mesh());
+    let mut lights = vec![];
+    world.collect_lights(&mut lights);
+    assert!(!world.objects[0].is_light());
+    assert_eq!(lights.len(), 9);
+    assert!(lights.iter().all(|light| light.is_light() && light.area() > 0.));
+
+    // Each light lies on the mesh where the mesh itself is lit
+    seed_random(7);
+    for light in &lights {
+        let sample = light.sample_surface().unwrap();
+        let above = sample.point + 0.01 * sample.normal;
+        let hit = world.hit(&Ray::new(above, -sample.normal, 0.), 0.001, INFINITY).unwrap();
+        assert!((hit.point - sample.point).length() < 1e-6);
+        assert!(matches!(hit.material.as_ref(), Material::DiffuseLight(_)));
+    }
+}
+
+#[test]
+fn emitting_meshes_are_sampled_by_area() {
+    // A square lamp made of two triangles of different shapes is sampled as one light
+    let lamp = Arc::new(DiffuseLight::from_colour(4., 4., 4.));
+    let positions = vec![[0., 2., 0.], [1., 2., 0.], [1., 2., 3.], [0., 2., 3.]];
+    let mesh = Mesh::from_buffers(positions, vec![[0, 1, 2], [0, 2, 3]], lamp);
+    let mut lights = vec![];
+    HittableList { objects: vec![Arc::new(mesh)] }.collect_lights(&mut lights);
+    assert_eq!(lights.len(), 1);
+    let light = &lights[0];
+    assert!(light.is_light() && (light.area() - 3.).abs() < 1e-9);
+
+    // Points are spread evenly over the square, and every direction towards it has a density
+    seed_random(9);
+    let n = 20_000;
+    let points: Vec<Vec3> = (0..n).map(|_| light.sample_surface().unwrap().point).collect();
+    assert!(points.iter().all(|p| (p.y - 2.).abs() < 1e-6 && (0. ..=1.).contains(&p.x) && (0. ..=3.).contains(&p.z)));
+    let far_third = points.iter().filter(|p| p.z > 2.).count() as f64 / n as f64;
+    assert!((far_third - 1. / 3.).abs() < 0.02, "{far_third} of points are in the far third");
+
+    let origin = Vec3::new(0.3, 0.5, 1.2);
+    assert!((0..100).all(|_| light.pdf_value(&origin, &light.random(&origin)) > 0.));
+
+    // The density over directions integrates to one
+    seed_random(3);
+    let samples = 500_000;
+    let sum: f64 = (0..samples).map(|_| light.pdf_value(&origin, &Vec3::random_unit_vector())).sum();
+    let integral = 4. * PI * sum / samples as f64;
+    assert!((integral - 1.).abs() < 0.05, "{integral} is not close to 1");
+}
+
+#[test]
+fn closed_emitting_meshes_are_as_bright_sampled_or_not() {
+    // A glowing cube above a floor, whose far faces are sampled as often as its near ones
+    let lamp = Arc::new(DiffuseLight::from_colour(4., 4., 4.));
+    let positions = vec![
+        [-0.5, 1., -0.5], [0.5, 1., -0.5], [0.5, 1., 0.5], [-0.5, 1., 0.5],
+        [-0.5, 2., -0.5], [0.5, 2., -0.5], [0.5, 2., 0.5], [-0.5, 2., 0.5],
+    ];
+    let indices = vec![
+        [0, 1, 2], [0, 2, 3], [4, 6, 5], [4, 7, 6], [0, 4, 5], [0, 5, 1],
+        [1, 5, 6], [1, 6, 2], [2, 6, 7], [2, 7, 3], [3, 7, 4], [3, 4, 0],
+    ];
+    let mut world = HittableList::new();
+    world.add(Mesh::from_buffers(positions, indices, lamp));
+    world.add(XZRect::new(-5., 5., -5., 5., 0., Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))));
+    let camera = Camera::new(
+        Vec3::new(0., 4., 6.), Vec3::new(0., 0., 0.), Vec3::new(0., 1., 0.), 40., 0., 10., 1., 2., 0., 1.
+    );
+    let scene = Scene::new(world, camera, Vec3::zero());
+    assert_eq!(scene.lights.objects.len(), 1);
+
+    let mean = |light_sampling, samples_per_pixel| {
+        let settings = RenderSettings {
+            image_width: 12,
+            image_height: 12,
+            samples_per_pixel,
+            max_depth: Some(4),
+            seed: 3,
+            light_sampling,
+            ..Default::default()
+        };
+        let buffer = render(&scene, &settings, &ProgressBar::hidden());
+        let sum: f64 = (0..buffer.radiance.len()).map(|i| buffer.pixel(i).x).sum();
+        sum / buffer.radiance.len() as f64
+    };
+    let (sampled, unsampled) = (mean(true, 256), mean(false, 1024));
+    assert!((sampled - unsampled).abs() < 0.03 * unsampled, "{sampled} differs from {unsampled}");
+}
+
+#[test]
+fn smooth_shaded_lights_emit_from_their_faces() {
+    // Vertex normals leaning well away from the face only change how the light is shaded
+    let lamp = Arc::new(DiffuseLight::from_colour(4., 4., 4.));
+    let vertices = [Vec3::new(0., 2., 0.), Vec3::new(0., 2., 1.), Vec3::new(1., 2., 0.)];
+    let leaning = [Vec3::new(0.8, 0.6, 0.), Vec3::new(0., 0.6, 0.8), Vec3::new(-0.8, 0.6, 0.)];
+    let triangle = Triangle::with_attributes(vertices, Some(leaning), None, Arc::clone(&lamp));
+    let to_buffer = |v: Vec3| [v.x as f32, v.y as f32, v.z as f32];
+    let mesh = Mesh::new(
+        vertices.map(to_buffer).to_vec(), leaning.map(|n| to_buffer(n.unit())).to_vec(), vec![],
+        vec![[0, 1, 2]], vec![], vec![lamp]
+    );
+
+    let origin = Vec3::new(0.3, 0.5, 0.2);
+    for light in [&triangle, &mesh] {
+        seed_random(13);
+        let face = Vec3::new(0., 1., 0.);
+        assert!((0..100).all(|_| (light.sample_surface().unwrap().normal - face).near_zero()));
+
+        // The density over directions integrates to one
+        let samples = 500_000;
+        let sum: f64 = (0..samples).map(|_| light.pdf_value(&origin, &Vec3::random_unit_vector())).sum();
+        let integral = 4. * PI * sum / samples as f64;
+        assert!((integral - 1.).abs() < 0.05, "{integral} is not close to 1");
+    }
+}
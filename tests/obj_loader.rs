@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use ray_tracing::*;
+
+/// Grey material given to faces without one of their own
+fn default_material() -> Arc<Material> {
+    Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))
+}
+
+/// Writes files into a fresh temporary directory named after a test, returning its path
+fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("ray-tracing-test-obj-{test}-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    for (name, contents) in files {
+        fs::write(directory.join(name), contents).unwrap();
+    }
+    directory
+}
+
+/// Hits an object with a ray pointing straight down at a point above it
+fn hit_from_above(object: &Hittable, x: f64, z: f64) -> Option<HitRecord> {
+    object.hit(&Ray::new(Vec3::new(x, 1000., z), Vec3::new(0., -1., 0.), 0.), 0.001, INFINITY)
+}
+
+#[test]
+fn triangles_are_hit_inside_their_edges() {
+    let triangle = Triangle::new(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), default_material());
+    let towards = |x: f64, y: f64| Ray::new(Vec3::new(x, y, 2.), Vec3::new(0., 0., -1.), 0.);
+
+    let hit = triangle.hit(&towards(0.25, 0.25), 0.001, INFINITY).unwrap();
+    assert!((hit.t - 2.).abs() < 1e-12);
+    assert!((hit.point - Vec3::new(0.25, 0.25, 0.)).near_zero());
+    assert!((hit.normal - Vec3::new(0., 0., 1.)).near_zero());
+    assert!(hit.front_face);
+
+    // Rays outside the edges, parallel to the face or beyond the range miss
+    assert!(triangle.hit(&towards(0.6, 0.6), 0.001, INFINITY).is_none());
+    assert!(triangle.hit(&towards(-0.1, 0.5), 0.001, INFINITY).is_none());
+    assert!(triangle.hit(&Ray::new(Vec3::new(-1., 0.2, 0.), Vec3::new(1., 0., 0.), 0.), 0.001, INFINITY).is_none());
+    assert!(triangle.hit(&towards(0.25, 0.25), 0.001, 1.).is_none());
+
+    // Flat triangles still have boxes with some thickness
+    let bounds = triangle.bounding_box(0., 1.).unwrap();
+    assert!(bounds.maximum.z > bounds.minimum.z);
+}
+
+#[test]
+fn vertex_attributes_are_interpolated() {
+    let normals = [Vec3::new(0., 0., 1.), Vec3::new(1., 0., 1.).unit(), Vec3::new(0., 1., 1.).unit()];
+    let uvs = [(0.1, 0.2), (0.9, 0.2), (0.1, 0.8)];
+    let triangle = Triangle::with_attributes(
+        [Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.)], Some(normals), Some(uvs), default_material()
+    );
+
+    // A hit at barycentric coordinates (0.5, 0.25, 0.25) blends each vertex by that much
+    let hit = triangle.hit(&Ray::new(Vec3::new(0.25, 0.25, 2.), Vec3::new(0., 0., -1.), 0.), 0.001, INFINITY).unwrap();
+    let normal = (0.5 * normals[0] + 0.25 * normals[1] + 0.25 * normals[2]).unit();
+    assert!((hit.normal - normal).near_zero());
+    assert!((hit.u - 0.3).abs() < 1e-12 && (hit.v - 0.35).abs() < 1e-12);
+
+    // Without texture coordinates, the barycentric coordinates of the second and third vertex are used
+    let plain = Triangle::new(Vec3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), default_material());
+    let hit = plain.hit(&Ray::new(Vec3::new(0.2, 0.3, 2.), Vec3::new(0., 0., -1.), 0.), 0.001, INFINITY).unwrap();
+    assert!((hit.u - 0.2).abs() < 1e-12 && (hit.v - 0.3).abs() < 1e-12);
+}
+
+#[test]
+fn obj_files_load_with_their_materials() {
+    // The pyramid's four sides use its gold metal and its base, a quad split in two, its stone
+    let list = load_obj_list(Path::new("scenes/models/pyramid.obj"), default_material()).unwrap();
+    assert_eq!(list.objects.len(), 6);
+    let mut materials = vec![];
+    list.collect_materials(&mut materials);
+    assert_eq!(materials.iter().filter(|m| matches!(m.as_ref(), Material::Metal(_))).count(), 4);
+    assert_eq!(materials.iter().filter(|m| matches!(m.as_ref(), Material::Lambertian(_))).count(), 2);
+
+    // The sides are hit from above and the base from below
+    let bvh = load_obj(Path::new("scenes/models/pyramid.obj"), default_material(), BVHBuildMethod::SAH).unwrap();
+    let hit = hit_from_above(&bvh, 0., 0.).unwrap();
+    assert!((hit.point.y - 250.).abs() < 1e-6);
+    let below = bvh.hit(&Ray::new(Vec3::new(10., -10., 10.), Vec3::new(0., 1., 0.), 0.), 0.001, INFINITY).unwrap();
+    assert!(below.point.y.abs() < 1e-9 && matches!(below.material.as_ref(), Material::Lambertian(_)));
+}
+
+#[test]
+fn mtl_materials_map_onto_the_closest_material() {
+    let obj = "mtllib materials.mtl\nv 0 0 0\nv 1 0 0\nv 0 0 1\n\
+        usemtl lamp\nf 1 2 3\nusemtl glass\nf 1 2 3\nusemtl chrome\nf 1 2 3\nusemtl plain\nf 1 2 3\n";
+    let mtl = "newmtl lamp\nKe 4 4 4\n\nnewmtl glass\nd 0.5\nNi 1.3\n\n\
+        newmtl chrome\nKs 0.9 0.9 0.9\nNs 1000\nillum 3\n\nnewmtl plain\nKd 0.2 0.4 0.6\n";
+    let directory = write_files("materials", &[("model.obj", obj), ("materials.mtl", mtl)]);
+    let list = load_obj_list(&directory.join("model.obj"), default_material()).unwrap();
+    let mut materials = vec![];
+    list.collect_materials(&mut materials);
+    assert!(matches!(materials[0].as_ref(), Material::DiffuseLight(_)));
+    assert!(matches!(materials[1].as_ref(), Material::Dielectric(_)));
+    assert!(matches!(materials[2].as_ref(), Material::Metal(_)));
+    assert!(matches!(materials[3].as_ref(), Material::Lambertian(_)));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn broken_obj_files_are_reported() {
+    let directory = write_files("broken", &[
+        ("missing-mtl.obj", "mtllib missing.mtl\nv 0 0 0\nv 1 0 0\nv 0 0 1\nusemtl red\nf 1 2 3\n"),
+        ("no-mtl.obj", "v 0 0 0\nv 1 0 0\nv 0 0 1\nf 1 2 3\n"),
+        ("empty.obj", "v 0 0 0\nv 1 0 0\n"),
+        ("missing-texture.obj", "mtllib textured.mtl\nv 0 0 0\nv 1 0 0\nv 0 0 1\nusemtl painted\nf 1 2 3\n"),
+        ("textured.mtl", "newmtl painted\nKd 0.5 0.5 0.5\nmap_Kd missing.png\n"),
+    ]);
+
+    // MTL files which are named but can't be loaded are errors rather than warnings
+    let error = load_obj_list(&directory.join("missing-mtl.obj"), default_material()).err().unwrap();
+    assert!(error.contains("could not load materials"), "{error}");
+    assert!(load_obj_mesh(&directory.join("missing-mtl.obj"), default_material()).is_err());
+
+    // So are textures which can't be loaded, naming the material using them
+    for loaded in [
+        load_obj_list(&directory.join("missing-texture.obj"), default_material()).map(|_| ()),
+        load_obj_mesh(&directory.join("missing-texture.obj"), default_material()).map(|_| ()),
+    ] {
+        let error = loaded.err().unwrap();
+        assert!(error.contains("material 'painted'") && error.contains("missing.png"), "{error}");
+    }
+
+    // Models without an MTL file use the default material
+    let list = load_obj_list(&directory.join("no-mtl.obj"), default_material()).unwrap();
+    assert_eq!(list.objects.len(), 1);
+
+    let error = load_obj_list(&directory.join("empty.obj"), default_material()).err().unwrap();
+    assert!(error.contains("contains no triangles"), "{error}");
+    assert!(load_obj_list(&directory.join("absent.obj"), default_material()).is_err());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn emissive_obj_meshes_are_sampled_as_lights() {
+    let directory = write_files("lamp", &[
+        ("lamp.obj", "mtllib lamp.mtl\nv 0 0 0\nv 1 0 0\nv 0 0 1\nv 1 0 1\nusemtl glow\nf 1 2 3\nf 2 4 3\n"),
+        ("lamp.mtl", "newmtl glow\nKe 4 4 4\n"),
+        ("half.obj", "mtllib lamp.mtl\nv 0 0 0\nv 1 0 0\nv 0 0 1\nv 1 0 1\nf 1 2 3\nusemtl glow\nf 2 4 3\n"),
+    ]);
+
+    // The default material isn't a light, but only counts if some face uses it
+    let mesh = load_obj_mesh(&directory.join("lamp.obj"), default_material()).unwrap();
+    assert!(mesh.is_light() && (mesh.area() - 1.).abs() < 1e-6);
+    let mut materials = vec![];
+    mesh.collect_materials(&mut materials);
+    assert_eq!(materials.len(), 1);
+
+    let half = load_obj_mesh(&directory.join("half.obj"), default_material()).unwrap();
+    assert!(!half.is_light());
+    let mut lights = vec![];
+    half.collect_lights(&mut lights);
+    assert_eq!(lights.len(), 1);
+    fs::remove_dir_all(&directory).unwrap();
+}
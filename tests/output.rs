@@ -0,0 +1,55 @@
+use ray_tracing::*;
+
+/// Builds a buffer with a horizontal gradient of radiance from 0 to a maximum value
+fn gradient(width: usize, max: f64) -> RenderBuffer {
+    let mut buffer = RenderBuffer::new(width, 4);
+    for i in 0..width * 4 {
+        let value = max * (i % width) as f64 / (width - 1) as f64;
+        buffer.radiance[i] = Vec3::new(value, 0.5 * value, 0.25 * value);
+        buffer.weights[i] = 1.;
+        buffer.samples[i] = 1;
+    }
+    buffer
+}
+
+#[test]
+fn exr_output_keeps_radiance_above_one() {
+    let buffer = gradient(32, 15.);
+    let path = std::env::temp_dir().join(format!("ray-tracing-test-{}.exr", std::process::id()));
+    save_image(&buffer, &path, &OutputSettings::default()).unwrap();
+    let image = image::open(&path).unwrap().into_rgb32f();
+    std::fs::remove_file(&path).unwrap();
+
+    for (i, pixel) in image.pixels().enumerate() {
+        let expected = buffer.pixel(i);
+        assert_eq!(pixel.0, [expected.x as f32, expected.y as f32, expected.z as f32]);
+    }
+}
+
+#[test]
+fn dithering_preserves_the_average_colour() {
+    // A flat colour between two 8-bit levels is rounded down without dithering
+    let mut buffer = RenderBuffer::new(64, 64);
+    let level = (100.4f64 / 256.).powi(2);
+    buffer.radiance.fill(Vec3::new(level, level, level));
+    buffer.weights.fill(1.);
+    buffer.samples.fill(1);
+
+    let tone_map = ToneMap { transfer: TransferFunction::Gamma2, ..ToneMap::default() };
+    let mean = |image: &image::RgbImage| image.pixels().map(|p| p.0[0] as f64).sum::<f64>() / (64. * 64.);
+    assert_eq!(mean(&buffer.to_image8(&tone_map, false)), 100.);
+
+    // Each level covers the interval above it, so the exact value is half a level higher
+    assert!((mean(&buffer.to_image8(&tone_map, true)) + 0.5 - 100.4).abs() < 0.05);
+}
+
+#[test]
+fn extended_reinhard_maps_the_white_point_to_white() {
+    let tone_map = ToneMap { operator: ToneMapOperator::ExtendedReinhard, white_point: 6., ..ToneMap::default() };
+    let white = tone_map.apply(Vec3::new(6., 6., 6.));
+    assert!((white.x - 1.).abs() < 1e-12 && (white.y - 1.).abs() < 1e-12);
+
+    // Darker colours are compressed but stay below white
+    let grey = tone_map.apply(Vec3::new(1., 1., 1.));
+    assert!(grey.x > 0. && grey.x < 1.);
+}
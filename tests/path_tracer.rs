@@ -0,0 +1,80 @@
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::sampler::seed_random_stream;
+use ray_tracing::scenes::build_scene;
+
+/// Mean radiance of the rays through a grid of points over a scene's image, given a function
+/// tracing each ray. Each ray has its own random number stream, so that two functions
+/// consuming random numbers in the same order see the same numbers
+fn mean_radiance<F: Fn(&Scene, &Ray) -> Vec3>(scene: &Scene, size: usize, samples: u64, colour: F) -> f64 {
+    let mut sum = Vec3::zero();
+    for pixel in 0..size * size {
+        for sample in 0..samples {
+            seed_random_stream(5, pixel as u64, sample);
+            let u = ((pixel % size) as f64 + random_double()) / size as f64;
+            let v = ((pixel / size) as f64 + random_double()) / size as f64;
+            sum += colour(scene, &scene.camera.get_ray(u, v));
+        }
+    }
+    (sum.x + sum.y + sum.z) / (3 * size * size) as f64 / samples as f64
+}
+
+#[test]
+fn capped_paths_match_the_recursive_tracer() {
+    // Without Russian roulette, both tracers take the same random numbers in the same order
+    let scene = build_scene("cornell-box-smoke", 1., BVHBuildMethod::SAH).unwrap();
+    for depth in [1, 2, 5] {
+        for pixel in 0..64 {
+            seed_random_stream(9, pixel, 0);
+            let ray = scene.camera.get_ray(random_double(), random_double());
+            seed_random_stream(9, pixel, 1);
+            let recursive = ray_colour(&ray, &scene.background, &scene.world, &scene.lights, depth);
+            seed_random_stream(9, pixel, 1);
+            let iterative = path_colour(&ray, &scene.background, &scene.world, &scene.lights, depth, Some(depth));
+            assert!((recursive - iterative).length() <= 1e-9 * f64::max(recursive.length(), 1.));
+        }
+    }
+}
+
+#[test]
+fn russian_roulette_converges_to_the_same_image() {
+    // Smoke scatters light many times, so deep paths carry a noticeable amount of it
+    let scene = build_scene("cornell-box-smoke", 1., BVHBuildMethod::SAH).unwrap();
+    let recursive = mean_radiance(&scene, 8, 256, |scene, ray| {
+        ray_colour(ray, &scene.background, &scene.world, &scene.lights, 50)
+    });
+    let roulette = mean_radiance(&scene, 8, 256, |scene, ray| {
+        path_colour(ray, &scene.background, &scene.world, &scene.lights, 3, None)
+    });
+    assert!((recursive - roulette).abs() < 0.02 * recursive, "{roulette} differs from {recursive}");
+}
+
+#[test]
+fn hard_caps_darken_the_image() {
+    let scene = build_scene("cornell-box", 1., BVHBuildMethod::SAH).unwrap();
+    let capped = mean_radiance(&scene, 8, 64, |scene, ray| {
+        path_colour(ray, &scene.background, &scene.world, &scene.lights, 3, Some(2))
+    });
+    let uncapped = mean_radiance(&scene, 8, 64, |scene, ray| {
+        path_colour(ray, &scene.background, &scene.world, &scene.lights, 3, None)
+    });
+    assert!(capped < 0.9 * uncapped, "{capped} is not darker than {uncapped}");
+}
+
+#[test]
+fn renders_without_a_hard_cap_finish() {
+    // Glass loses no light, so only the cap on the chance of surviving ends its paths
+    seed_random(3);
+    let scene = build_scene("in-a-weekend", 1., BVHBuildMethod::SAH).unwrap();
+    let settings = RenderSettings {
+        image_width: 16,
+        image_height: 16,
+        samples_per_pixel: 4,
+        min_depth: 1,
+        seed: 3,
+        tile_size: 8,
+        ..Default::default()
+    };
+    let buffer = render(&scene, &settings, &ProgressBar::hidden());
+    assert!((0..buffer.radiance.len()).all(|i| buffer.pixel(i).length().is_finite()));
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+use std::sync::Arc;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Settings for a small render with a given integrator
+fn settings(size: usize, samples_per_pixel: i32, integrator: Integrator) -> RenderSettings {
+    RenderSettings {
+        image_width: size,
+        image_height: size,
+        samples_per_pixel,
+        max_depth: Some(6),
+        seed: 31,
+        tile_size: 8,
+        integrator,
+        ..Default::default()
+    }
+}
+
+/// Photon mapping with fewer photons than usual, so that tests run quickly
+fn photon_mapping() -> Integrator {
+    Integrator::PhotonMapping(PhotonSettings { photons: 50_000, radius: None })
+}
+
+/// Root mean square difference between the radiance of the lower half of two renders, where
+/// the floor is, clamped to what a display shows
+fn floor_rmse(a: &RenderBuffer, b: &RenderBuffer) -> f64 {
+    let clamp = |c: Vec3| Vec3::new(c.x.min(1.), c.y.min(1.), c.z.min(1.));
+    let floor = a.radiance.len() / 2..a.radiance.len();
+    let sum: f64 = floor.clone().map(|i| (clamp(a.pixel(i)) - clamp(b.pixel(i))).length_squared()).sum();
+    (sum / (3 * floor.len()) as f64).sqrt()
+}
+
+/// Checks that two renders are identical down to the bit
+fn identical(a: &RenderBuffer, b: &RenderBuffer) -> bool {
+    a.samples == b.samples && a.radiance.iter().zip(&b.radiance).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z)
+}
+
+#[test]
+fn photons_are_found_within_the_radius() {
+    seed_random(11);
+    let photons: Vec<Photon> = (0..2000)
+        .map(|_| Photon::new(Vec3::random(-1., 1.), Vec3::random_unit_vector(), Vec3::one()))
+        .collect();
+    let map = PhotonMap::new(photons.clone(), 0.2);
+    assert_eq!(map.len(), photons.len());
+
+    for _ in 0..100 {
+        let point = Vec3::random(-1.2, 1.2);
+        let mut expected: Vec<f64> = photons.iter()
+            .filter(|photon| (photon.point - point).length() <= 0.2)
+            .map(|photon| photon.point.x).collect();
+        let mut found: Vec<f64> = map.within(&point).iter().map(|photon| photon.point.x).collect();
+        expected.sort_by(f64::total_cmp);
+        found.sort_by(f64::total_cmp);
+        assert_eq!(expected, found);
+    }
+}
+
+#[test]
+fn scenes_without_caustics_match_the_path_tracer() {
+    // Photons are only stored after a specular bounce, and no path reaches a light through one
+    seed_random(31);
+    let scene = build_scene("cornell-box", 1., BVHBuildMethod::SAH).unwrap();
+    assert!(PhotonMap::emit(&scene, &PhotonSettings::default(), 31, None).is_empty());
+
+    let path = render(&scene, &settings(8, 4, Integrator::Path), &ProgressBar::hidden());
+    let photons = render(&scene, &settings(8, 4, photon_mapping()), &ProgressBar::hidden());
+    assert!(identical(&path, &photons));
+}
+
+#[test]
+fn photon_mapping_resolves_caustics() {
+    let scene = load_scene(Path::new("scenes/caustics.toml"), 1., BVHBuildMethod::SAH).unwrap();
+    let render_with = |samples_per_pixel, integrator| {
+        render(&scene, &settings(24, samples_per_pixel, integrator), &ProgressBar::hidden())
+    };
+    let reference = render_with(256, Integrator::Bidirectional);
+    let path = render_with(16, Integrator::Path);
+    let photons = render_with(16, photon_mapping());
+
+    // The caustic on the floor is only found by chance without photons
+    let photons = floor_rmse(&photons, &reference);
+    let path = floor_rmse(&path, &reference);
+    assert!(photons < 0.5 * path, "{photons} is not much less noisy than {path}");
+}
+
+#[test]
+fn photon_mapping_renders_are_reproducible() {
+    let scene = load_scene(Path::new("scenes/caustics.toml"), 1., BVHBuildMethod::SAH).unwrap();
+    let mut settings = settings(10, 4, photon_mapping());
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap()
+        .install(|| render(&scene, &settings, &ProgressBar::hidden()));
+
+    // Emits the photons again for each call, with a different number of threads
+    settings.samples_per_pixel = 2;
+    let mut buffer = RenderBuffer::new(settings.image_width, settings.image_height);
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+    settings.samples_per_pixel = 4;
+    rayon::ThreadPoolBuilder::new().num_threads(3).build().unwrap()
+        .install(|| render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {}));
+
+    assert!(identical(&single, &buffer));
+}
+
+#[test]
+fn photon_paths_are_bounded_by_the_maximum_depth() {
+    // Photons refracted through the sphere take three hits to land, and reflected ones two
+    let scene = load_scene(Path::new("scenes/caustics.toml"), 1., BVHBuildMethod::SAH).unwrap();
+    let photons = PhotonSettings { photons: 20_000, radius: None };
+    let emit = |max_depth| PhotonMap::emit(&scene, &photons, 31, max_depth).len();
+
+    // Camera paths take a hit to reach a photon, which then needs a specular bounce to land
+    assert_eq!(emit(Some(2)), 0);
+    let reflected = emit(Some(3));
+    let refracted = emit(Some(4));
+    assert!(reflected > 0 && refracted > 2 * reflected, "{reflected} reflected and {refracted} refracted photons");
+    assert_eq!(emit(None), emit(Some(33)));
+}
+
+#[test]
+fn photons_are_emitted_while_the_shutter_is_open() {
+    // A glass ball passes under the light only while the first camera's shutter is open, and
+    // is far away while the second's is
+    let light = Arc::new(DiffuseLight::new(Arc::new(SolidColour::new(Vec3::new(10., 10., 10.)))));
+    let scene = |min_time, max_time| {
+        let mut world = HittableList::new();
+        world.add(XZRect::new(-0.5, 0.5, -0.5, 0.5, 4., Arc::clone(&light)));
+        world.add(XZRect::new(-5., 5., -5., 5., 0., Arc::new(Lambertian::new(Vec3::new(0.5, 0.5, 0.5)))));
+        world.add(MovingSphere::new(
+            Vec3::new(0., 1.5, 0.), Vec3::new(0., 1.5, -0.5), 100., 101., 1., Arc::new(Dielectric::new(1.5))
+        ));
+        let camera = Camera::new(
+            Vec3::new(0., 2., 10.), Vec3::zero(), Vec3::new(0., 1., 0.), 40., 0., 10., 1., 2., min_time, max_time
+        );
+        Scene::new(world, camera, Vec3::zero())
+    };
+
+    let photons = PhotonSettings { photons: 20_000, radius: None };
+    let open = PhotonMap::emit(&scene(100., 101.), &photons, 31, None).len();
+    let closed = PhotonMap::emit(&scene(0., 1.), &photons, 31, None).len();
+    assert!(open > 10 * closed.max(100), "{open} photons with the ball nearby and {closed} without");
+}
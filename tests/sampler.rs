@@ -0,0 +1,150 @@
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::sampler::{end_sample, start_sample, start_vertex};
+use ray_tracing::scenes::build_scene;
+
+/// Settings for a small render with a given sampler
+fn settings(size: usize, samples_per_pixel: i32, sampler: Sampler) -> RenderSettings {
+    RenderSettings {
+        image_width: size,
+        image_height: size,
+        samples_per_pixel,
+        max_depth: Some(8),
+        seed: 31,
+        tile_size: 8,
+        sampler,
+        ..Default::default()
+    }
+}
+
+/// Root mean square difference between the radiance of two renders
+fn rmse(a: &RenderBuffer, b: &RenderBuffer) -> f64 {
+    let sum: f64 = (0..a.radiance.len()).map(|i| (a.pixel(i) - b.pixel(i)).length_squared()).sum();
+    (sum / (3 * a.radiance.len()) as f64).sqrt()
+}
+
+/// Mean of the root mean square errors of renders with a few different seeds against a reference
+fn mean_rmse(scene: &Scene, samples_per_pixel: i32, sampler: Sampler, reference: &RenderBuffer) -> f64 {
+    let seeds = [101, 102, 103];
+    seeds.iter().map(|&seed| {
+        let settings = RenderSettings { seed, ..settings(12, samples_per_pixel, sampler) };
+        rmse(&render(scene, &settings, &ProgressBar::hidden()), reference)
+    }).sum::<f64>() / seeds.len() as f64
+}
+
+/// Draws every number of the current sample within a range of dimensions
+fn draw(count: usize) -> Vec<f64> {
+    (0..count).map(|_| random_double()).collect()
+}
+
+#[test]
+fn low_discrepancy_samplers_converge_faster() {
+    for name in ["cornell-box", "simple-light"] {
+        seed_random(31);
+        let scene = build_scene(name, 1., BVHBuildMethod::SAH).unwrap();
+        let reference = render(&scene, &settings(12, 1024, Sampler::Independent), &ProgressBar::hidden());
+        let independent = mean_rmse(&scene, 16, Sampler::Independent, &reference);
+        for sampler in [Sampler::Stratified, Sampler::Halton, Sampler::Sobol] {
+            let error = mean_rmse(&scene, 16, sampler, &reference);
+            assert!(error < 0.85 * independent, "{name}: {sampler:?} has error {error}, against {independent}");
+        }
+    }
+}
+
+#[test]
+fn sobol_points_fill_every_elementary_interval() {
+    // The first 16 samples of a pixel have one point in every interval of area 1/16 whose
+    // sides are powers of two, in the camera's dimensions and those of later bounces
+    for (key, pair) in [(3, 0), (3, 3), (17, 0), (17, 7)] {
+        let points: Vec<(f64, f64)> = (0..16).map(|i| (
+            Sampler::Sobol.sample(key, i, 16, 2 * pair).unwrap(),
+            Sampler::Sobol.sample(key, i, 16, 2 * pair + 1).unwrap(),
+        )).collect();
+        for columns in [1, 2, 4, 8, 16] {
+            let rows = 16 / columns;
+            let mut cells = vec![0; 16];
+            for (x, y) in &points {
+                cells[(x * columns as f64) as usize * rows + (y * rows as f64) as usize] += 1;
+            }
+            assert!(cells.iter().all(|&count| count == 1), "{columns} by {rows} intervals of pair {pair}: {cells:?}");
+        }
+    }
+}
+
+#[test]
+fn stratified_and_halton_numbers_fill_every_stratum() {
+    let strata = |sampler: Sampler, samples: u32, dimension: u32| {
+        let mut strata: Vec<usize> = (0..samples)
+            .map(|i| (sampler.sample(7, i, samples, dimension).unwrap() * samples as f64) as usize)
+            .collect();
+        strata.sort();
+        strata
+    };
+    for dimension in 0..20 {
+        assert_eq!(strata(Sampler::Stratified, 10, dimension), (0..10).collect::<Vec<_>>());
+    }
+
+    // Halton numbers fill each stratum of a power of their dimension's base
+    assert_eq!(strata(Sampler::Halton, 8, 0), (0..8).collect::<Vec<_>>());
+    assert_eq!(strata(Sampler::Halton, 9, 1), (0..9).collect::<Vec<_>>());
+    assert_eq!(strata(Sampler::Halton, 17, 6), (0..17).collect::<Vec<_>>());
+
+    // The independent sampler leaves every number to the sample's stream
+    assert_eq!(Sampler::Independent.sample(7, 0, 1, 0), None);
+}
+
+#[test]
+fn bounces_draw_from_fixed_dimensions() {
+    for sampler in [Sampler::Stratified, Sampler::Halton, Sampler::Sobol] {
+        // However many numbers the first bounce uses, the second starts at the same dimension
+        seed_random(1);
+        start_sample(sampler, 31, 5, 2, 4);
+        let camera = draw(CAMERA_DIMENSIONS as usize);
+        start_vertex(0);
+        draw(3);
+        start_vertex(1);
+        let second = draw(VERTEX_DIMENSIONS as usize + 1);
+
+        seed_random(2);
+        start_sample(sampler, 31, 5, 2, 4);
+        assert_eq!(camera, draw(CAMERA_DIMENSIONS as usize));
+        start_vertex(0);
+        draw(VERTEX_DIMENSIONS as usize + 5);
+        start_vertex(1);
+        let again = draw(VERTEX_DIMENSIONS as usize + 1);
+
+        // Numbers beyond a bounce's dimensions come from the stream instead
+        let fixed = VERTEX_DIMENSIONS as usize;
+        assert_eq!(second[..fixed], again[..fixed]);
+        assert_ne!(second[fixed], again[fixed]);
+        end_sample();
+    }
+
+    // Independent samplers don't change the stream
+    seed_random(3);
+    let stream = draw(4);
+    seed_random(3);
+    start_sample(Sampler::Independent, 31, 5, 2, 4);
+    assert_eq!(stream, draw(4));
+}
+
+#[test]
+fn low_discrepancy_renders_are_reproducible() {
+    seed_random(31);
+    let scene = build_scene("cornell-box-smoke", 1., BVHBuildMethod::SAH).unwrap();
+    let mut settings = settings(10, 4, Sampler::Sobol);
+    let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap()
+        .install(|| render(&scene, &settings, &ProgressBar::hidden()));
+
+    // Renders two samples per pixel first, then continues to four with a different tile size
+    settings.samples_per_pixel = 2;
+    settings.tile_size = 3;
+    let mut buffer = RenderBuffer::new(settings.image_width, settings.image_height);
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+    settings.samples_per_pixel = 4;
+    settings.tile_size = 5;
+    render_progressive(&scene, &settings, &mut buffer, &ProgressBar::hidden(), |_| {});
+
+    assert_eq!(single.samples, buffer.samples);
+    assert!(single.radiance.iter().zip(&buffer.radiance).all(|(a, b)| a.x == b.x && a.y == b.y && a.z == b.z));
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::Path;
+use ray_tracing::*;
+use ray_tracing::scene_file::{build_scene_description, SceneDescription};
+
+/// Camera table shared by the scenes below, looking down the z axis at the origin
+const CAMERA: &str = "
+[camera]
+look_from = [0.0, 0.0, 5.0]
+look_at = [0.0, 0.0, 0.0]
+vertical_fov = 40.0
+";
+
+/// Parses and builds a scene from TOML source
+fn build(source: &str) -> Result<Scene, String> {
+    let description: SceneDescription = toml::from_str(source).map_err(|e| e.to_string())?;
+    build_scene_description(&description, Path::new("scenes"), 1., BVHBuildMethod::SAH)
+}
+
+/// Hits the scene along a ray from the camera towards a point
+fn hit_towards(scene: &Scene, point: Vec3) -> HitRecord {
+    let origin = Vec3::new(0., 0., 5.);
+    scene.world.hit(&Ray::new(origin, point - origin, 0.), 0.001, INFINITY).unwrap()
+}
+
+/// Gets the albedo a diffuse hit scatters light with
+fn albedo(hit: &HitRecord) -> Vec3 {
+    match hit.material.scatter(&Ray::new(Vec3::zero(), -hit.normal, 0.), hit) {
+        Some(ScatterRecord::Diffuse(Lobe::Cosine { albedo, .. })) => albedo,
+        _ => panic!("hit isn't diffuse"),
+    }
+}
+
+#[test]
+fn every_example_scene_loads() {
+    let mut count = 0;
+    for entry in fs::read_dir("scenes").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|extension| extension == "toml") {
+            let scene = load_scene(&path, 1., BVHBuildMethod::SAH);
+            assert!(scene.is_ok(), "{}: {}", path.display(), scene.err().unwrap());
+            count += 1;
+        }
+    }
+    assert!(count >= 10);
+}
+
+#[test]
+fn named_materials_and_textures_are_shared() {
+    let scene = build(&format!(r#"
+        {CAMERA}
+        [textures.red]
+        type = "solid_colour"
+        colour = [0.8, 0.1, 0.1]
+
+        [textures.stripes]
+        type = "checker"
+        even = "red"
+        odd = [0.1, 0.1, 0.8]
+
+        [materials.painted]
+        type = "lambertian"
+        albedo = "red"
+
+        [[objects]]
+        type = "sphere"
+        centre = [-1.5, 0.0, 0.0]
+        radius = 0.5
+        material = "painted"
+
+        [[objects]]
+        type = "sphere"
+        centre = [1.5, 0.0, 0.0]
+        radius = 0.5
+        material = "painted"
+
+        [[objects]]
+        type = "sphere"
+        centre = [0.0, 1.5, 0.0]
+        radius = 0.5
+        material = {{ type = "lambertian", albedo = [0.2, 0.7, 0.2] }}
+    "#)).unwrap();
+
+    // Objects naming the same material share it, while inline materials are their own
+    let left = hit_towards(&scene, Vec3::new(-1.5, 0., 0.));
+    let right = hit_towards(&scene, Vec3::new(1.5, 0., 0.));
+    let top = hit_towards(&scene, Vec3::new(0., 1.5, 0.));
+    assert!(std::sync::Arc::ptr_eq(&left.material, &right.material));
+    assert_eq!(scene.material_id(&left.material), scene.material_id(&right.material));
+    assert_ne!(scene.material_id(&left.material), scene.material_id(&top.material));
+
+    // Materials see the colours of the textures they name, or of plain colours given inline
+    assert!((albedo(&left) - Vec3::new(0.8, 0.1, 0.1)).near_zero());
+    assert!((albedo(&top) - Vec3::new(0.2, 0.7, 0.2)).near_zero());
+}
+
+#[test]
+fn unknown_references_are_reported() {
+    let error = build(&format!(r#"
+        {CAMERA}
+        [materials.painted]
+        type = "lambertian"
+        albedo = "missing"
+    "#)).err().unwrap();
+    assert!(error.contains("material 'painted'") && error.contains("unknown texture 'missing'"), "{error}");
+
+    let error = build(&format!(r#"
+        {CAMERA}
+        [[objects]]
+        type = "sphere"
+        centre = [0.0, 0.0, 0.0]
+        radius = 1.0
+        material = "missing"
+    "#)).err().unwrap();
+    assert!(error.contains("object 1") && error.contains("unknown material 'missing'"), "{error}");
+
+    // Textures referring to each other in a loop can never be built
+    let error = build(&format!(r#"
+        {CAMERA}
+        [textures.a]
+        type = "checker"
+        even = "b"
+        odd = [0.0, 0.0, 0.0]
+
+        [textures.b]
+        type = "checker"
+        even = "a"
+        odd = [0.0, 0.0, 0.0]
+    "#)).err().unwrap();
+    assert!(error.contains("texture 'a'"), "{error}");
+
+    // Images which can't be loaded are reported along with their path
+    let error = build(&format!(r#"
+        {CAMERA}
+        [textures.map]
+        type = "image"
+        path = "missing.jpg"
+    "#)).err().unwrap();
+    assert!(error.contains("texture 'map'") && error.contains("missing.jpg"), "{error}");
+
+    // Misspelt fields are rejected rather than ignored
+    assert!(build(&format!("{CAMERA}\nbackgroud = [1.0, 1.0, 1.0]")).is_err());
+}
+
+#[test]
+fn errors_name_materials_in_a_consistent_order() {
+    // Of several broken materials, the first by name is always the one reported
+    let source = format!(r#"
+        {CAMERA}
+        [materials.zinc]
+        type = "lambertian"
+        albedo = "missing"
+
+        [materials.brass]
+        type = "lambertian"
+        albedo = "missing"
+
+        [materials.iron]
+        type = "lambertian"
+        albedo = "missing"
+    "#);
+    for _ in 0..8 {
+        let error = build(&source).err().unwrap();
+        assert!(error.contains("material 'brass'"), "{error}");
+    }
+}
+
+#[test]
+fn backgrounds_are_colours_or_environment_maps() {
+    // Scenes are black behind everything unless given a background
+    let scene = build(CAMERA).unwrap();
+    assert!(matches!(scene.background, Background::Colour(colour) if colour.near_zero()));
+
+    let scene = build(&format!("background = [0.7, 0.8, 1.0]\n{CAMERA}")).unwrap();
+    let colour = scene.background.value(&Vec3::new(0., 1., 0.));
+    assert!((colour - Vec3::new(0.7, 0.8, 1.)).near_zero());
+    assert!(scene.lights.objects.is_empty());
+
+    // Environment maps are loaded relative to the scene and sampled as lights
+    let scene = build(&format!("background = {{ environment = \"sky.hdr\" }}\n{CAMERA}")).unwrap();
+    assert!(matches!(scene.background, Background::Environment(_)));
+    assert_eq!(scene.lights.objects.len(), 1);
+
+    let error = build(&format!("background = {{ environment = \"missing.hdr\" }}\n{CAMERA}")).err().unwrap();
+    assert!(error.contains("missing.hdr"), "{error}");
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+use std::sync::Arc;
+use indicatif::ProgressBar;
+use ray_tracing::*;
+use ray_tracing::scenes::build_scene;
+
+/// Sellmeier coefficients of Schott BK7 glass
+const BK7: RefractionIndex = RefractionIndex::Sellmeier {
+    b: [1.03961212, 0.231792344, 1.01046945],
+    c: [0.00600069867, 0.0200179144, 103.560653],
+};
+
+/// Settings for a small render with a given integrator
+fn settings(size: usize, samples_per_pixel: i32, integrator: Integrator) -> RenderSettings {
+    RenderSettings {
+        image_width: size,
+        image_height: size,
+        samples_per_pixel,
+        max_depth: Some(8),
+        seed: 29,
+        tile_size: 8,
+        integrator,
+        ..Default::default()
+    }
+}
+
+/// Mean radiance of each channel over every pixel of a render
+fn mean(buffer: &RenderBuffer) -> Vec3 {
+    let sum = (0..buffer.radiance.len()).fold(Vec3::zero(), |sum, i| sum + buffer.pixel(i));
+    sum / buffer.radiance.len() as f64
+}
+
+#[test]
+fn colours_survive_the_round_trip_through_spectra() {
+    // Many sets of wavelengths average out to the colour each spectrum was upsampled from
+    seed_random(29);
+    let colours = [Vec3::new(0.65, 0.05, 0.05), Vec3::new(0.12, 0.45, 0.15), Vec3::new(0.2, 0.3, 0.9), Vec3::new(4., 4., 4.)];
+    let mut sums = [Vec3::zero(); 4];
+    let trials = 100_000;
+    for _ in 0..trials {
+        let wavelengths = SampledWavelengths::sample();
+        for (sum, colour) in sums.iter_mut().zip(colours) {
+            *sum += wavelengths.to_rgb(wavelengths.upsample(colour));
+        }
+    }
+    for (sum, colour) in sums.iter().zip(colours) {
+        let average = *sum / trials as f64;
+        assert!((average - colour).length() < 0.01 * colour.length(), "{average:?} differs from {colour:?}");
+    }
+
+    // Greys are flat spectra
+    for wavelength in [400., 500., 600., 700.] {
+        assert!((rgb_to_spectrum(Vec3::new(0.5, 0.5, 0.5), wavelength) - 0.5).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn flat_spectra_are_white() {
+    // The colour matching functions summed over the spectrum convert to white
+    let xyz = (0..4700).fold(Vec3::zero(), |sum, i| sum + cie_xyz(360. + 0.1 * i as f64));
+    let rgb = xyz_to_rgb(xyz / xyz.y);
+    assert!((rgb - Vec3::one()).length() < 1e-3, "{rgb:?} isn't white");
+
+    // Single wavelengths have the expected hues
+    let red = xyz_to_rgb(cie_xyz(650.));
+    let blue = xyz_to_rgb(cie_xyz(450.));
+    assert!(red.x > red.y && red.x > red.z);
+    assert!(blue.z > blue.x && blue.z > blue.y);
+}
+
+#[test]
+fn refraction_indices_vary_with_wavelength() {
+    // BK7 is quoted as 1.5168 at the helium d line, and bends blue light more than red
+    assert!((BK7.at(587.6) - 1.5168).abs() < 1e-4);
+    assert!(BK7.at(450.) > BK7.at(650.));
+    assert!(BK7.is_dispersive());
+
+    let cauchy = RefractionIndex::Cauchy { a: 1.5046, b: 0.0042 };
+    assert!((cauchy.at(500.) - (1.5046 + 0.0042 / 0.25)).abs() < 1e-12);
+    assert!(!RefractionIndex::Cauchy { a: 1.5, b: 0. }.is_dispersive());
+    assert!(!RefractionIndex::Constant(1.5).is_dispersive());
+    assert_eq!(RefractionIndex::Constant(1.5).at(400.), 1.5);
+}
+
+#[test]
+fn dispersive_glass_splits_light_by_wavelength() {
+    let glass = Arc::new(Dielectric::from_index(BK7));
+    assert!(glass.is_dispersive());
+    assert!(!Dielectric::new(1.5).is_dispersive());
+
+    // A ray entering a block of glass at an angle is bent further the shorter its wavelength
+    let block = XZRect::new(-10., 10., -10., 10., 0., Arc::clone(&glass));
+    let ray = Ray::new(Vec3::new(-1., 1., 0.), Vec3::new(1., -1., 0.), 0.);
+    let hit = block.hit(&ray, 0.001, INFINITY).unwrap();
+    let refracted = |wavelength| loop {
+        // Rays are sometimes reflected instead, so refraction is waited for
+        if let Some(ScatterRecord::Specular { ray, .. }) = glass.scatter(&ray.with_wavelength(Some(wavelength)), &hit) {
+            if ray.direction.y < 0. {
+                return ray.direction.unit();
+            }
+        }
+    };
+    seed_random(29);
+    let (blue, red) = (refracted(450.), refracted(650.));
+    assert!(blue.x < red.x, "{blue:?} isn't bent more than {red:?}");
+
+    // Rays carrying no wavelength are bent by the index at the d line
+    let plain = loop {
+        if let Some(ScatterRecord::Specular { ray: scattered, .. }) = glass.scatter(&ray, &hit) {
+            if scattered.direction.y < 0. {
+                break scattered.direction.unit();
+            }
+        }
+    };
+    assert!((plain - refracted(587.6)).length() < 1e-12);
+}
+
+#[test]
+fn spectral_renders_match_rgb_renders() {
+    for name in ["cornell-box", "two-spheres"] {
+        seed_random(29);
+        let scene = build_scene(name, 1., BVHBuildMethod::SAH).unwrap();
+        let rgb = mean(&render(&scene, &settings(12, 256, Integrator::Path), &ProgressBar::hidden()));
+        let spectral = mean(&render(&scene, &settings(12, 256, Integrator::Spectral), &ProgressBar::hidden()));
+        for channel in 0..3 {
+            assert!(
+                (rgb[channel] - spectral[channel]).abs() < 0.05 * rgb[channel],
+                "{name}: {spectral:?} differs from {rgb:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn dispersive_scenes_load_and_render() {
+    let scene = load_scene(Path::new("scenes/dispersion.toml"), 1., BVHBuildMethod::SAH).unwrap();
+    let buffer = render(&scene, &settings(8, 4, Integrator::Spectral), &ProgressBar::hidden());
+    assert!((0..buffer.radiance.len()).all(|i| buffer.pixel(i).x.is_finite()));
+    assert!(mean(&buffer).length() > 0.);
+}